@@ -0,0 +1,225 @@
+//! `--output-dir`: one result per target and channel, laid out as
+//! `<dir>/<target>/<channel>/result.json` next to the overall result and an
+//! `index.json` manifest.
+
+use crate::channels::ChannelTally;
+use crate::content::{self, BlankCounts};
+use crate::message::Message;
+use crate::paths;
+use crate::pipeline::Pipeline;
+use crate::warnings::Warnings;
+use crate::{
+    analyze_authors, annotate_words, build_result, compare_authors, write_output, AnalysisResult,
+    Args,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// Directory for messages whose export has no channel information.
+const NO_CHANNEL: &str = "no-channel";
+
+/// The analysis of one target's messages in one channel.
+#[derive(Debug)]
+pub struct BatchResult {
+    pub target: String,
+    pub channel_id: Option<String>,
+    pub channel_name: Option<String>,
+    pub result: AnalysisResult,
+}
+
+/// Splits the matched messages by labeled target (or the whole target when
+/// none is labeled) and channel, and analyzes each part on its own.
+pub fn build(args: &Args, pipeline: &Pipeline, msgs: &[Message]) -> Vec<BatchResult> {
+    let groups: Vec<(String, Vec<&Message>)> = if pipeline.target.labeled().next().is_some() {
+        pipeline
+            .target
+            .labeled()
+            .map(|group| {
+                let label = group.label.clone().unwrap_or_default();
+                (
+                    label,
+                    msgs.iter().filter(|msg| group.matches(msg)).collect(),
+                )
+            })
+            .collect()
+    } else {
+        vec![(pipeline.target.label(), msgs.iter().collect())]
+    };
+
+    let mut batch = Vec::new();
+    for (target, msgs) in groups {
+        let mut channels: BTreeMap<Option<&String>, Vec<Message>> = BTreeMap::new();
+        for msg in msgs {
+            let key = msg.channel_id.as_ref().or(msg.channel_name.as_ref());
+            channels.entry(key).or_default().push(msg.clone());
+        }
+        for msgs in channels.into_values() {
+            let mut blank = BlankCounts::default();
+            let mut channels = ChannelTally::default();
+            for msg in &msgs {
+                if let Some(kind) = content::classify_blank(&msg.content) {
+                    blank.add(kind);
+                }
+                channels.record(msg);
+            }
+            let channel_id = msgs[0].channel_id.clone();
+            let channel_name = msgs.iter().find_map(|msg| msg.channel_name.clone());
+            let mut result = build_result(
+                analyze_authors(args, pipeline, msgs),
+                blank,
+                Vec::new(),
+                0,
+                Warnings::default(),
+            );
+            result.channels = channels.into_vec();
+            annotate_words(args, &mut result);
+            compare_authors(args, &mut result);
+            pipeline.annotate(&mut result);
+            batch.push(BatchResult {
+                target: target.clone(),
+                channel_id,
+                channel_name,
+                result,
+            });
+        }
+    }
+    batch
+}
+
+#[derive(Serialize, Debug)]
+struct IndexEntry {
+    target: String,
+    channel_id: Option<String>,
+    channel_name: Option<String>,
+    /// Relative to the output directory.
+    path: String,
+    total_messages: usize,
+    unique_authors: usize,
+}
+
+#[derive(Serialize, Debug)]
+struct Index {
+    /// The overall result across every target and channel.
+    result: String,
+    results: Vec<IndexEntry>,
+}
+
+/// Writes the overall result, every batch result and the manifest. The
+/// batch results are sanitized and size-checked like the overall one.
+pub fn write(dir: &Path, result: &mut AnalysisResult, pipeline: &Pipeline) -> anyhow::Result<()> {
+    let root = paths::extended(dir);
+    fs::create_dir_all(&root)?;
+    write_output(result, &root.join("result.json"), &pipeline.output)?;
+
+    let mut used = HashSet::new();
+    let mut results = Vec::new();
+    for mut entry in std::mem::take(&mut result.batch) {
+        // Names end up in the directory layout and the index, so they are
+        // masked like the results.
+        entry.target = pipeline.sanitizer.mask_text(&entry.target);
+        entry.channel_name = entry
+            .channel_name
+            .map(|name| pipeline.sanitizer.mask_text(&name));
+        let channel = entry
+            .channel_name
+            .as_deref()
+            .or(entry.channel_id.as_deref())
+            .map_or_else(|| NO_CHANNEL.to_string(), slug);
+        let base = format!("{}/{}", slug(&entry.target), channel);
+        let mut rel = base.clone();
+        let mut n = 2;
+        while !used.insert(rel.clone()) {
+            rel = format!("{}-{}", base, n);
+            n += 1;
+        }
+        fs::create_dir_all(paths::extended(&root.join(&rel)))?;
+        let rel = format!("{}/result.json", rel);
+
+        pipeline.sanitizer.apply(&mut entry.result);
+        write_output(&mut entry.result, &root.join(&rel), &pipeline.output)?;
+        results.push(IndexEntry {
+            target: entry.target,
+            channel_id: entry.channel_id,
+            channel_name: entry.channel_name,
+            path: rel,
+            total_messages: entry.result.total_messages,
+            unique_authors: entry.result.unique_authors,
+        });
+    }
+
+    let index = Index {
+        result: "result.json".to_string(),
+        results,
+    };
+    fs::write(
+        root.join("index.json"),
+        serde_json::to_string_pretty(&index)?,
+    )?;
+    Ok(())
+}
+
+/// A directory name: lowercase letters, digits and dashes.
+fn slug(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.trim().trim_start_matches('#').chars() {
+        if c.is_alphanumeric() {
+            out.extend(c.to_lowercase());
+        } else if !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    let out = out.trim_matches('-');
+    if out.is_empty() {
+        "unnamed".to_string()
+    } else {
+        out.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(slug("#General Chat"), "general-chat");
+        assert_eq!(slug("deleted users"), "deleted-users");
+        assert_eq!(slug("Ünïcode/../ok"), "ünïcode-ok");
+        assert_eq!(slug("***"), "unnamed");
+    }
+
+    #[test]
+    fn test_batch_results_get_the_word_statistics() {
+        let args = Args::try_parse_from([
+            "deleted-user-analyzer",
+            "-i",
+            "a.json",
+            "--output-dir",
+            "out",
+            "--hapax",
+            "--rank-frequency",
+        ])
+        .unwrap();
+        let pipeline = Pipeline::from_args(&args).unwrap();
+        let msg = |channel: &str, content: &str| Message {
+            author_id: "1".to_string(),
+            content: content.to_string(),
+            channel_id: Some(channel.to_string()),
+            mentioned_user_name: Some("Deleted User".to_string()),
+            ..Message::default()
+        };
+        let batch = build(
+            &args,
+            &pipeline,
+            &[msg("10", "where refund where"), msg("20", "miss you")],
+        );
+        assert_eq!(batch.len(), 2);
+        let result = &batch[0].result;
+        assert_eq!(result.hapax_legomena, ["refund"]);
+        assert!(result.rank_frequency.is_some());
+        assert_eq!(result.channels[0].messages, 1);
+    }
+}
@@ -0,0 +1,582 @@
+//! Bounded-memory analysis for exports larger than RAM.
+//!
+//! Messages are streamed one at a time instead of being loaded up front.
+//! Deduplication keeps only a 64-bit content hash per unique message, and
+//! per-author word counts are spilled to temporary bucket files whenever the
+//! estimated footprint exceeds the budget. Buckets are merged one at a time at
+//! the end, so the merge never holds more than one bucket's spilled counts.
+
+use crate::channels::ChannelTally;
+use crate::concordance::{self, ConcordanceLine};
+use crate::conflicts::ConflictTracker;
+use crate::content::{self, BlankCounts, CustomEmojiTally};
+use crate::events::EventTally;
+use crate::filterstats::FilterTally;
+use crate::fuzzy;
+use crate::hapax;
+use crate::input::{self, Loader};
+use crate::keyness;
+use crate::lifecycle;
+use crate::links::AuthorLinks;
+use crate::message::FallbackStats;
+use crate::optout::OptOutTally;
+use crate::pings::TargetIds;
+use crate::pipeline::Pipeline;
+use crate::radar::{self, RadarInputs, RadarTally};
+use crate::readability::ReadabilityTally;
+use crate::references::{self, ReferenceTally};
+use crate::report;
+use crate::sampling::SamplingInfo;
+use crate::sentiment::SentimentTally;
+use crate::stem::Variants;
+use crate::tfidf;
+use crate::toxicity::FlagTally;
+use crate::vocab::VocabCap;
+use crate::warnings::Warnings;
+use crate::zipf;
+use crate::{
+    attach_sampling, build_author_analysis, build_result, language_code, repair_timestamps,
+    AnalysisResult, Args, AuthorAnalysis, MessageCounts,
+};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+const SPILL_BUCKETS: usize = 16;
+
+/// Rough per-entry cost of a hash map slot plus the owned `String` header.
+const ENTRY_OVERHEAD: usize = 64;
+
+/// Cost of one remembered content hash in an author's dedup set.
+const HASH_ENTRY_BYTES: usize = 16;
+
+struct AuthorAcc {
+    author_name: String,
+    author_nickname: String,
+    counts: MessageCounts,
+    first_seen: Option<DateTime<Utc>>,
+    last_seen: Option<DateTime<Utc>>,
+    seen: HashSet<u64>,
+    words: HashMap<String, usize>,
+    /// Words evicted by `--max-author-vocab` so far.
+    evicted: usize,
+    /// Few distinct emoji exist, so these are never spilled.
+    emoji: BTreeMap<String, usize>,
+    custom_emoji: CustomEmojiTally,
+    languages: BTreeMap<String, usize>,
+    links: AuthorLinks,
+    references: ReferenceTally,
+    concordance: Vec<ConcordanceLine>,
+    sentiment: SentimentTally,
+    flags: FlagTally,
+    readability: ReadabilityTally,
+    /// Unix milliseconds of every timed message, for the radar profile and
+    /// lifecycle.
+    times: Vec<i64>,
+    radar: RadarTally,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SpillRecord {
+    author_id: String,
+    words: HashMap<String, usize>,
+}
+
+/// One author's merged spilled counts.
+#[derive(Default)]
+struct Spilled {
+    words: HashMap<String, usize>,
+    evicted: usize,
+}
+
+/// Temporary spill directory with one append-only file per bucket. The
+/// directory is removed when the value is dropped.
+struct Spill {
+    dir: PathBuf,
+    writers: Vec<BufWriter<File>>,
+    spills: usize,
+}
+
+impl Spill {
+    fn create() -> anyhow::Result<Self> {
+        let dir = std::env::temp_dir().join(format!(
+            "deleted-user-analyzer-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_millis()
+        ));
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create spill directory {}", dir.display()))?;
+        let writers = (0..SPILL_BUCKETS)
+            .map(|bucket| {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(dir.join(format!("bucket-{}.jsonl", bucket)))?;
+                Ok(BufWriter::new(file))
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        Ok(Spill {
+            dir,
+            writers,
+            spills: 0,
+        })
+    }
+
+    fn write(&mut self, authors: &mut HashMap<String, AuthorAcc>) -> anyhow::Result<()> {
+        for (author_id, acc) in authors.iter_mut() {
+            if acc.words.is_empty() {
+                continue;
+            }
+            let record = SpillRecord {
+                author_id: author_id.clone(),
+                words: std::mem::take(&mut acc.words),
+            };
+            let writer = &mut self.writers[bucket_of(author_id)];
+            serde_json::to_writer(&mut *writer, &record)?;
+            writer.write_all(b"\n")?;
+        }
+        self.spills += 1;
+        Ok(())
+    }
+
+    /// Merges one bucket's spilled counts per author, keeping each author
+    /// within `cap` as records are merged.
+    fn read_bucket(
+        &mut self,
+        bucket: usize,
+        cap: Option<VocabCap>,
+    ) -> anyhow::Result<HashMap<String, Spilled>> {
+        self.writers[bucket].flush()?;
+        let path = self.dir.join(format!("bucket-{}.jsonl", bucket));
+        let mut merged: HashMap<String, Spilled> = HashMap::new();
+        for line in BufReader::new(File::open(&path)?).lines() {
+            let record: SpillRecord = serde_json::from_str(&line?)?;
+            let spilled = merged.entry(record.author_id).or_default();
+            for (word, count) in record.words {
+                *spilled.words.entry(word).or_insert(0) += count;
+            }
+            if let Some(cap) = cap {
+                spilled.evicted += cap.trim(&mut spilled.words);
+            }
+        }
+        Ok(merged)
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn bucket_of(author_id: &str) -> usize {
+    let mut hasher = DefaultHasher::new();
+    author_id.hash(&mut hasher);
+    (hasher.finish() % SPILL_BUCKETS as u64) as usize
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn analyze(
+    args: &Args,
+    pipeline: &Pipeline,
+    max_memory_mb: usize,
+) -> anyhow::Result<AnalysisResult> {
+    let budget = max_memory_mb.saturating_mul(1024 * 1024);
+    let mut loader = Loader::new(args.strict);
+    let mut fallbacks = FallbackStats::default();
+    let mut conflict_tracker = ConflictTracker::default();
+    let mut blank_messages = BlankCounts::default();
+    let mut authors: HashMap<String, AuthorAcc> = HashMap::new();
+    let mut spill: Option<Spill> = None;
+    let mut warnings = Warnings::default();
+
+    // Dedup state and author metadata stay resident; only word maps spill.
+    let mut resident_bytes = 0usize;
+    let mut word_bytes = 0usize;
+    let mut loaded = 0usize;
+    let mut matched = 0usize;
+
+    let mut sampled = 0usize;
+    let mut opted_out = OptOutTally::default();
+    let mut channels = ChannelTally::default();
+    let mut reply_only_matches = 0usize;
+    // Learned as messages arrive, so tags seen before their ID is resolved
+    // count as talk.
+    let mut target_ids = TargetIds::default();
+    let mut events = pipeline.events.tally();
+    let mut filter_stats = FilterTally::default();
+
+    for path in input::expand_inputs(&args.input)? {
+        let name = path.display().to_string();
+        let name = name.as_str();
+        loader.stream_raw_messages(&path, |raw| {
+            let mut msg = raw.into_message(&mut fallbacks);
+            loaded += 1;
+            if pipeline.sampler.is_some_and(|sampler| !sampler.keep(&msg)) {
+                return Ok(());
+            }
+            sampled += 1;
+            if pipeline.opted_out(&msg, &mut opted_out) {
+                return Ok(());
+            }
+            repair_timestamps(std::slice::from_mut(&mut msg), &mut warnings);
+            conflict_tracker.record_one(name, &msg);
+
+            let rejected_by = pipeline.filters.rejected_by(&msg);
+            filter_stats.record(rejected_by);
+            if rejected_by.is_some() {
+                return Ok(());
+            }
+            matched += 1;
+            channels.record(&msg);
+            if let Some(events) = &mut events {
+                events.record(&msg);
+            }
+            if pipeline.target.matched_by_reply_only(&msg) {
+                reply_only_matches += 1;
+            }
+
+            let acc = authors.entry(msg.author_id.clone()).or_insert_with(|| {
+                resident_bytes +=
+                    ENTRY_OVERHEAD + msg.author_name.len() + msg.author_nickname.len();
+                AuthorAcc {
+                    author_name: msg.author_name.clone(),
+                    author_nickname: msg.author_nickname.clone(),
+                    counts: MessageCounts::default(),
+                    first_seen: None,
+                    last_seen: None,
+                    seen: HashSet::new(),
+                    words: HashMap::new(),
+                    evicted: 0,
+                    emoji: BTreeMap::new(),
+                    custom_emoji: CustomEmojiTally::default(),
+                    languages: BTreeMap::new(),
+                    links: AuthorLinks::default(),
+                    references: ReferenceTally::default(),
+                    concordance: Vec::new(),
+                    sentiment: SentimentTally::default(),
+                    flags: FlagTally::default(),
+                    readability: ReadabilityTally::default(),
+                    times: Vec::new(),
+                    radar: RadarTally::default(),
+                }
+            });
+            acc.counts.total += 1;
+            target_ids.learn(&msg, &pipeline.target);
+            acc.counts.add_mentions(&msg, &target_ids);
+            acc.links.record(&msg.content);
+            acc.references.record(&msg.content);
+            if let Some(ts) = msg.parsed_timestamp() {
+                acc.first_seen = acc.first_seen.min(Some(ts)).or(Some(ts));
+                acc.last_seen = acc.last_seen.max(Some(ts));
+                acc.times.push(ts.timestamp_millis());
+                resident_bytes += std::mem::size_of::<i64>();
+            }
+            acc.radar.record(&msg);
+
+            if let Some(kind) = content::classify_blank(&msg.content) {
+                blank_messages.add(kind);
+                acc.counts.blank += 1;
+                return Ok(());
+            }
+            if !acc.seen.insert(content_hash(&msg.content)) {
+                return Ok(());
+            }
+            acc.counts.unique += 1;
+            resident_bytes += HASH_ENTRY_BYTES;
+
+            for emoji in content::emoji(&msg.content) {
+                *acc.emoji.entry(emoji).or_insert(0) += 1;
+            }
+            acc.custom_emoji.record(&msg.content);
+            if args.detect_languages {
+                *acc.languages
+                    .entry(language_code(&msg).to_string())
+                    .or_insert(0) += 1;
+            }
+            if let Some(kwic) = &pipeline.concordance {
+                acc.concordance.extend(kwic.lines(&msg));
+            }
+            acc.readability.record(&msg.content);
+            if args.sentiment {
+                acc.sentiment.record(&msg);
+            }
+            if let Some(flagger) = &pipeline.flagger {
+                acc.flags.record(&msg, flagger);
+            }
+            for word in pipeline.tokenizer.tokenize_message(&msg) {
+                match acc.words.get_mut(&word) {
+                    Some(count) => *count += 1,
+                    None => {
+                        word_bytes += ENTRY_OVERHEAD + word.len();
+                        acc.words.insert(word, 1);
+                    }
+                }
+            }
+            if let Some(cap) = pipeline.vocab_cap {
+                let evicted = cap.trim(&mut acc.words);
+                acc.evicted += evicted;
+                // Word lengths are not known here; only the entry overhead
+                // is credited back, which errs towards spilling early.
+                word_bytes = word_bytes.saturating_sub(evicted * ENTRY_OVERHEAD);
+            }
+
+            // Never spill less than a quarter of the budget at a time, so a
+            // dedup set that has outgrown the budget does not cause a spill
+            // for every message.
+            let word_budget = budget.saturating_sub(resident_bytes).max(budget / 4);
+            if word_bytes > word_budget {
+                if spill.is_none() {
+                    spill = Some(Spill::create()?);
+                }
+                if let Some(spill) = spill.as_mut() {
+                    spill.write(&mut authors)?;
+                }
+                word_bytes = 0;
+            }
+            Ok(())
+        })?;
+    }
+    if !loader.unknown_fields.is_empty() {
+        anyhow::bail!("{}", loader.unknown_fields);
+    }
+
+    if args.verbose {
+        println!("Loaded {} messages", loaded);
+        report::print_missing_fields(&fallbacks);
+        println!(
+            "Found {} messages mentioning {}",
+            matched,
+            pipeline.target.label()
+        );
+        println!("Found {} unique authors", authors.len());
+        if let Some(spill) = &spill {
+            println!(
+                "Spilled word counts to disk {} times to stay within {} MB",
+                spill.spills, max_memory_mb
+            );
+        }
+    }
+
+    let (mut analysis_data, radar_inputs): (Vec<AuthorAnalysis>, Vec<RadarInputs>) =
+        match spill.as_mut() {
+            None => authors
+                .into_iter()
+                .map(|(author_id, acc)| finish_author(pipeline, author_id, acc, Spilled::default()))
+                .unzip(),
+            Some(spill) => {
+                let mut buckets: Vec<Vec<(String, AuthorAcc)>> =
+                    (0..SPILL_BUCKETS).map(|_| Vec::new()).collect();
+                for (author_id, acc) in authors {
+                    buckets[bucket_of(&author_id)].push((author_id, acc));
+                }
+
+                let mut analysis_data = Vec::new();
+                for (bucket, bucket_authors) in buckets.into_iter().enumerate() {
+                    let mut spilled = spill.read_bucket(bucket, pipeline.vocab_cap)?;
+                    for (author_id, acc) in bucket_authors {
+                        let words = spilled.remove(&author_id).unwrap_or_default();
+                        analysis_data.push(finish_author(pipeline, author_id, acc, words));
+                    }
+                }
+                analysis_data.into_iter().unzip()
+            }
+        };
+    for (analysis, profile) in analysis_data
+        .iter_mut()
+        .zip(radar::normalize(&radar_inputs))
+    {
+        analysis.radar = Some(profile);
+    }
+
+    let mut word_variants = Variants::default();
+    if args.fuzzy_words {
+        fuzzy::merge(&mut analysis_data);
+        for author in &mut analysis_data {
+            word_variants.merge(std::mem::take(&mut author.word_variants));
+        }
+    }
+    tfidf::score(&mut analysis_data);
+    if let Some(measure) = args.keyness {
+        keyness::score(&mut analysis_data, measure);
+    }
+    warnings.record_load(&loader, &fallbacks);
+    let mut result = build_result(
+        analysis_data,
+        blank_messages,
+        conflict_tracker.conflicts(),
+        fallbacks.records,
+        warnings,
+    );
+    if let Some(sampler) = &pipeline.sampler {
+        attach_sampling(&mut result, SamplingInfo::new(sampler, loaded, sampled));
+    }
+    result.opted_out = opted_out.summary();
+    result.channels = channels.into_vec();
+    references::resolve_names(&mut result);
+    result.reply_only_matches = reply_only_matches;
+    result.events = events.map(EventTally::summary);
+    result.filter_stats = filter_stats.summary(&pipeline.active_filters());
+    result.word_variants = word_variants.into_map();
+    if args.hapax {
+        hapax::annotate(&mut result);
+    }
+    if args.rank_frequency {
+        result.rank_frequency = Some(zipf::rank_frequency(&result.global_word_frequency));
+    }
+    pipeline.annotate(&mut result);
+    Ok(result)
+}
+
+fn finish_author(
+    pipeline: &Pipeline,
+    author_id: String,
+    acc: AuthorAcc,
+    spilled: Spilled,
+) -> (AuthorAnalysis, RadarInputs) {
+    let Spilled {
+        mut words,
+        mut evicted,
+    } = spilled;
+    for (word, count) in acc.words {
+        *words.entry(word).or_insert(0) += count;
+    }
+    evicted += acc.evicted;
+    let mut times = acc.times;
+    times.sort_unstable();
+    let radar_inputs = acc.radar.inputs(&times);
+    let lifecycle = lifecycle::classify_times(&times, pipeline.timezone);
+    if let Some(cap) = pipeline.vocab_cap {
+        evicted += cap.evict(&mut words);
+    }
+    let mut analysis = build_author_analysis(
+        author_id,
+        acc.author_name,
+        acc.author_nickname,
+        acc.counts,
+        words,
+    );
+    analysis.evicted_words = evicted;
+    analysis.first_message_at = acc.first_seen;
+    analysis.last_message_at = acc.last_seen;
+    analysis.lifecycle = lifecycle;
+    analysis.emoji_frequency = acc.emoji;
+    analysis.custom_emoji = acc.custom_emoji.into_vec();
+    analysis.language_frequency = acc.languages;
+    analysis.link_domains = acc.links.domains;
+    analysis.messages_with_links = acc.links.messages;
+    analysis.references = acc.references.into_references();
+    analysis.concordance = acc.concordance;
+    concordance::sort(&mut analysis.concordance);
+    acc.readability.apply(&mut analysis);
+    analysis.sentiment = acc.sentiment.into_sentiment();
+    if let Some(flagger) = &pipeline.flagger {
+        acc.flags.apply(&mut analysis, flagger);
+    }
+    (analysis, radar_inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_spill_round_trip_merges_counts() {
+        let mut spill = Spill::create().unwrap();
+        let mut authors = HashMap::new();
+        authors.insert(
+            "42".to_string(),
+            AuthorAcc {
+                author_name: "alice".to_string(),
+                author_nickname: "Alice".to_string(),
+                counts: MessageCounts::default(),
+                first_seen: None,
+                last_seen: None,
+                seen: HashSet::new(),
+                words: HashMap::from([("hello".to_string(), 2)]),
+                evicted: 0,
+                emoji: BTreeMap::new(),
+                custom_emoji: CustomEmojiTally::default(),
+                languages: BTreeMap::new(),
+                links: AuthorLinks::default(),
+                references: ReferenceTally::default(),
+                concordance: Vec::new(),
+                sentiment: SentimentTally::default(),
+                flags: FlagTally::default(),
+                readability: ReadabilityTally::default(),
+                times: Vec::new(),
+                radar: RadarTally::default(),
+            },
+        );
+        spill.write(&mut authors).unwrap();
+        authors
+            .get_mut("42")
+            .unwrap()
+            .words
+            .insert("hello".to_string(), 1);
+        spill.write(&mut authors).unwrap();
+
+        let merged = spill.read_bucket(bucket_of("42"), None).unwrap();
+        assert_eq!(merged["42"].words["hello"], 3);
+        assert!(authors["42"].words.is_empty());
+
+        let dir = spill.dir.clone();
+        drop(spill);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_radar_and_lifecycle_match_in_memory_analysis() {
+        let message = |id: usize, author: &str, day: u32, content: &str| {
+            serde_json::json!({
+                "message_id": id.to_string(),
+                "content": content,
+                "timestamp": format!("2024-03-{:02}T12:00:00+00:00", day),
+                "author_name": author,
+                "author_id": author,
+                "mentioned_user_name": "Deleted User",
+            })
+        };
+        let messages = serde_json::json!([
+            message(1, "a", 1, "you are so stupid"),
+            message(2, "a", 2, "hello there"),
+            message(3, "a", 2, "hello there"),
+            message(4, "a", 9, ""),
+            message(5, "b", 3, "your idea was fine"),
+            message(6, "b", 20, "thanks"),
+        ]);
+        let path = std::env::temp_dir().join(format!("dua-bounded-{}.json", std::process::id()));
+        fs::write(&path, messages.to_string()).unwrap();
+        let input = path.to_str().unwrap();
+
+        let args = Args::try_parse_from(["deleted-user-analyzer", "-i", input]).unwrap();
+        let pipeline = Pipeline::from_args(&args).unwrap();
+        let in_memory = crate::analyze_in_memory(&args, &pipeline).unwrap();
+        let bounded = analyze(&args, &pipeline, 64).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let profiles = |result: &AnalysisResult| -> BTreeMap<String, _> {
+            result
+                .authors_analysis
+                .iter()
+                .map(|author| (author.author_id.clone(), (author.radar, author.lifecycle)))
+                .collect()
+        };
+        assert_eq!(profiles(&bounded), profiles(&in_memory));
+        assert!(profiles(&bounded)
+            .values()
+            .all(|(radar, lifecycle)| radar.is_some() && lifecycle.is_some()));
+    }
+}
@@ -0,0 +1,265 @@
+//! `--word-buckets`: top words per calendar period, showing how the language
+//! used about the target evolved.
+//!
+//! `--word-trends` follows chosen words (or the overall top words) through
+//! the same periods as one count series per word, for spotting when a term
+//! spiked.
+
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+/// Words listed per bucket.
+pub const TOP_WORDS_PER_BUCKET: usize = 10;
+
+/// Words followed by `--word-trends` when none are given.
+pub const TREND_WORDS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    /// ISO weeks, labeled `2024-W09`.
+    Week,
+    Month,
+    Quarter,
+    Year,
+    /// Fixed-length buckets of this many days, labeled by their first day.
+    Days(u32),
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_ascii_lowercase();
+        Ok(match value.as_str() {
+            "day" => Period::Day,
+            "week" => Period::Week,
+            "month" => Period::Month,
+            "quarter" => Period::Quarter,
+            "year" => Period::Year,
+            _ => match value.strip_suffix('d').map(str::parse::<u32>) {
+                Some(Ok(days)) if days > 0 => Period::Days(days),
+                _ => {
+                    return Err(format!(
+                        "`{}` is not day, week, month, quarter, year or a day count like 14d",
+                        value
+                    ))
+                }
+            },
+        })
+    }
+}
+
+impl Period {
+    /// The label of the bucket `ts` falls in, by its date in `ts`'s zone;
+    /// labels sort chronologically.
+    pub fn label<Z: TimeZone>(&self, ts: DateTime<Z>) -> String {
+        let date = ts.date_naive();
+        match self {
+            Period::Day => date.format("%Y-%m-%d").to_string(),
+            Period::Week => {
+                let week = date.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Period::Month => date.format("%Y-%m").to_string(),
+            Period::Quarter => format!("{}-Q{}", date.year(), date.month0() / 3 + 1),
+            Period::Year => date.year().to_string(),
+            Period::Days(days) => {
+                let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap_or_default();
+                let offset = (date - epoch).num_days();
+                let start = offset - offset.rem_euclid(i64::from(*days));
+                (epoch + chrono::Duration::days(start))
+                    .format("%Y-%m-%d")
+                    .to_string()
+            }
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct WordBucket {
+    pub period: String,
+    pub messages: usize,
+    pub top_words: Vec<(String, usize)>,
+}
+
+/// Count series per word over the periods that have messages.
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct WordTrends {
+    /// Period labels in chronological order; periods without messages
+    /// are left out.
+    pub periods: Vec<String>,
+    /// Unique messages per period, for turning counts into rates.
+    pub messages: Vec<usize>,
+    pub words: Vec<WordTrend>,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct WordTrend {
+    pub word: String,
+    /// Uses per period, aligned with `periods`.
+    pub counts: Vec<usize>,
+}
+
+/// Full word counts per bucket, kept until the top lists are cut so that
+/// per-author counts can be merged into the global series.
+#[derive(Debug, Clone, Default)]
+pub struct BucketCounts {
+    buckets: BTreeMap<String, (usize, HashMap<String, usize>)>,
+}
+
+impl BucketCounts {
+    pub fn record(&mut self, label: String, words: &[String]) {
+        let (messages, counts) = self.buckets.entry(label).or_default();
+        *messages += 1;
+        for word in words {
+            *counts.entry(word.clone()).or_insert(0) += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &BucketCounts) {
+        for (label, (messages, counts)) in &other.buckets {
+            let (total, merged) = self.buckets.entry(label.clone()).or_default();
+            *total += messages;
+            for (word, count) in counts {
+                *merged.entry(word.clone()).or_insert(0) += count;
+            }
+        }
+    }
+
+    /// Renames every word, adding up the counts of words renamed alike.
+    pub fn map_words(&mut self, rename: impl Fn(&str) -> String) {
+        for (_, counts) in self.buckets.values_mut() {
+            let mut mapped: HashMap<String, usize> = HashMap::with_capacity(counts.len());
+            for (word, count) in counts.drain() {
+                *mapped.entry(rename(&word)).or_insert(0) += count;
+            }
+            *counts = mapped;
+        }
+    }
+
+    /// Series for `words`, or for the [`TREND_WORDS`] most counted words
+    /// over every bucket when `words` is empty.
+    pub fn trends(&self, words: &[String]) -> WordTrends {
+        let words: Vec<String> = if words.is_empty() {
+            let mut totals: HashMap<&str, usize> = HashMap::new();
+            for (_, counts) in self.buckets.values() {
+                for (word, count) in counts {
+                    *totals.entry(word).or_insert(0) += count;
+                }
+            }
+            let mut ranked: Vec<(&str, usize)> = totals.into_iter().collect();
+            ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+            ranked
+                .into_iter()
+                .take(TREND_WORDS)
+                .map(|(word, _)| word.to_string())
+                .collect()
+        } else {
+            words
+                .iter()
+                .map(|word| word.trim().to_lowercase())
+                .collect()
+        };
+        WordTrends {
+            periods: self.buckets.keys().cloned().collect(),
+            messages: self
+                .buckets
+                .values()
+                .map(|(messages, _)| *messages)
+                .collect(),
+            words: words
+                .into_iter()
+                .map(|word| WordTrend {
+                    counts: self
+                        .buckets
+                        .values()
+                        .map(|(_, counts)| counts.get(&word).copied().unwrap_or(0))
+                        .collect(),
+                    word,
+                })
+                .collect(),
+        }
+    }
+
+    /// Buckets in chronological order with their most frequent words; ties
+    /// are broken alphabetically.
+    pub fn top_words(&self) -> Vec<WordBucket> {
+        self.buckets
+            .iter()
+            .map(|(label, (messages, counts))| {
+                let mut words: Vec<(String, usize)> =
+                    counts.iter().map(|(w, c)| (w.clone(), *c)).collect();
+                words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                words.truncate(TOP_WORDS_PER_BUCKET);
+                WordBucket {
+                    period: label.clone(),
+                    messages: *messages,
+                    top_words: words,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Utc;
+    use chrono_tz::Tz;
+
+    fn ts(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_period_labels() {
+        let t = ts("2024-03-02T10:00:00Z");
+        assert_eq!(Period::Day.label(t), "2024-03-02");
+        assert_eq!(Period::Week.label(t), "2024-W09");
+        assert_eq!(Period::Month.label(t), "2024-03");
+        assert_eq!(Period::Quarter.label(t), "2024-Q1");
+        assert_eq!(Period::Year.label(t), "2024");
+        assert_eq!("14d".parse::<Period>(), Ok(Period::Days(14)));
+        assert_eq!(Period::Days(1).label(t), "2024-03-02");
+        assert!("0d".parse::<Period>().is_err());
+        assert!("fortnight".parse::<Period>().is_err());
+
+        let late = ts("2024-03-31T23:30:00Z").with_timezone(&Tz::Europe__Berlin);
+        assert_eq!(Period::Month.label(late), "2024-04");
+    }
+
+    #[test]
+    fn test_merge_and_top_words() {
+        let words = |list: &[&str]| list.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        let mut a = BucketCounts::default();
+        a.record("2024-02".to_string(), &words(&["gone", "why"]));
+        let mut b = BucketCounts::default();
+        b.record("2024-02".to_string(), &words(&["gone"]));
+        b.record("2024-01".to_string(), &words(&["hello"]));
+        a.merge(&b);
+
+        let buckets = a.top_words();
+        assert_eq!(buckets[0].period, "2024-01");
+        assert_eq!(buckets[1].messages, 2);
+        assert_eq!(
+            buckets[1].top_words,
+            [("gone".to_string(), 2), ("why".to_string(), 1)]
+        );
+
+        let trends = a.trends(&[]);
+        assert_eq!(trends.periods, ["2024-01", "2024-02"]);
+        assert_eq!(trends.messages, [1, 2]);
+        assert_eq!(trends.words[0].word, "gone");
+        assert_eq!(trends.words[0].counts, [0, 2]);
+        let chosen = a.trends(&words(&["Hello", "never"]));
+        assert_eq!(chosen.words[0].counts, [1, 0]);
+        assert_eq!(chosen.words[1].counts, [0, 0]);
+    }
+}
@@ -0,0 +1,212 @@
+//! [`MessageFilter`]: one predicate interface for the target check, the
+//! built-in filters and any custom closure, so they can be combined without
+//! going through the command line.
+
+use crate::expr::Expr;
+use crate::filter::{
+    AttachmentFilter, AuthorFilter, ChannelFilter, ContentFilter, DateWindow, IdRange,
+    LanguageFilter, RoleFilter,
+};
+use crate::message::Message;
+use crate::target::Target;
+
+/// Decides whether a message is kept. Any `Fn(&Message) -> bool` is a
+/// filter too.
+pub trait MessageFilter: Send + Sync {
+    fn matches(&self, msg: &Message) -> bool;
+
+    /// Kept only when both filters keep the message.
+    fn and<F: MessageFilter>(self, other: F) -> And<Self, F>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    /// Kept when either filter keeps the message.
+    fn or<F: MessageFilter>(self, other: F) -> Or<Self, F>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+
+    /// Kept when this filter drops the message.
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+}
+
+impl<F: Fn(&Message) -> bool + Send + Sync> MessageFilter for F {
+    fn matches(&self, msg: &Message) -> bool {
+        self(msg)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct And<A, B>(A, B);
+
+impl<A: MessageFilter, B: MessageFilter> MessageFilter for And<A, B> {
+    fn matches(&self, msg: &Message) -> bool {
+        self.0.matches(msg) && self.1.matches(msg)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Or<A, B>(A, B);
+
+impl<A: MessageFilter, B: MessageFilter> MessageFilter for Or<A, B> {
+    fn matches(&self, msg: &Message) -> bool {
+        self.0.matches(msg) || self.1.matches(msg)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Not<A>(A);
+
+impl<A: MessageFilter> MessageFilter for Not<A> {
+    fn matches(&self, msg: &Message) -> bool {
+        !self.0.matches(msg)
+    }
+}
+
+/// Named filters applied in order; a message is kept when every one keeps
+/// it. An empty chain keeps everything.
+#[derive(Default)]
+pub struct FilterChain {
+    filters: Vec<(String, Box<dyn MessageFilter>)>,
+}
+
+impl FilterChain {
+    pub fn new() -> Self {
+        FilterChain::default()
+    }
+
+    /// Appends a filter, run after the ones already in the chain.
+    pub fn with(mut self, name: impl Into<String>, filter: impl MessageFilter + 'static) -> Self {
+        self.push(name, filter);
+        self
+    }
+
+    pub fn push(&mut self, name: impl Into<String>, filter: impl MessageFilter + 'static) {
+        self.filters.push((name.into(), Box::new(filter)));
+    }
+
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Filter names in the order they run.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.filters.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The name of the first filter that drops the message, or `None` when
+    /// the chain keeps it.
+    pub fn rejected_by(&self, msg: &Message) -> Option<&str> {
+        self.filters
+            .iter()
+            .find(|(_, filter)| !filter.matches(msg))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+impl MessageFilter for FilterChain {
+    fn matches(&self, msg: &Message) -> bool {
+        self.rejected_by(msg).is_none()
+    }
+}
+
+impl std::fmt::Debug for FilterChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// The deleted-user (or `--target`) check.
+impl MessageFilter for Target {
+    fn matches(&self, msg: &Message) -> bool {
+        Target::matches(self, msg)
+    }
+}
+
+impl MessageFilter for Expr {
+    fn matches(&self, msg: &Message) -> bool {
+        Expr::matches(self, msg)
+    }
+}
+
+macro_rules! contains_filter {
+    ($($ty:ty),*) => {
+        $(impl MessageFilter for $ty {
+            fn matches(&self, msg: &Message) -> bool {
+                self.contains(msg)
+            }
+        })*
+    };
+}
+
+contains_filter!(
+    AuthorFilter,
+    ChannelFilter,
+    RoleFilter,
+    AttachmentFilter,
+    ContentFilter,
+    LanguageFilter,
+    DateWindow,
+    IdRange
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(author: &str, content: &str, mentioned: &str) -> Message {
+        Message {
+            author_id: author.to_string(),
+            content: content.to_string(),
+            mentioned_user_name: Some(mentioned.to_string()),
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_chain_and_combinators() {
+        let chain = FilterChain::new()
+            .with("target", Target::default())
+            .with("long", |msg: &Message| msg.content.len() > 3)
+            .with(
+                "authors",
+                AuthorFilter::new(&["1".to_string()], None, &[])
+                    .unwrap()
+                    .or(|msg: &Message| msg.author_id == "2"),
+            );
+        assert_eq!(chain.len(), 3);
+
+        assert!(chain.matches(&msg("1", "hello", "Deleted User")));
+        assert!(chain.matches(&msg("2", "hello", "Deleted User")));
+        assert_eq!(
+            chain.rejected_by(&msg("1", "hello", "someone")),
+            Some("target")
+        );
+        assert_eq!(
+            chain.rejected_by(&msg("1", "hi", "Deleted User")),
+            Some("long")
+        );
+        assert_eq!(
+            chain.rejected_by(&msg("3", "hello", "Deleted User")),
+            Some("authors")
+        );
+
+        let not_target = Target::default().not();
+        assert!(not_target.matches(&msg("1", "hi", "someone")));
+        assert!(FilterChain::new().matches(&Message::default()));
+    }
+}
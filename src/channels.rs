@@ -0,0 +1,70 @@
+//! Per-channel breakdown of the analyzed messages for merged exports.
+
+use crate::message::Message;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Messages to deleted users in one channel.
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct ChannelCount {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_name: Option<String>,
+    pub messages: usize,
+}
+
+/// Tally keyed by channel ID, or by name for exports without IDs. Messages
+/// without any channel field are not counted.
+#[derive(Debug, Default)]
+pub struct ChannelTally(BTreeMap<String, ChannelCount>);
+
+impl ChannelTally {
+    pub fn record(&mut self, msg: &Message) {
+        let Some(key) = msg.channel_id.as_ref().or(msg.channel_name.as_ref()) else {
+            return;
+        };
+        let entry = self.0.entry(key.clone()).or_insert_with(|| ChannelCount {
+            channel_id: msg.channel_id.clone(),
+            channel_name: None,
+            messages: 0,
+        });
+        entry.messages += 1;
+        if entry.channel_name.is_none() {
+            entry.channel_name = msg.channel_name.clone();
+        }
+    }
+
+    /// Channels by descending message count.
+    pub fn into_vec(self) -> Vec<ChannelCount> {
+        let mut channels: Vec<ChannelCount> = self.0.into_values().collect();
+        channels.sort_by_key(|c| std::cmp::Reverse(c.messages));
+        channels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tally_groups_by_id_and_fills_names() {
+        let msg = |id: Option<&str>, name: Option<&str>| Message {
+            channel_id: id.map(str::to_string),
+            channel_name: name.map(str::to_string),
+            ..Message::default()
+        };
+        let mut tally = ChannelTally::default();
+        tally.record(&msg(Some("1"), None));
+        tally.record(&msg(Some("1"), Some("general")));
+        tally.record(&msg(None, Some("random")));
+        tally.record(&msg(None, None));
+
+        let channels = tally.into_vec();
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].messages, 2);
+        assert_eq!(channels[0].channel_name.as_deref(), Some("general"));
+        assert_eq!(channels[1].channel_id, None);
+    }
+}
@@ -0,0 +1,218 @@
+//! Resumable run state for long multi-file analyses.
+//!
+//! After every input file the loaded-and-filtered messages and the load
+//! statistics are written to a state file. A run started with `--resume`
+//! skips the files already recorded there and continues from the saved
+//! aggregates. The state records a hash of the options that decide which
+//! messages it holds, and a resume with different ones is refused rather
+//! than mixing two selections in one result.
+
+use crate::conflicts::ConflictTracker;
+use crate::filterstats::FilterTally;
+use crate::fingerprint;
+use crate::gaps::GapTracker;
+use crate::inactive::ActivityTracker;
+use crate::message::{FallbackStats, Message};
+use crate::nicknames::NicknameTracker;
+use crate::optout::OptOutTally;
+use crate::paths;
+use crate::sampling::BottomK;
+use crate::warnings::Warnings;
+use crate::Args;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+const STATE_VERSION: u32 = 14;
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct RunState {
+    version: u32,
+    /// `selection_hash` of the options the state was written with.
+    selection: String,
+    pub processed_files: BTreeSet<String>,
+    pub loaded: usize,
+    /// Messages that passed `--sample`, counted before the target filter.
+    pub sampled: usize,
+    /// Smallest sample keys seen so far under `--sample-n`.
+    pub sample_keys: Option<BottomK>,
+    pub fallbacks: FallbackStats,
+    pub warnings: Warnings,
+    pub conflicts: ConflictTracker,
+    pub opted_out: OptOutTally,
+    /// Messages each filter removed.
+    pub filter_stats: FilterTally,
+    /// Every message ID per channel under `--deletion-gaps`.
+    pub gaps: Option<GapTracker>,
+    /// Every author's nicknames under `--nickname-differs` /
+    /// `--multiple-nicknames`.
+    pub nicknames: Option<NicknameTracker>,
+    /// Every author's last message under `--inactive-targets`.
+    pub activity: Option<ActivityTracker>,
+    /// Messages that already passed the target filter.
+    pub matched: Vec<Message>,
+}
+
+/// A hash of the options applied while files are loaded: the target,
+/// sampling, opt-out and filters, and the trackers kept per message. Options
+/// applied to the loaded messages afterwards may change between runs.
+fn selection_hash(args: &Args) -> String {
+    let selection = format!(
+        "{:?}",
+        (
+            (
+                &args.target,
+                &args.target_regex,
+                args.match_case,
+                args.normalize_unicode,
+                args.no_target_filter,
+                &args.targets_file,
+                args.inactive_targets,
+            ),
+            (
+                args.sample,
+                args.sample_n,
+                args.seed,
+                &args.opt_out,
+                args.strict
+            ),
+            (
+                &args.filter,
+                args.since,
+                args.until,
+                args.timezone,
+                args.after_id,
+                args.before_id,
+            ),
+            (
+                &args.channel,
+                &args.role,
+                &args.exclude_role,
+                args.only_with_attachments,
+                args.text_only,
+                &args.content_match,
+                &args.content_exclude,
+                &args.exclude_keyword_file,
+                args.min_content_chars,
+                args.max_content_chars,
+                args.language,
+            ),
+            (
+                &args.authors,
+                &args.authors_file,
+                &args.exclude_authors,
+                args.include_system,
+                args.exclude_bots,
+            ),
+            (
+                &args.events,
+                args.event_window,
+                args.exclude_events,
+                args.deletion_gaps,
+                args.gap_window,
+                args.nickname_differs,
+                args.multiple_nicknames,
+            ),
+        )
+    );
+    fingerprint::hash(&selection)
+}
+
+impl RunState {
+    pub fn new(args: &Args) -> Self {
+        RunState {
+            version: STATE_VERSION,
+            selection: selection_hash(args),
+            ..Default::default()
+        }
+    }
+
+    /// Loads the state saved by a run with the same selection options as
+    /// `args`.
+    pub fn load(path: &Path, args: &Args) -> anyhow::Result<Self> {
+        let data = fs::read_to_string(paths::extended(path))
+            .with_context(|| format!("failed to read state {}", path.display()))?;
+        let state: RunState = serde_json::from_str(&data)
+            .with_context(|| format!("failed to parse state {}", path.display()))?;
+        if state.version != STATE_VERSION {
+            anyhow::bail!(
+                "state file {} has version {}, expected {}",
+                path.display(),
+                state.version,
+                STATE_VERSION
+            );
+        }
+        if state.selection != selection_hash(args) {
+            anyhow::bail!(
+                "state file {} was written with different target, filter or sampling options; \
+                 resume with the same options or start over without --resume",
+                path.display()
+            );
+        }
+        Ok(state)
+    }
+
+    /// Writes the state atomically so an interruption mid-write never leaves
+    /// a truncated checkpoint behind.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let tmp = path.with_extension("tmp");
+        fs::write(paths::extended(&tmp), serde_json::to_vec(self)?)
+            .with_context(|| format!("failed to write state {}", tmp.display()))?;
+        fs::rename(paths::extended(&tmp), paths::extended(path))
+            .with_context(|| format!("failed to write state {}", path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn args(extra: &[&str]) -> Args {
+        let base = ["deleted-user-analyzer", "-i", "a.json"];
+        Args::try_parse_from(base.iter().chain(extra)).unwrap()
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let path = std::env::temp_dir().join(format!("dua-state-{}.json", std::process::id()));
+
+        let mut state = RunState::new(&args(&[]));
+        state.processed_files.insert("a.json".to_string());
+        state.loaded = 3;
+        state.save(&path).unwrap();
+
+        // Options applied after loading may change.
+        let loaded = RunState::load(&path, &args(&["--hapax"])).unwrap();
+        assert!(loaded.processed_files.contains("a.json"));
+        assert_eq!(loaded.loaded, 3);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_state_version_mismatch_is_rejected() {
+        let path = std::env::temp_dir().join(format!("dua-state-old-{}.json", std::process::id()));
+        RunState::default().save(&path).unwrap();
+
+        assert!(RunState::load(&path, &args(&[])).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_state_with_other_selection_is_rejected() {
+        let path =
+            std::env::temp_dir().join(format!("dua-state-filters-{}.json", std::process::id()));
+        RunState::new(&args(&[])).save(&path).unwrap();
+
+        let error = RunState::load(&path, &args(&["--channel", "general"]))
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(error.contains("different target, filter or sampling options"));
+        assert!(RunState::load(&path, &args(&["--target", "bob"])).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,132 @@
+//! `--cjk`: word segmentation for Chinese and Japanese, which are written
+//! without spaces and otherwise come out as one token per sentence.
+//!
+//! Runs of Han and kana characters are cut out of the content and segmented
+//! on their own; everything around them goes through the normal tokenizer.
+
+use jieba_rs::Jieba;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// Loaded on first use; building the dictionary takes a moment.
+static JIEBA: LazyLock<Jieba> = LazyLock::new(Jieba::new);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CjkMode {
+    /// Dictionary segmentation (jieba) for Chinese; runs containing kana
+    /// are Japanese, which the dictionary does not cover, and fall back to
+    /// bigrams.
+    Jieba,
+    /// Overlapping character pairs for every run: no dictionary, works for
+    /// any of the scripts, but counts pairs rather than words.
+    Bigram,
+}
+
+impl FromStr for CjkMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "jieba" => Ok(CjkMode::Jieba),
+            "bigram" => Ok(CjkMode::Bigram),
+            other => Err(format!("`{}` is not jieba or bigram", other)),
+        }
+    }
+}
+
+/// Han ideographs and Japanese kana. Hangul is left out: Korean is written
+/// with spaces between words.
+pub fn is_cjk(c: char) -> bool {
+    is_han(c) || is_kana(c)
+}
+
+fn is_han(c: char) -> bool {
+    matches!(c,
+        '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FA1F}'
+        | '\u{3005}' // 々
+    )
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{309F}'
+        | '\u{30A0}'..='\u{30FF}'
+        | '\u{31F0}'..='\u{31FF}'
+        | '\u{FF66}'..='\u{FF9F}'
+    )
+}
+
+/// Splits `content` into alternating non-CJK text and CJK runs, in order.
+/// `true` marks a run.
+pub fn runs(content: &str) -> Vec<(&str, bool)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut in_run = false;
+    for (i, c) in content.char_indices() {
+        if is_cjk(c) != in_run {
+            if i > start {
+                runs.push((&content[start..i], in_run));
+            }
+            start = i;
+            in_run = !in_run;
+        }
+    }
+    if start < content.len() {
+        runs.push((&content[start..], in_run));
+    }
+    runs
+}
+
+/// The words of one CJK run.
+pub fn segment(run: &str, mode: CjkMode) -> Vec<String> {
+    if mode == CjkMode::Jieba && !run.chars().any(is_kana) {
+        return JIEBA
+            .cut(run, true)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+    }
+    bigrams(run)
+}
+
+/// Overlapping pairs; a single character is its own token.
+fn bigrams(run: &str) -> Vec<String> {
+    let chars: Vec<char> = run.chars().collect();
+    if chars.len() < 2 {
+        return vec![run.to_string()];
+    }
+    chars.windows(2).map(|pair| pair.iter().collect()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs_and_segments() {
+        assert_eq!(
+            runs("iPhone很好 ok日本語"),
+            [
+                ("iPhone", false),
+                ("很好", true),
+                (" ok", false),
+                ("日本語", true)
+            ]
+        );
+        assert_eq!(
+            segment("東京タワー", CjkMode::Bigram),
+            ["東京", "京タ", "タワ", "ワー"]
+        );
+        assert_eq!(segment("猫", CjkMode::Bigram), ["猫"]);
+        // Kana marks the run as Japanese, so jieba is not used for it.
+        assert_eq!(segment("すごい", CjkMode::Jieba), ["すご", "ごい"]);
+        assert_eq!(
+            segment("我们在学习中文", CjkMode::Jieba),
+            ["我们", "在", "学习", "中文"]
+        );
+        assert!("icu".parse::<CjkMode>().is_err());
+    }
+}
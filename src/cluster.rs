@@ -0,0 +1,261 @@
+//! `--cluster K`: groups authors with similar vocabularies into K clusters
+//! by spherical k-means, the cosine-similarity form of k-means used by
+//! `--similarity`, so groups of accounts writing alike show up together.
+//!
+//! Each author is their counted word frequencies scaled to unit length.
+//! The first centre is the author with the most distinct words and every
+//! next one the author least like any centre so far, so clusters are the
+//! same on every run. Authors without counted words are not clustered. A
+//! cluster's `top_words` are the words its centre weighs most above the mean
+//! of all authors, which is what sets its members apart.
+
+use crate::AnalysisResult;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Reassignment rounds before giving up on convergence.
+const MAX_ROUNDS: usize = 100;
+
+/// Words listed per cluster.
+const TOP_WORDS: usize = 10;
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct AuthorCluster {
+    /// From 1, largest cluster first.
+    pub cluster: usize,
+    /// Member author IDs.
+    pub authors: Vec<String>,
+    /// The words setting the cluster apart, with how far its centre's weight
+    /// for each is above the mean.
+    pub top_words: Vec<(String, f64)>,
+}
+
+/// Parses a `--cluster` count.
+pub fn parse_count(value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(k) if k >= 2 => Ok(k),
+        _ => Err(format!("`{}` is not a cluster count of 2 or more", value)),
+    }
+}
+
+type Vector<'a> = HashMap<&'a str, f64>;
+
+fn unit(words: &BTreeMap<String, usize>) -> Vector<'_> {
+    let norm = words
+        .values()
+        .map(|&count| (count * count) as f64)
+        .sum::<f64>()
+        .sqrt();
+    words
+        .iter()
+        .map(|(word, &count)| (word.as_str(), count as f64 / norm))
+        .collect()
+}
+
+/// Cosine similarity of a unit vector with any vector.
+fn similarity(unit: &Vector, other: &Vector) -> f64 {
+    let norm = other.values().map(|w| w * w).sum::<f64>().sqrt();
+    if norm == 0.0 {
+        return 0.0;
+    }
+    let dot: f64 = unit
+        .iter()
+        .filter_map(|(word, w)| other.get(word).map(|o| w * o))
+        .sum();
+    dot / norm
+}
+
+fn mean<'a>(vectors: &[&Vector<'a>]) -> Vector<'a> {
+    let mut sum: Vector = HashMap::new();
+    for vector in vectors {
+        for (word, w) in vector.iter() {
+            *sum.entry(word).or_insert(0.0) += w;
+        }
+    }
+    let n = vectors.len().max(1) as f64;
+    sum.values_mut().for_each(|w| *w /= n);
+    sum
+}
+
+/// Cluster index per vector, with clusters numbered by first appearance.
+fn assign(vectors: &[Vector], k: usize) -> Vec<usize> {
+    let largest = (0..vectors.len())
+        .max_by(|&a, &b| vectors[a].len().cmp(&vectors[b].len()).then(b.cmp(&a)))
+        .unwrap_or(0);
+    let mut centres: Vec<Vector> = vec![vectors[largest].clone()];
+    while centres.len() < k {
+        let farthest = (0..vectors.len())
+            .map(|i| {
+                let nearest = centres
+                    .iter()
+                    .map(|c| similarity(&vectors[i], c))
+                    .fold(f64::MIN, f64::max);
+                (i, nearest)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+            .map_or(0, |(i, _)| i);
+        centres.push(vectors[farthest].clone());
+    }
+
+    let mut assignment = vec![usize::MAX; vectors.len()];
+    for _ in 0..MAX_ROUNDS {
+        let next: Vec<usize> = vectors
+            .iter()
+            .map(|v| {
+                (0..k)
+                    .max_by(|&a, &b| {
+                        similarity(v, &centres[a])
+                            .total_cmp(&similarity(v, &centres[b]))
+                            .then(b.cmp(&a))
+                    })
+                    .unwrap_or(0)
+            })
+            .collect();
+        if next == assignment {
+            break;
+        }
+        assignment = next;
+        for (c, centre) in centres.iter_mut().enumerate() {
+            let members: Vec<&Vector> = vectors
+                .iter()
+                .zip(&assignment)
+                .filter(|(_, &a)| a == c)
+                .map(|(v, _)| v)
+                .collect();
+            // An emptied cluster keeps its centre and may win authors back.
+            if !members.is_empty() {
+                *centre = mean(&members);
+            }
+        }
+    }
+    assignment
+}
+
+/// Clusters the result's authors, setting each author's `cluster` and the
+/// result's `clusters`.
+pub fn annotate(result: &mut AnalysisResult, k: usize) {
+    let authors = &mut result.authors_analysis;
+    let members: Vec<usize> = (0..authors.len())
+        .filter(|&i| !authors[i].word_frequency.is_empty())
+        .collect();
+    if members.is_empty() {
+        return;
+    }
+    let vectors: Vec<Vector> = members
+        .iter()
+        .map(|&i| unit(&authors[i].word_frequency))
+        .collect();
+    let k = k.min(vectors.len());
+    let assignment = assign(&vectors, k);
+    let overall = mean(&vectors.iter().collect::<Vec<_>>());
+
+    let mut clusters: Vec<_> = (0..k)
+        .map(|c| {
+            let inside: Vec<usize> = (0..vectors.len()).filter(|&v| assignment[v] == c).collect();
+            let centre = mean(&inside.iter().map(|&v| &vectors[v]).collect::<Vec<_>>());
+            let mut words: Vec<(String, f64)> = centre
+                .iter()
+                .map(|(word, w)| (word.to_string(), w - overall[word]))
+                .filter(|(_, lift)| *lift > 0.0)
+                .collect();
+            words.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            words.truncate(TOP_WORDS);
+            (inside, words)
+        })
+        .filter(|(inside, _)| !inside.is_empty())
+        .collect();
+    clusters.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+
+    result.clusters = clusters
+        .into_iter()
+        .enumerate()
+        .map(|(n, (inside, top_words))| {
+            for &v in &inside {
+                authors[members[v]].cluster = Some(n + 1);
+            }
+            AuthorCluster {
+                cluster: n + 1,
+                authors: inside
+                    .iter()
+                    .map(|&v| authors[members[v]].author_id.clone())
+                    .collect(),
+                top_words,
+            }
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::BlankCounts;
+    use crate::sanitize::Sanitizer;
+    use crate::warnings::Warnings;
+    use crate::{build_author_analysis, build_result, AuthorAnalysis, MessageCounts};
+
+    fn author(id: &str, words: &[(&str, usize)]) -> AuthorAnalysis {
+        let words: HashMap<String, usize> = words
+            .iter()
+            .map(|(word, count)| (word.to_string(), *count))
+            .collect();
+        let counts = MessageCounts::default();
+        build_author_analysis(id.to_string(), id.to_string(), String::new(), counts, words)
+    }
+
+    #[test]
+    fn test_groups_alike_authors() {
+        let authors = vec![
+            author("1", &[("refund", 3), ("scam", 2)]),
+            author("2", &[("refund", 2), ("scam", 2), ("where", 1)]),
+            author("3", &[("miss", 2), ("rip", 1)]),
+            author("4", &[("refund", 1), ("scam", 3)]),
+            author("5", &[("miss", 1), ("rip", 2)]),
+            author("6", &[]),
+        ];
+        let mut result = build_result(
+            authors,
+            BlankCounts::default(),
+            Vec::new(),
+            0,
+            Warnings::default(),
+        );
+        annotate(&mut result, 2);
+        assert_eq!(result.clusters.len(), 2);
+        assert_eq!(result.clusters[0].authors, ["1", "2", "4"]);
+        assert_eq!(result.clusters[1].authors, ["3", "5"]);
+        assert_eq!(result.clusters[1].top_words[0].0, "miss");
+        let cluster = |i: usize| result.authors_analysis[i].cluster;
+        assert_eq!(
+            (cluster(0), cluster(2), cluster(5)),
+            (Some(1), Some(2), None)
+        );
+        assert!(parse_count("1").is_err());
+    }
+
+    #[test]
+    fn test_top_words_are_masked() {
+        let authors = vec![
+            author("1", &[("shit", 3), ("refund", 1)]),
+            author("2", &[("shit", 2), ("scam", 1)]),
+            author("3", &[("miss", 2), ("rip", 1)]),
+        ];
+        let mut result = build_result(
+            authors,
+            BlankCounts::default(),
+            Vec::new(),
+            0,
+            Warnings::default(),
+        );
+        // As in `main`: clusters are annotated before the result is masked.
+        annotate(&mut result, 2);
+        Sanitizer::new(true, None).unwrap().apply(&mut result);
+        let words: Vec<&str> = result
+            .clusters
+            .iter()
+            .flat_map(|cluster| cluster.top_words.iter().map(|(word, _)| word.as_str()))
+            .collect();
+        assert!(words.contains(&"s***"));
+        assert!(!words.contains(&"shit"));
+    }
+}
@@ -0,0 +1,194 @@
+//! `--collocations`: word pairs ranked by how much more often they occur
+//! together than their words' own frequencies predict, which surfaces an
+//! author's characteristic phrases rather than just their frequent ones.
+//!
+//! Pairs are adjacent words within one message, as written and skipping any
+//! pair with a stopword, like `--ngrams`. Both measures compare a pair's
+//! count with how often its first word starts a pair and its second word
+//! ends one:
+//!
+//! - `pmi`: pointwise mutual information, `log2(p(xy) / (p(x·) p(·y)))`.
+//!   Favors rare, exclusive pairs, so pairs seen fewer than
+//!   `--collocation-min-count` times are left out.
+//! - `llr`: Dunning's log-likelihood ratio, which also weighs how much
+//!   evidence there is, so frequent strong pairs rank first.
+
+use crate::stopwords::Stopwords;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Collocations listed per author and for the whole result.
+const LIMIT: usize = 10;
+
+/// Default `--collocation-min-count`.
+pub const DEFAULT_MIN_COUNT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measure {
+    Pmi,
+    Llr,
+}
+
+impl FromStr for Measure {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "pmi" => Ok(Measure::Pmi),
+            "llr" => Ok(Measure::Llr),
+            other => Err(format!("`{}` is not pmi or llr", other)),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Collocation {
+    /// The two words, space-separated.
+    pub words: String,
+    pub count: usize,
+    pub score: f64,
+}
+
+/// Adjacent pair counts with their marginals.
+#[derive(Debug, Clone, Default)]
+pub struct PairCounts {
+    pairs: HashMap<(String, String), usize>,
+    /// Pairs each word starts.
+    first: HashMap<String, usize>,
+    /// Pairs each word ends.
+    second: HashMap<String, usize>,
+    total: usize,
+}
+
+impl PairCounts {
+    pub fn record(&mut self, words: &[String], stopwords: &Stopwords) {
+        for pair in words.windows(2) {
+            if pair.iter().any(|word| stopwords.contains(word)) {
+                continue;
+            }
+            let (x, y) = (pair[0].clone(), pair[1].clone());
+            *self.first.entry(x.clone()).or_insert(0) += 1;
+            *self.second.entry(y.clone()).or_insert(0) += 1;
+            *self.pairs.entry((x, y)).or_insert(0) += 1;
+            self.total += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &PairCounts) {
+        for (pair, count) in &other.pairs {
+            *self.pairs.entry(pair.clone()).or_insert(0) += count;
+        }
+        for (word, count) in &other.first {
+            *self.first.entry(word.clone()).or_insert(0) += count;
+        }
+        for (word, count) in &other.second {
+            *self.second.entry(word.clone()).or_insert(0) += count;
+        }
+        self.total += other.total;
+    }
+
+    /// Renames every word, adding up the counts of words renamed alike.
+    pub fn map_words(&mut self, rename: impl Fn(&str) -> String) {
+        let mut mapped = PairCounts {
+            total: self.total,
+            ..PairCounts::default()
+        };
+        for ((x, y), count) in std::mem::take(&mut self.pairs) {
+            *mapped.pairs.entry((rename(&x), rename(&y))).or_insert(0) += count;
+        }
+        for (word, count) in std::mem::take(&mut self.first) {
+            *mapped.first.entry(rename(&word)).or_insert(0) += count;
+        }
+        for (word, count) in std::mem::take(&mut self.second) {
+            *mapped.second.entry(rename(&word)).or_insert(0) += count;
+        }
+        *self = mapped;
+    }
+
+    /// The highest-scoring pairs seen at least `min_count` times.
+    pub fn rank(&self, measure: Measure, min_count: usize) -> Vec<Collocation> {
+        let mut ranked: Vec<Collocation> = self
+            .pairs
+            .iter()
+            .filter(|(_, &count)| count >= min_count)
+            .map(|((x, y), &count)| Collocation {
+                words: format!("{} {}", x, y),
+                count,
+                score: self.score(measure, count, self.first[x], self.second[y]),
+            })
+            .collect();
+        ranked.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| b.count.cmp(&a.count))
+                .then_with(|| a.words.cmp(&b.words))
+        });
+        ranked.truncate(LIMIT);
+        ranked
+    }
+
+    fn score(&self, measure: Measure, k11: usize, first: usize, second: usize) -> f64 {
+        let n = self.total as f64;
+        let (k11, first, second) = (k11 as f64, first as f64, second as f64);
+        match measure {
+            Measure::Pmi => (k11 * n / (first * second)).log2(),
+            Measure::Llr => {
+                let k12 = first - k11;
+                let k21 = second - k11;
+                let k22 = n - k11 - k12 - k21;
+                let term = |k: f64, row: f64, col: f64| {
+                    if k > 0.0 {
+                        k * (k * n / (row * col)).ln()
+                    } else {
+                        0.0
+                    }
+                };
+                2.0 * (term(k11, first, second)
+                    + term(k12, first, n - second)
+                    + term(k21, n - first, second)
+                    + term(k22, n - first, n - second))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_ranks_exclusive_pairs_first() {
+        let stopwords = Stopwords::load(&[whatlang::Lang::Eng], None).unwrap();
+        let mut counts = PairCounts::default();
+        for text in [
+            "game night tonight",
+            "game night again",
+            "good game",
+            "good game folks",
+            "good night",
+            "see the game",
+        ] {
+            counts.record(&words(text), &stopwords);
+        }
+        // "the game" has a stopword and is never counted.
+        assert!(!counts
+            .pairs
+            .contains_key(&("the".to_string(), "game".to_string())));
+
+        let pmi = counts.rank(Measure::Pmi, 2);
+        let ranked: Vec<&str> = pmi.iter().map(|c| c.words.as_str()).collect();
+        assert_eq!(ranked, ["good game", "game night"]);
+        assert!(pmi[0].score > 0.0);
+
+        let llr = counts.rank(Measure::Llr, 1);
+        assert_eq!(llr.len(), 5);
+        assert!(llr.iter().all(|c| c.score >= 0.0));
+        assert!("dice".parse::<Measure>().is_err());
+    }
+}
@@ -0,0 +1,98 @@
+//! `--concordance`: every use of the given words with the words around it
+//! (keyword in context), grouped by author, to show how a word was used
+//! rather than only how often.
+//!
+//! Words are matched against the content as written, split on whitespace,
+//! ignoring case and surrounding punctuation, so `Refund?!` matches
+//! `refund`. Context keeps the original spelling.
+
+use crate::message::Message;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Default `--concordance-context`.
+pub const DEFAULT_CONTEXT: usize = 5;
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct ConcordanceLine {
+    pub message_id: String,
+    /// Up to `--concordance-context` words before the keyword.
+    pub left: String,
+    /// The keyword as written.
+    pub keyword: String,
+    /// Up to `--concordance-context` words after it.
+    pub right: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Concordance {
+    /// Lowercased.
+    words: Vec<String>,
+    context: usize,
+}
+
+impl Concordance {
+    pub fn new(words: &[String], context: usize) -> Option<Self> {
+        let words: Vec<String> = words
+            .iter()
+            .map(|word| word.trim().to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect();
+        (!words.is_empty()).then_some(Concordance { words, context })
+    }
+
+    /// One line per keyword use in `msg`.
+    pub fn lines(&self, msg: &Message) -> Vec<ConcordanceLine> {
+        let chunks: Vec<&str> = msg.content.split_whitespace().collect();
+        let mut lines = Vec::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let bare = chunk
+                .trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase();
+            if !self.words.contains(&bare) {
+                continue;
+            }
+            lines.push(ConcordanceLine {
+                message_id: msg.message_id.clone(),
+                left: chunks[i.saturating_sub(self.context)..i].join(" "),
+                keyword: chunk.to_string(),
+                right: chunks[i + 1..(i + 1 + self.context).min(chunks.len())].join(" "),
+            });
+        }
+        lines
+    }
+}
+
+/// Oldest message first, by snowflake; lines within a message keep their
+/// order.
+pub fn sort(lines: &mut [ConcordanceLine]) {
+    lines.sort_by_key(|line| line.message_id.parse::<u64>().unwrap_or(u64::MAX));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyword_in_context() {
+        let concordance = Concordance::new(&["Refund".to_string()], 2).unwrap();
+        let msg = Message {
+            message_id: "1".to_string(),
+            content: "where is my Refund?! I want a refund now".to_string(),
+            ..Message::default()
+        };
+        let lines = concordance.lines(&msg);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            (
+                lines[0].left.as_str(),
+                lines[0].keyword.as_str(),
+                lines[0].right.as_str()
+            ),
+            ("is my", "Refund?!", "I want")
+        );
+        assert_eq!(lines[1].right, "now");
+        assert!(concordance.lines(&Message::default()).is_empty());
+        assert!(Concordance::new(&[" ".to_string()], 2).is_none());
+    }
+}
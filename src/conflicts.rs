@@ -0,0 +1,122 @@
+use crate::Message;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// One name observed for an author ID, with where it was seen.
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct NameSighting {
+    pub author_name: String,
+    pub message_count: usize,
+    pub files: Vec<String>,
+}
+
+/// An author ID that appeared under names that do not normalize to the same
+/// handle, which usually points at an exporter bug or an ID collision.
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct AuthorConflict {
+    pub author_id: String,
+    pub names: Vec<NameSighting>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct SightingAcc {
+    message_count: usize,
+    files: BTreeSet<String>,
+}
+
+/// Collects every author name seen per author ID while inputs are loaded.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct ConflictTracker {
+    seen: HashMap<String, BTreeMap<String, SightingAcc>>,
+}
+
+impl ConflictTracker {
+    pub fn record(&mut self, file: &str, msgs: &[Message]) {
+        for msg in msgs {
+            self.record_one(file, msg);
+        }
+    }
+
+    pub fn record_one(&mut self, file: &str, msg: &Message) {
+        let acc = self
+            .seen
+            .entry(msg.author_id.clone())
+            .or_default()
+            .entry(msg.author_name.clone())
+            .or_default();
+        acc.message_count += 1;
+        if !acc.files.contains(file) {
+            acc.files.insert(file.to_string());
+        }
+    }
+
+    pub fn conflicts(self) -> Vec<AuthorConflict> {
+        let mut conflicts: Vec<AuthorConflict> = self
+            .seen
+            .into_iter()
+            .filter(|(_, names)| {
+                let handles: BTreeSet<String> = names.keys().map(|n| normalize_name(n)).collect();
+                handles.len() > 1
+            })
+            .map(|(author_id, names)| {
+                let mut names: Vec<NameSighting> = names
+                    .into_iter()
+                    .map(|(author_name, acc)| NameSighting {
+                        author_name,
+                        message_count: acc.message_count,
+                        files: acc.files.into_iter().collect(),
+                    })
+                    .collect();
+                names.sort_by_key(|n| std::cmp::Reverse(n.message_count));
+                AuthorConflict { author_id, names }
+            })
+            .collect();
+        conflicts.sort_by(|a, b| a.author_id.cmp(&b.author_id));
+        conflicts
+    }
+}
+
+/// Lowercases a name and drops a legacy `#1234` discriminator so harmless
+/// formatting differences between exports are not reported as conflicts.
+fn normalize_name(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    match name.rsplit_once('#') {
+        Some((handle, disc)) if disc.len() == 4 && disc.chars().all(|c| c.is_ascii_digit()) => {
+            handle.to_string()
+        }
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(author_id: &str, author_name: &str) -> Message {
+        Message {
+            message_id: "1".to_string(),
+            author_name: author_name.to_string(),
+            author_id: author_id.to_string(),
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_conflicting_names_across_files() {
+        let mut tracker = ConflictTracker::default();
+        tracker.record("a.json", &[msg("1", "alice"), msg("2", "bob")]);
+        tracker.record("b.json", &[msg("1", "mallory"), msg("2", "Bob#1234")]);
+
+        let conflicts = tracker.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].author_id, "1");
+        assert_eq!(conflicts[0].names.len(), 2);
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("Alice#0001"), "alice");
+        assert_eq!(normalize_name("alice#dev"), "alice#dev");
+    }
+}
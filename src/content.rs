@@ -0,0 +1,615 @@
+use crate::message::Message;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Why a message carries no analyzable text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlankKind {
+    Empty,
+    Whitespace,
+    AttachmentOnly,
+}
+
+/// Tally of messages without analyzable text, by kind.
+#[derive(Serialize, JsonSchema, Debug, Default, Clone, Copy)]
+pub struct BlankCounts {
+    pub empty: usize,
+    pub whitespace_only: usize,
+    pub attachment_only: usize,
+}
+
+impl BlankCounts {
+    pub fn add(&mut self, kind: BlankKind) {
+        match kind {
+            BlankKind::Empty => self.empty += 1,
+            BlankKind::Whitespace => self.whitespace_only += 1,
+            BlankKind::AttachmentOnly => self.attachment_only += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.empty + self.whitespace_only + self.attachment_only
+    }
+}
+
+const ATTACHMENT_HOSTS: &[&str] = &[
+    "https://cdn.discordapp.com/attachments/",
+    "https://media.discordapp.net/attachments/",
+];
+
+/// Classifies content that has no words to analyze. Returns `None` for
+/// ordinary text messages.
+pub fn classify_blank(content: &str) -> Option<BlankKind> {
+    if content.is_empty() {
+        return Some(BlankKind::Empty);
+    }
+    if content.trim().is_empty() {
+        return Some(BlankKind::Whitespace);
+    }
+    let attachment_only = content
+        .split_whitespace()
+        .all(|w| ATTACHMENT_HOSTS.iter().any(|host| w.starts_with(host)));
+    if attachment_only {
+        return Some(BlankKind::AttachmentOnly);
+    }
+    None
+}
+
+/// `content` without Markdown blockquotes: `> ` lines, and everything from a
+/// `>>> ` line on. Quoting is how replies carry the words they answer, which
+/// would otherwise be credited to the replier.
+pub fn strip_quotes(content: &str) -> String {
+    let mut kept = Vec::new();
+    for line in content.lines() {
+        let line_start = line.trim_start();
+        if line_start == ">>>" || line_start.starts_with(">>> ") {
+            break;
+        }
+        if line_start == ">" || line_start.starts_with("> ") {
+            continue;
+        }
+        kept.push(line);
+    }
+    kept.join("\n")
+}
+
+/// Confidence [`language`] needs before it names a language.
+pub const MIN_LANGUAGE_CONFIDENCE: f64 = 0.3;
+
+/// `http(s)://` and `www.` links, including `<...>`-wrapped ones that
+/// suppress embeds.
+static URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<?\b(?:https?://|www\.)\S+").expect("URL pattern is valid"));
+/// Raw user, role and channel mention tokens, and `@handle` words.
+static MENTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"<@[!&]?\d+>|<#\d+>|(^|\s)@[\p{L}\p{N}_][\p{L}\p{N}_.]*")
+        .expect("mention pattern is valid")
+});
+/// Paired Discord markdown markers around their text: bold, underline,
+/// strikethrough and spoilers, in the order they are removed so nested
+/// markers (`**__both__**`) come off one after another.
+static MARKDOWN: LazyLock<[Regex; 4]> = LazyLock::new(|| {
+    [r"\*\*", "__", "~~", r"\|\|"].map(|marker| {
+        Regex::new(&format!(r"(?s){0}(.+?){0}", marker)).expect("markdown pattern is valid")
+    })
+});
+/// Spoilered text with its `||` markers.
+static SPOILER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)\|\|.+?\|\|").expect("spoiler pattern is valid"));
+/// Raw user mention tags: `<@id>`, or `<@!id>` when sent by nickname.
+static USER_MENTION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<@!?(\d+)>").expect("user mention pattern is valid"));
+/// Raw channel references: `<#id>`.
+static CHANNEL_REF: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<#(\d+)>").expect("channel reference pattern is valid"));
+/// `#channel-name` words with at least one letter, so `#1` stays a number.
+static HASHTAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|\s)#([\p{N}_-]*\p{L}[\p{L}\p{N}_-]*)").expect("hashtag pattern is valid")
+});
+/// Fenced blocks (an unclosed fence runs to the end) and inline code.
+static CODE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)```.*?(?:```|\z)|``[^`]+``|`[^`\n]+`").expect("code pattern is valid")
+});
+/// Unicode emoji with their modifiers and joiners, and custom emoji as
+/// `<:name:id>` tokens or `:name:` shortcodes.
+static EMOJI: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"<a?:\w+:\d+>|(^|\s):[\w+-]+:|[\p{Extended_Pictographic}\p{Emoji_Modifier}\p{Regional_Indicator}\x{FE0F}\x{200D}\x{20E3}]",
+    )
+    .expect("emoji pattern is valid")
+});
+
+/// Custom Discord emoji markup: `<:name:id>`, or `<a:name:id>` when
+/// animated.
+static CUSTOM_EMOJI: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<(a?):(\w+):(\d+)>").expect("custom emoji pattern is valid"));
+/// One Unicode emoji with its modifiers, variation selectors and joined
+/// parts, or a flag (two regional indicators).
+static EMOJI_SEQUENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\p{Regional_Indicator}{2}|\p{Extended_Pictographic}[\x{FE0F}\p{Emoji_Modifier}]*(?:\x{200D}\p{Extended_Pictographic}[\x{FE0F}\p{Emoji_Modifier}]*)*",
+    )
+    .expect("emoji sequence pattern is valid")
+});
+/// Pictographs shown as emoji by default; others (`©`, `‼`) only count when
+/// followed by a modifier or variation selector.
+static EMOJI_PRESENTATION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\p{Emoji_Presentation}").expect("emoji presentation pattern is valid")
+});
+
+/// The Unicode emoji in `content`, each reduced to its base codepoint: skin
+/// tones, variation selectors and joined parts are dropped, so `👍🏽` counts
+/// as `👍` and `👨‍💻` as `👨`. Flags are kept whole. Custom emoji are not
+/// included.
+pub fn emoji(content: &str) -> Vec<String> {
+    EMOJI_SEQUENCE
+        .find_iter(content)
+        .filter_map(|found| {
+            let sequence = found.as_str();
+            let mut chars = sequence.chars();
+            let base = chars.next()?;
+            if sequence.len() > base.len_utf8() && is_regional_indicator(base) {
+                return Some(sequence.to_string());
+            }
+            let decorated = chars.next().is_some();
+            (decorated || EMOJI_PRESENTATION.is_match(sequence)).then(|| base.to_string())
+        })
+        .collect()
+}
+
+/// A custom emoji used in a message.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CustomEmoji<'a> {
+    pub name: &'a str,
+    pub id: &'a str,
+    pub animated: bool,
+}
+
+/// The custom emoji in `content`, in order.
+pub fn custom_emoji(content: &str) -> Vec<CustomEmoji<'_>> {
+    CUSTOM_EMOJI
+        .captures_iter(content)
+        .map(|caps| CustomEmoji {
+            animated: !caps[1].is_empty(),
+            name: caps.get(2).map_or("", |m| m.as_str()),
+            id: caps.get(3).map_or("", |m| m.as_str()),
+        })
+        .collect()
+}
+
+/// How often a custom emoji was used. Emoji are told apart by ID, since
+/// one can be renamed; `name` is the first name seen.
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct CustomEmojiCount {
+    pub name: String,
+    pub id: String,
+    pub animated: bool,
+    pub count: usize,
+}
+
+/// Custom emoji counts by ID.
+#[derive(Debug, Clone, Default)]
+pub struct CustomEmojiTally(HashMap<String, CustomEmojiCount>);
+
+impl CustomEmojiTally {
+    pub fn record(&mut self, content: &str) {
+        for emoji in custom_emoji(content) {
+            self.add(emoji.name, emoji.id, emoji.animated, 1);
+        }
+    }
+
+    /// Adds counts from another tally's output.
+    pub fn merge(&mut self, counts: &[CustomEmojiCount]) {
+        for c in counts {
+            self.add(&c.name, &c.id, c.animated, c.count);
+        }
+    }
+
+    fn add(&mut self, name: &str, id: &str, animated: bool, count: usize) {
+        self.0
+            .entry(id.to_string())
+            .or_insert_with(|| CustomEmojiCount {
+                name: name.to_string(),
+                id: id.to_string(),
+                animated,
+                count: 0,
+            })
+            .count += count;
+    }
+
+    /// Most used first.
+    pub fn into_vec(self) -> Vec<CustomEmojiCount> {
+        let mut counts: Vec<CustomEmojiCount> = self.0.into_values().collect();
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.id.cmp(&b.id)));
+        counts
+    }
+}
+
+/// `content` with bold, underline, strikethrough and spoiler markers
+/// removed and their text kept, so `||secret||` reads as `secret`.
+pub fn strip_markdown(content: &str) -> Cow<'_, str> {
+    let mut text = Cow::Borrowed(content);
+    for re in MARKDOWN.iter() {
+        if re.is_match(&text) {
+            text = Cow::Owned(re.replace_all(&text, "${1}").into_owned());
+        }
+    }
+    text
+}
+
+/// `content` without spoilered text (`||...||`).
+pub fn strip_spoilers(content: &str) -> String {
+    SPOILER.replace_all(content, " ").into_owned()
+}
+
+/// `content` without custom emoji markup, which would otherwise be counted
+/// as words like `pepe123456789`.
+pub fn strip_custom_emoji(content: &str) -> Cow<'_, str> {
+    CUSTOM_EMOJI.replace_all(content, " ")
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// Replaces every match with a space, keeping the whitespace a `(^|\s)`
+/// group consumed, so neighbouring words never run together.
+fn blank_out(re: &Regex, content: &str) -> String {
+    re.replace_all(content, "$1 ").into_owned()
+}
+
+/// `content` without links, which would otherwise split into fragments
+/// like `https`, `com` and path segments.
+pub fn strip_urls(content: &str) -> String {
+    blank_out(&URL, content)
+}
+
+/// The links in `content`, without `<...>` wrapping or the punctuation
+/// that follows them in a sentence.
+pub fn urls(content: &str) -> Vec<&str> {
+    URL.find_iter(content)
+        .map(|found| {
+            found
+                .as_str()
+                .trim_start_matches('<')
+                .trim_end_matches(['>', ')', ']', '.', ',', '!', '?', ';', ':', '"', '\''])
+        })
+        .filter(|url| !url.is_empty())
+        .collect()
+}
+
+/// The host a link points at, lowercased and without `www.`, user info or
+/// a port.
+pub fn url_domain(url: &str) -> Option<String> {
+    let lower = url.to_ascii_lowercase();
+    let rest = ["https://", "http://"]
+        .iter()
+        .find_map(|scheme| lower.strip_prefix(scheme))
+        .unwrap_or(&lower);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+/// `content` without mentions: raw `<@id>`, `<@&id>` and `<#id>` tokens,
+/// `@handle` words and rendered mentions of the given display names
+/// (`@Deleted User`).
+pub fn strip_mentions(content: &str, names: &[&str]) -> String {
+    let mut rest = content.to_string();
+    for name in names.iter().filter(|name| !name.trim().is_empty()) {
+        rest = rest.replace(&format!("@{}", name), " ");
+    }
+    blank_out(&MENTION, &rest)
+}
+
+/// `content` without Markdown code: fenced blocks and inline spans.
+pub fn strip_code_blocks(content: &str) -> String {
+    blank_out(&CODE, content)
+}
+
+/// `content` without Unicode or custom emoji.
+pub fn strip_emoji(content: &str) -> String {
+    blank_out(&EMOJI, content)
+}
+
+/// Whether any word of `content` is a Discord attachment URL.
+pub fn has_attachment_link(content: &str) -> bool {
+    content
+        .split_whitespace()
+        .any(|w| ATTACHMENT_HOSTS.iter().any(|host| w.starts_with(host)))
+}
+
+/// Mentions found in one message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MentionStats {
+    pub mentions: usize,
+    /// The message has at least one mention and no other words.
+    pub mention_only: bool,
+}
+
+/// Counts raw mention tokens (`<@id>`, `<@!id>`, `<@&id>`), `@handle` words
+/// and rendered mentions of the given display names (`@Deleted User`).
+pub fn mention_stats(content: &str, names: &[&str]) -> MentionStats {
+    let mut rest = content.to_string();
+    let mut mentions = 0;
+    for name in names.iter().filter(|name| !name.trim().is_empty()) {
+        let rendered = format!("@{}", name);
+        let found = rest.matches(&rendered).count();
+        if found > 0 {
+            mentions += found;
+            rest = rest.replace(&rendered, " ");
+        }
+    }
+
+    let mut words = Vec::new();
+    for word in rest.split_whitespace() {
+        if is_mention_token(word) {
+            mentions += 1;
+        } else {
+            words.push(word);
+        }
+    }
+    let has_text = words
+        .iter()
+        .any(|word| word.chars().any(char::is_alphanumeric));
+    MentionStats {
+        mentions,
+        mention_only: mentions > 0 && !has_text,
+    }
+}
+
+/// The user IDs tagged in `content` with `<@id>` or `<@!id>`, in order and
+/// without repeats. Role tags (`<@&id>`) are not included.
+pub fn user_mention_ids(content: &str) -> Vec<&str> {
+    let mut ids: Vec<&str> = Vec::new();
+    for caps in USER_MENTION.captures_iter(content) {
+        let id = caps.get(1).map_or("", |m| m.as_str());
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+/// The channel IDs referenced in `content` with `<#id>`, in order.
+pub fn channel_ref_ids(content: &str) -> Vec<&str> {
+    CHANNEL_REF
+        .captures_iter(content)
+        .filter_map(|caps| caps.get(1).map(|m| m.as_str()))
+        .collect()
+}
+
+/// The `#channel-name` words in `content`, lowercased and without the `#`.
+pub fn hashtags(content: &str) -> Vec<String> {
+    HASHTAG
+        .captures_iter(content)
+        .filter_map(|caps| caps.get(1).map(|m| m.as_str().to_lowercase()))
+        .collect()
+}
+
+/// The mentioned user's name and nickname, as they appear in rendered
+/// mentions.
+pub fn mentioned_names(msg: &Message) -> Vec<&str> {
+    [&msg.mentioned_user_name, &msg.mentioned_user_nickname]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect()
+}
+
+/// [`mention_stats`] for a message, treating its mentioned user's name and
+/// nickname as rendered mentions.
+pub fn message_mentions(msg: &Message) -> MentionStats {
+    mention_stats(&msg.content, &mentioned_names(msg))
+}
+
+/// The language `msg` is written in, detected once mentions, links, custom
+/// emoji and code are removed. `None` when the detection's confidence is
+/// below [`MIN_LANGUAGE_CONFIDENCE`], as it is for most messages of a few
+/// words.
+pub fn language(msg: &Message) -> Option<whatlang::Lang> {
+    let text = strip_mentions(&msg.content, &mentioned_names(msg));
+    let text = strip_code_blocks(&strip_urls(&text));
+    let info = whatlang::detect(&strip_custom_emoji(&text))?;
+    (info.confidence() >= MIN_LANGUAGE_CONFIDENCE).then(|| info.lang())
+}
+
+fn is_mention_token(word: &str) -> bool {
+    let word = word.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '>');
+    if let Some(inner) = word.strip_prefix("<@").and_then(|w| w.strip_suffix('>')) {
+        let id = inner.trim_start_matches(['!', '&']);
+        return !id.is_empty() && id.chars().all(|c| c.is_ascii_digit());
+    }
+    word.strip_prefix('@')
+        .is_some_and(|handle| handle.chars().next().is_some_and(char::is_alphanumeric))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_quotes() {
+        assert_eq!(
+            strip_quotes("> you were wrong\n>\nno I was not"),
+            "no I was not"
+        );
+        assert_eq!(strip_quotes("fine\n>>> all of\nthis is quoted"), "fine");
+        assert_eq!(
+            strip_quotes(">not a quote -> arrow"),
+            ">not a quote -> arrow"
+        );
+    }
+
+    #[test]
+    fn test_emoji_reduced_to_base() {
+        assert_eq!(
+            emoji("gg 👍🏽👍 <:pepe:123> 🇩🇪 👨‍👩‍👧 ❤️ ©2024 ‼ 1️⃣ 😂😂"),
+            ["👍", "👍", "🇩🇪", "👨", "❤", "😂", "😂"]
+        );
+        assert!(emoji("no emoji here :wave:").is_empty());
+    }
+
+    #[test]
+    fn test_custom_emoji() {
+        let content = "gg <:pepe:123> <a:party_blob:456> <:pepe:123> <:broken:>";
+        let found = custom_emoji(content);
+        assert_eq!(found.len(), 3);
+        assert_eq!(
+            found[1],
+            CustomEmoji {
+                name: "party_blob",
+                id: "456",
+                animated: true
+            }
+        );
+        assert_eq!(
+            strip_custom_emoji(content)
+                .split_whitespace()
+                .collect::<Vec<_>>(),
+            ["gg", "<:broken:>"]
+        );
+
+        let mut tally = CustomEmojiTally::default();
+        tally.record(content);
+        tally.record("<:pepe_renamed:123>");
+        let counts = tally.into_vec();
+        assert_eq!(counts.len(), 2);
+        assert_eq!((counts[0].name.as_str(), counts[0].count), ("pepe", 3));
+    }
+
+    #[test]
+    fn test_strip_spans() {
+        assert_eq!(
+            strip_urls("see https://example.com/a?b=1 and <www.test.org/x>, ok")
+                .split_whitespace()
+                .collect::<Vec<_>>(),
+            ["see", "and", "ok"]
+        );
+        assert_eq!(
+            strip_mentions(
+                "<@123> hi <@&9> in <#42> @mod @Deleted User bye a@b.c",
+                &["Deleted User"]
+            )
+            .split_whitespace()
+            .collect::<Vec<_>>(),
+            ["hi", "in", "bye", "a@b.c"]
+        );
+        assert_eq!(
+            strip_code_blocks("run `cargo test` then\n```rust\nfn main() {}\n```\ndone ```tail")
+                .split_whitespace()
+                .collect::<Vec<_>>(),
+            ["run", "then", "done"]
+        );
+        assert_eq!(
+            strip_emoji("gg 👍🏽 <:pepe:123> :wave: at 10:30 🇩🇪 👨‍👩‍👧 ❤️ end")
+                .split_whitespace()
+                .collect::<Vec<_>>(),
+            ["gg", "at", "10:30", "end"]
+        );
+    }
+
+    #[test]
+    fn test_strip_markdown() {
+        assert_eq!(
+            strip_markdown("**bold** __under__ ~~gone~~ ||secret|| **__both__** a*b"),
+            "bold under gone secret both a*b"
+        );
+        assert_eq!(strip_markdown("** not closed"), "** not closed");
+        assert_eq!(
+            strip_spoilers("it was ||the butler|| all along")
+                .split_whitespace()
+                .collect::<Vec<_>>(),
+            ["it", "was", "all", "along"]
+        );
+    }
+
+    #[test]
+    fn test_classify_blank() {
+        assert_eq!(classify_blank(""), Some(BlankKind::Empty));
+        assert_eq!(classify_blank(" \n\t"), Some(BlankKind::Whitespace));
+        assert_eq!(
+            classify_blank("https://cdn.discordapp.com/attachments/1/2/image.png"),
+            Some(BlankKind::AttachmentOnly)
+        );
+        assert_eq!(
+            classify_blank("look https://cdn.discordapp.com/attachments/1/2/a.png"),
+            None
+        );
+        assert_eq!(classify_blank("hello"), None);
+    }
+
+    #[test]
+    fn test_urls_and_domains() {
+        let content =
+            "see https://Example.com/a?b=1 and <www.test.org/x>, or http://u@cdn.net:8080.";
+        let found = urls(content);
+        assert_eq!(
+            found,
+            [
+                "https://Example.com/a?b=1",
+                "www.test.org/x",
+                "http://u@cdn.net:8080"
+            ]
+        );
+        let domains: Vec<String> = found.iter().filter_map(|url| url_domain(url)).collect();
+        assert_eq!(domains, ["example.com", "test.org", "cdn.net"]);
+        assert_eq!(url_domain("https://"), None);
+    }
+
+    #[test]
+    fn test_mention_stats() {
+        let names = ["Deleted User"];
+        assert_eq!(
+            mention_stats("<@123> <@!456>", &names),
+            MentionStats {
+                mentions: 2,
+                mention_only: true
+            }
+        );
+        assert_eq!(
+            mention_stats("@Deleted User!!", &names),
+            MentionStats {
+                mentions: 1,
+                mention_only: true
+            }
+        );
+        assert_eq!(
+            mention_stats("@Deleted User where did you go", &names),
+            MentionStats {
+                mentions: 1,
+                mention_only: false
+            }
+        );
+        assert_eq!(mention_stats("email me at a@b.c", &names).mentions, 0);
+        assert!(!mention_stats("", &names).mention_only);
+        assert_eq!(
+            user_mention_ids("<@123> hi <@!456> <@&789> <@123>"),
+            ["123", "456"]
+        );
+        let refs = "go to <#42> or #Off-Topic, not #1 or a#b";
+        assert_eq!(channel_ref_ids(refs), ["42"]);
+        assert_eq!(hashtags(refs), ["off-topic"]);
+    }
+
+    #[test]
+    fn test_language_ignores_mentions_and_links() {
+        let msg = |content: &str| Message {
+            content: content.to_string(),
+            ..Message::default()
+        };
+        assert_eq!(
+            language(&msg(
+                "<@123> je ne sais pas ce que tu veux dire https://x.fr/a"
+            )),
+            Some(whatlang::Lang::Fra)
+        );
+        assert_eq!(language(&msg("lol")), None);
+        assert_eq!(language(&msg("<@123>")), None);
+    }
+}
@@ -0,0 +1,153 @@
+//! `--cooccurrence`: a sparse word × word matrix counting how often two
+//! counted words appear near each other in one message, for graph or
+//! embedding analysis of the vocabulary used toward the target.
+//!
+//! Words co-occur when at most `--cooccurrence-window` positions apart in
+//! a message's counted words (after stopwords, `--stem` and `--lemmatize`;
+//! 0 takes the whole message). The matrix is symmetric, so each pair is
+//! written once with its words in alphabetical order, and a word never
+//! pairs with itself. A `.json` path gets the vocabulary with `[row,
+//! column, count]` triples indexing into it; any other path gets CSV rows
+//! of `word_a,word_b,count`. Most frequent pairs come first in both.
+
+use crate::paths;
+use crate::timeline::csv_field;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Default `--cooccurrence-window`.
+pub const DEFAULT_WINDOW: usize = 5;
+
+#[derive(Debug, Clone, Default)]
+pub struct Cooccurrence {
+    /// Keyed with the alphabetically first word first.
+    pairs: HashMap<(String, String), usize>,
+}
+
+impl Cooccurrence {
+    pub fn record(&mut self, words: &[String], window: usize) {
+        for (i, word) in words.iter().enumerate() {
+            let end = if window == 0 {
+                words.len()
+            } else {
+                (i + 1 + window).min(words.len())
+            };
+            for other in &words[i + 1..end] {
+                if other == word {
+                    continue;
+                }
+                let key = if word < other {
+                    (word.clone(), other.clone())
+                } else {
+                    (other.clone(), word.clone())
+                };
+                *self.pairs.entry(key).or_insert(0) += 1;
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &Cooccurrence) {
+        for (pair, count) in &other.pairs {
+            *self.pairs.entry(pair.clone()).or_insert(0) += count;
+        }
+    }
+
+    /// Renames every word, merging pairs that end up alike, as masking does.
+    pub fn map_words(&mut self, rename: impl Fn(&str) -> String) {
+        let mut mapped = Cooccurrence::default();
+        for ((a, b), count) in std::mem::take(&mut self.pairs) {
+            let (a, b) = (rename(&a), rename(&b));
+            if a == b {
+                continue;
+            }
+            let key = if a < b { (a, b) } else { (b, a) };
+            *mapped.pairs.entry(key).or_insert(0) += count;
+        }
+        *self = mapped;
+    }
+
+    /// Most frequent first, then alphabetical.
+    fn entries(&self) -> Vec<(&str, &str, usize)> {
+        let mut entries: Vec<(&str, &str, usize)> = self
+            .pairs
+            .iter()
+            .map(|((a, b), count)| (a.as_str(), b.as_str(), *count))
+            .collect();
+        entries.sort_by(|x, y| y.2.cmp(&x.2).then_with(|| (x.0, x.1).cmp(&(y.0, y.1))));
+        entries
+    }
+
+    fn to_csv(&self) -> String {
+        let mut out = String::from("word_a,word_b,count\n");
+        for (a, b, count) in self.entries() {
+            out.push_str(&format!("{},{},{}\n", csv_field(a), csv_field(b), count));
+        }
+        out
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let entries = self.entries();
+        let mut index: BTreeMap<&str, usize> = BTreeMap::new();
+        for (a, b, _) in &entries {
+            index.insert(a, 0);
+            index.insert(b, 0);
+        }
+        for (i, position) in index.values_mut().enumerate() {
+            *position = i;
+        }
+        let triples: Vec<[usize; 3]> = entries
+            .iter()
+            .map(|(a, b, count)| [index[a], index[b], *count])
+            .collect();
+        json!({ "words": index.keys().collect::<Vec<_>>(), "entries": triples })
+    }
+}
+
+/// Writes CSV for a `.csv` path and JSON otherwise.
+pub fn write(matrix: &Cooccurrence, path: &Path) -> anyhow::Result<()> {
+    let data = if paths::is_csv(path) {
+        matrix.to_csv()
+    } else {
+        serde_json::to_string(&matrix.to_json())? + "\n"
+    };
+    fs::write(paths::extended(path), data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_window_and_sparse_formats() {
+        let mut matrix = Cooccurrence::default();
+        matrix.record(&words("miss you so much you"), 1);
+        let mut more = Cooccurrence::default();
+        more.record(&words("much miss"), 0);
+        matrix.merge(&more);
+        // "you you" is never a pair; "miss much" only pairs in the second
+        // message, with no window.
+        assert_eq!(
+            matrix.to_csv(),
+            "word_a,word_b,count\nmiss,much,1\nmiss,you,1\nmuch,so,1\nmuch,you,1\nso,you,1\n"
+        );
+
+        matrix.map_words(|word| {
+            if word == "so" {
+                "much".to_string()
+            } else {
+                word.to_string()
+            }
+        });
+        assert_eq!(matrix.pairs[&("much".to_string(), "you".to_string())], 2);
+        let json = matrix.to_json();
+        assert_eq!(json["words"], json!(["miss", "much", "you"]));
+        assert_eq!(json["entries"][0], json!([1, 2, 2]));
+    }
+}
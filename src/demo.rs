@@ -0,0 +1,70 @@
+//! `demo`: runs the analysis on a small synthetic export bundled into the
+//! binary, as a reference for the input schema and the output shape before
+//! any real data is at hand.
+//!
+//! The export covers the cases the analysis distinguishes: two deleted
+//! accounts, a reply without a ping, a bot, a pinned-message notice, roles,
+//! attachments and a blank message. All names and IDs are made up.
+
+use crate::checkpoint::RunState;
+use crate::input::Loader;
+use crate::locale::Locale;
+use crate::paths;
+use crate::pipeline::Pipeline;
+use crate::report::display_results;
+use crate::{summarize_state, write_output, AnalysisResult, Args};
+use anyhow::Context;
+use clap::Parser;
+use std::fs;
+use std::path::Path;
+
+/// The label the bundled export is reported under.
+const NAME: &str = "demo_export.json";
+const EXPORT: &str = include_str!("../data/demo_export.json");
+
+/// Analyzes the bundled export with the default options, like
+/// `--input demo_export.json` would.
+pub fn analyze(verbose: bool) -> anyhow::Result<(Pipeline, AnalysisResult)> {
+    let mut args = Args::try_parse_from(["deleted-user-analyzer", "--input", NAME])?;
+    args.verbose = verbose;
+    let pipeline = Pipeline::from_args(&args)?;
+    // Strict, so the bundled export is known to use only schema fields.
+    let mut loader = Loader::new(true);
+    let raw_msgs = loader.parse_raw_messages(NAME, EXPORT.as_bytes().to_vec())?;
+    let mut state = RunState::new(&args);
+    pipeline.ingest(&mut state, NAME, raw_msgs);
+    let mut result = summarize_state(&args, &pipeline, state, &loader);
+    pipeline.sanitizer.apply(&mut result);
+    Ok((pipeline, result))
+}
+
+pub fn run(output: Option<&Path>, verbose: bool, export: Option<&Path>) -> anyhow::Result<()> {
+    if let Some(path) = export {
+        fs::write(paths::extended(path), EXPORT)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        println!("Demo export saved to: {}", path.display());
+    }
+
+    let (pipeline, mut result) = analyze(verbose)?;
+    display_results(&result, verbose, &Locale::default());
+    if let Some(path) = output {
+        write_output(&mut result, path, &pipeline.output)?;
+        println!("Results saved to: {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demo_export_analyzes() {
+        let (_, result) = analyze(false).unwrap();
+        assert_eq!(result.total_messages, 21);
+        assert_eq!(result.messages_to_deleted_users, 19);
+        assert_eq!(result.unique_authors, 5);
+        assert_eq!(result.reply_only_matches, 1);
+        assert!(result.warnings.is_empty());
+    }
+}
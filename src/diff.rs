@@ -0,0 +1,209 @@
+//! `diff-exports`: compares two exports of the same channels by message ID,
+//! to check that a re-export is complete.
+
+use crate::input::{self, Loader};
+use crate::message::{FallbackStats, Message};
+use crate::paths;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Examples of each kind of difference printed to the console.
+const CONSOLE_EXAMPLES: usize = 10;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AuthorRef {
+    pub author_id: String,
+    pub author_name: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EditedMessage {
+    pub message_id: String,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ExportDiff {
+    pub messages_a: usize,
+    pub messages_b: usize,
+    /// Message IDs in the first export only.
+    pub only_in_a: Vec<String>,
+    /// Message IDs in the second export only.
+    pub only_in_b: Vec<String>,
+    /// Messages in both whose content differs.
+    pub edited: Vec<EditedMessage>,
+    /// Authors with messages in only one of the exports.
+    pub authors_only_in_a: Vec<AuthorRef>,
+    pub authors_only_in_b: Vec<AuthorRef>,
+}
+
+impl ExportDiff {
+    pub fn is_empty(&self) -> bool {
+        self.only_in_a.is_empty()
+            && self.only_in_b.is_empty()
+            && self.edited.is_empty()
+            && self.authors_only_in_a.is_empty()
+            && self.authors_only_in_b.is_empty()
+    }
+}
+
+/// Messages by ID; a repeated ID keeps its last occurrence.
+fn load(path: &Path) -> anyhow::Result<BTreeMap<String, Message>> {
+    let mut loader = Loader::new(false);
+    let mut fallbacks = FallbackStats::default();
+    let mut msgs = BTreeMap::new();
+    for file in input::expand_inputs(&[path.to_path_buf()])? {
+        for raw in loader.read_raw_messages(&file)? {
+            let msg = raw.into_message(&mut fallbacks);
+            msgs.insert(msg.message_id.clone(), msg);
+        }
+    }
+    Ok(msgs)
+}
+
+pub fn diff(a: &BTreeMap<String, Message>, b: &BTreeMap<String, Message>) -> ExportDiff {
+    let only = |from: &BTreeMap<String, Message>, other: &BTreeMap<String, Message>| {
+        from.keys()
+            .filter(|id| !other.contains_key(*id))
+            .cloned()
+            .collect()
+    };
+    let edited = a
+        .iter()
+        .filter_map(|(id, before)| {
+            let after = b.get(id)?;
+            (before.content != after.content).then(|| EditedMessage {
+                message_id: id.clone(),
+                before: before.content.clone(),
+                after: after.content.clone(),
+            })
+        })
+        .collect();
+
+    let authors = |msgs: &BTreeMap<String, Message>| -> BTreeMap<String, String> {
+        msgs.values()
+            .map(|msg| (msg.author_id.clone(), msg.author_name.clone()))
+            .collect()
+    };
+    let (authors_a, authors_b) = (authors(a), authors(b));
+    let only_authors = |from: &BTreeMap<String, String>, other: &BTreeMap<String, String>| {
+        from.iter()
+            .filter(|(id, _)| !other.contains_key(*id))
+            .map(|(id, name)| AuthorRef {
+                author_id: id.clone(),
+                author_name: name.clone(),
+            })
+            .collect()
+    };
+
+    ExportDiff {
+        messages_a: a.len(),
+        messages_b: b.len(),
+        only_in_a: only(a, b),
+        only_in_b: only(b, a),
+        edited,
+        authors_only_in_a: only_authors(&authors_a, &authors_b),
+        authors_only_in_b: only_authors(&authors_b, &authors_a),
+    }
+}
+
+pub fn run(a: &Path, b: &Path, output: Option<&Path>) -> anyhow::Result<()> {
+    let result = diff(&load(a)?, &load(b)?);
+    let (a, b) = (a.display(), b.display());
+
+    println!("\nEXPORT DIFF");
+    println!("===========");
+    println!("{}: {} messages", a, result.messages_a);
+    println!("{}: {} messages", b, result.messages_b);
+    if result.is_empty() {
+        println!("The exports contain the same messages with the same content.");
+    }
+    print_ids(&format!("Only in {}", a), &result.only_in_a);
+    print_ids(&format!("Only in {}", b), &result.only_in_b);
+    if !result.edited.is_empty() {
+        println!("\nContent differs: {}", result.edited.len());
+        for edit in result.edited.iter().take(CONSOLE_EXAMPLES) {
+            println!(
+                "   {}: {:?} -> {:?}",
+                edit.message_id, edit.before, edit.after
+            );
+        }
+    }
+    print_authors(&format!("Authors only in {}", a), &result.authors_only_in_a);
+    print_authors(&format!("Authors only in {}", b), &result.authors_only_in_b);
+
+    if let Some(path) = output {
+        fs::write(
+            paths::extended(path),
+            serde_json::to_string_pretty(&result)?,
+        )?;
+        println!("\nDiff saved to: {}", path.display());
+    }
+    Ok(())
+}
+
+fn print_ids(label: &str, ids: &[String]) {
+    if ids.is_empty() {
+        return;
+    }
+    println!("\n{}: {}", label, ids.len());
+    for id in ids.iter().take(CONSOLE_EXAMPLES) {
+        println!("   {}", id);
+    }
+    if ids.len() > CONSOLE_EXAMPLES {
+        println!("   ... and {} more", ids.len() - CONSOLE_EXAMPLES);
+    }
+}
+
+fn print_authors(label: &str, authors: &[AuthorRef]) {
+    if authors.is_empty() {
+        return;
+    }
+    println!("\n{}: {}", label, authors.len());
+    for author in authors.iter().take(CONSOLE_EXAMPLES) {
+        println!("   {} ({})", author.author_name, author.author_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(msgs: &[(&str, &str, &str)]) -> BTreeMap<String, Message> {
+        msgs.iter()
+            .map(|(id, author, content)| {
+                let msg = Message {
+                    message_id: id.to_string(),
+                    author_id: author.to_string(),
+                    author_name: format!("name-{}", author),
+                    content: content.to_string(),
+                    ..Message::default()
+                };
+                (id.to_string(), msg)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_exports() {
+        let a = export(&[("1", "10", "hi"), ("2", "10", "before"), ("3", "11", "bye")]);
+        let b = export(&[("1", "10", "hi"), ("2", "10", "after"), ("4", "12", "new")]);
+        let result = diff(&a, &b);
+        assert_eq!(result.only_in_a, ["3"]);
+        assert_eq!(result.only_in_b, ["4"]);
+        assert_eq!(
+            result.edited,
+            [EditedMessage {
+                message_id: "2".to_string(),
+                before: "before".to_string(),
+                after: "after".to_string(),
+            }]
+        );
+        assert_eq!(result.authors_only_in_a[0].author_id, "11");
+        assert_eq!(result.authors_only_in_b[0].author_id, "12");
+        assert!(diff(&a, &a).is_empty());
+    }
+}
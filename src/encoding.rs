@@ -0,0 +1,227 @@
+use std::fmt;
+
+/// Encoding an input file was decoded with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    /// Mostly UTF-8, with invalid sequences replaced by U+FFFD.
+    Utf8Lossy,
+    Latin1,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf8Lossy => "UTF-8 (lossy)",
+            TextEncoding::Latin1 => "Latin-1",
+            TextEncoding::Utf16Le => "UTF-16LE",
+            TextEncoding::Utf16Be => "UTF-16BE",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug)]
+pub struct DecodedText {
+    pub text: String,
+    pub encoding: TextEncoding,
+    /// Bytes (or JSON escapes) that could not be decoded and were replaced.
+    pub replaced: usize,
+}
+
+/// Decodes raw file bytes into a string, never failing.
+///
+/// Valid UTF-8 is used as is. UTF-16 is recognized by its byte order mark.
+/// Input without a single valid multi-byte UTF-8 sequence is assumed to be
+/// Latin-1; anything else is decoded as lossy UTF-8. Lone surrogate `\u`
+/// escapes, which `serde_json` rejects, are replaced as well.
+pub fn decode(bytes: Vec<u8>) -> DecodedText {
+    let (text, encoding, replaced) = if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        decode_utf16(rest, u16::from_le_bytes, TextEncoding::Utf16Le)
+    } else if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        decode_utf16(rest, u16::from_be_bytes, TextEncoding::Utf16Be)
+    } else {
+        match String::from_utf8(bytes) {
+            Ok(mut text) => {
+                if text.starts_with('\u{FEFF}') {
+                    text.remove(0);
+                }
+                (text, TextEncoding::Utf8, 0)
+            }
+            Err(err) => {
+                let bytes = err.into_bytes();
+                if has_utf8_multibyte(&bytes) {
+                    let (text, replaced) = decode_utf8_lossy(&bytes);
+                    (text, TextEncoding::Utf8Lossy, replaced)
+                } else {
+                    let text = bytes.iter().map(|&b| b as char).collect();
+                    (text, TextEncoding::Latin1, 0)
+                }
+            }
+        }
+    };
+
+    let (text, escapes) = replace_lone_surrogate_escapes(text);
+    DecodedText {
+        text,
+        encoding,
+        replaced: replaced + escapes,
+    }
+}
+
+fn decode_utf16(
+    bytes: &[u8],
+    to_unit: fn([u8; 2]) -> u16,
+    encoding: TextEncoding,
+) -> (String, TextEncoding, usize) {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| to_unit([pair[0], pair[1]]))
+        .collect();
+    let mut replaced = bytes.len() % 2;
+    let text = char::decode_utf16(units)
+        .map(|unit| {
+            unit.unwrap_or_else(|_| {
+                replaced += 2;
+                char::REPLACEMENT_CHARACTER
+            })
+        })
+        .collect();
+    (text, encoding, replaced)
+}
+
+fn decode_utf8_lossy(bytes: &[u8]) -> (String, usize) {
+    let mut text = String::with_capacity(bytes.len());
+    let mut replaced = 0;
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        if !chunk.invalid().is_empty() {
+            text.push(char::REPLACEMENT_CHARACTER);
+            replaced += chunk.invalid().len();
+        }
+    }
+    (text, replaced)
+}
+
+/// Whether the bytes contain at least one well-formed multi-byte UTF-8 sequence.
+fn has_utf8_multibyte(bytes: &[u8]) -> bool {
+    bytes.windows(2).enumerate().any(|(i, pair)| {
+        let width = match pair[0] {
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return false,
+        };
+        bytes
+            .get(i..i + width)
+            .is_some_and(|seq| std::str::from_utf8(seq).is_ok())
+    })
+}
+
+/// Replaces `\uD800`-`\uDFFF` escapes that are not part of a valid
+/// surrogate pair with the `\uFFFD` escape.
+fn replace_lone_surrogate_escapes(text: String) -> (String, usize) {
+    if !text.contains("\\u") {
+        return (text, 0);
+    }
+
+    let bytes = text.as_bytes();
+    let mut out = String::with_capacity(text.len());
+    let mut replaced = 0;
+    let mut last = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' {
+            i += 1;
+            continue;
+        }
+        if bytes.get(i + 1) != Some(&b'u') {
+            // Skip the escaped character so `\\u` is not read as an escape.
+            i += 2;
+            continue;
+        }
+        let Some(unit) = hex_unit(bytes, i + 2) else {
+            i += 2;
+            continue;
+        };
+        match unit {
+            0xD800..=0xDBFF => {
+                let paired = bytes.get(i + 6) == Some(&b'\\')
+                    && bytes.get(i + 7) == Some(&b'u')
+                    && hex_unit(bytes, i + 8).is_some_and(|low| (0xDC00..=0xDFFF).contains(&low));
+                if paired {
+                    i += 12;
+                    continue;
+                }
+            }
+            0xDC00..=0xDFFF => {}
+            _ => {
+                i += 6;
+                continue;
+            }
+        }
+        out.push_str(&text[last..i]);
+        out.push_str("\\uFFFD");
+        replaced += 1;
+        i += 6;
+        last = i;
+    }
+    out.push_str(&text[last..]);
+    (out, replaced)
+}
+
+fn hex_unit(bytes: &[u8], start: usize) -> Option<u16> {
+    let digits = std::str::from_utf8(bytes.get(start..start + 4)?).ok()?;
+    u16::from_str_radix(digits, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_valid_utf8_with_bom() {
+        let decoded = decode("\u{FEFF}[\"héllo\"]".as_bytes().to_vec());
+        assert_eq!(decoded.text, "[\"héllo\"]");
+        assert_eq!(decoded.encoding, TextEncoding::Utf8);
+        assert_eq!(decoded.replaced, 0);
+    }
+
+    #[test]
+    fn test_decode_latin1() {
+        let decoded = decode(b"caf\xe9".to_vec());
+        assert_eq!(decoded.text, "café");
+        assert_eq!(decoded.encoding, TextEncoding::Latin1);
+    }
+
+    #[test]
+    fn test_decode_mixed_is_lossy() {
+        let decoded = decode(b"caf\xc3\xa9 and caf\xe9".to_vec());
+        assert_eq!(decoded.text, "café and caf\u{FFFD}");
+        assert_eq!(decoded.encoding, TextEncoding::Utf8Lossy);
+        assert_eq!(decoded.replaced, 1);
+    }
+
+    #[test]
+    fn test_decode_utf16le() {
+        let decoded = decode(vec![0xFF, 0xFE, b'h', 0, b'i', 0]);
+        assert_eq!(decoded.text, "hi");
+        assert_eq!(decoded.encoding, TextEncoding::Utf16Le);
+    }
+
+    #[test]
+    fn test_lone_surrogate_escapes_replaced() {
+        let input = r#"["\ud83d", "\ud83d\ude00", "\\ud83d", "\udc00x"]"#;
+        let decoded = decode(input.as_bytes().to_vec());
+        assert_eq!(
+            decoded.text,
+            r#"["\uFFFD", "\ud83d\ude00", "\\ud83d", "\uFFFDx"]"#
+        );
+        assert_eq!(decoded.replaced, 2);
+        let parsed: Vec<String> = serde_json::from_str(&decoded.text).unwrap();
+        assert_eq!(parsed, vec!["\u{FFFD}", "😀", "\\ud83d", "\u{FFFD}x"]);
+    }
+}
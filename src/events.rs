@@ -0,0 +1,241 @@
+//! `--events`: known events (server drama, raids, announcements) whose
+//! surrounding messages are bucketed apart from the baseline, or dropped
+//! with `--exclude-events`.
+
+use crate::filter::{parse_since, parse_until};
+use crate::message::Message;
+use crate::paths;
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Default for `--event-window`, in hours on each side of an event.
+pub const DEFAULT_WINDOW_HOURS: u32 = 24;
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub label: String,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+}
+
+impl Event {
+    fn contains(&self, ts: DateTime<Utc>) -> bool {
+        self.window_start <= ts && ts <= self.window_end
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Events {
+    events: Vec<Event>,
+    exclude: bool,
+}
+
+impl Events {
+    pub fn load(path: &Path, window_hours: u32, exclude: bool, tz: Tz) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(paths::extended(path))
+            .with_context(|| format!("failed to read events file {}", path.display()))?;
+        let events = parse_events(&text, Duration::hours(window_hours.into()), tz)
+            .with_context(|| format!("failed to parse events file {}", path.display()))?;
+        Ok(Events { events, exclude })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Whether `--exclude-events` drops messages.
+    pub fn is_excluding(&self) -> bool {
+        self.exclude && !self.is_empty()
+    }
+
+    /// Whether `--exclude-events` drops this message.
+    pub fn excludes(&self, msg: &Message) -> bool {
+        self.exclude && self.in_any_window(msg)
+    }
+
+    fn in_any_window(&self, msg: &Message) -> bool {
+        msg.parsed_timestamp()
+            .is_some_and(|ts| self.events.iter().any(|event| event.contains(ts)))
+    }
+
+    /// A tally for the buckets, or `None` when there is nothing to bucket.
+    pub fn tally(&self) -> Option<EventTally<'_>> {
+        if self.is_empty() || self.exclude {
+            return None;
+        }
+        Some(EventTally {
+            events: self,
+            buckets: vec![Bucket::default(); self.events.len()],
+            baseline: Bucket::default(),
+        })
+    }
+}
+
+/// One event per line: a date (`YYYY-MM-DD`, the whole day in `tz`) or an
+/// RFC 3339 time, then an optional description. Lines starting with `#` are
+/// comments.
+fn parse_events(text: &str, window: Duration, tz: Tz) -> anyhow::Result<Vec<Event>> {
+    let mut events = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (when, description) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let start =
+            parse_since(when, tz).map_err(|err| anyhow::anyhow!("line {}: {}", i + 1, err))?;
+        let end =
+            parse_until(when, tz).map_err(|err| anyhow::anyhow!("line {}: {}", i + 1, err))?;
+        let description = description.trim();
+        events.push(Event {
+            label: if description.is_empty() {
+                when.to_string()
+            } else {
+                format!("{} ({})", description, when)
+            },
+            window_start: start - window,
+            window_end: end + window,
+        });
+    }
+    Ok(events)
+}
+
+#[derive(Debug, Clone, Default)]
+struct Bucket {
+    messages: usize,
+    authors: HashSet<String>,
+}
+
+impl Bucket {
+    fn record(&mut self, msg: &Message) {
+        self.messages += 1;
+        if !self.authors.contains(&msg.author_id) {
+            self.authors.insert(msg.author_id.clone());
+        }
+    }
+}
+
+/// Counts analyzed messages per event window. A message inside overlapping
+/// windows counts toward each; messages outside every window (or without a
+/// usable time) form the baseline.
+pub struct EventTally<'a> {
+    events: &'a Events,
+    buckets: Vec<Bucket>,
+    baseline: Bucket,
+}
+
+impl EventTally<'_> {
+    pub fn record(&mut self, msg: &Message) {
+        let mut in_window = false;
+        if let Some(ts) = msg.parsed_timestamp() {
+            for (event, bucket) in self.events.events.iter().zip(&mut self.buckets) {
+                if event.contains(ts) {
+                    bucket.record(msg);
+                    in_window = true;
+                }
+            }
+        }
+        if !in_window {
+            self.baseline.record(msg);
+        }
+    }
+
+    pub fn summary(self) -> EventSummary {
+        EventSummary {
+            events: self
+                .events
+                .events
+                .iter()
+                .zip(self.buckets)
+                .map(|(event, bucket)| EventBucket {
+                    label: event.label.clone(),
+                    window_start: event.window_start,
+                    window_end: event.window_end,
+                    messages: bucket.messages,
+                    authors: bucket.authors.len(),
+                })
+                .collect(),
+            baseline_messages: self.baseline.messages,
+            baseline_authors: self.baseline.authors.len(),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct EventBucket {
+    pub label: String,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub messages: usize,
+    pub authors: usize,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct EventSummary {
+    pub events: Vec<EventBucket>,
+    pub baseline_messages: usize,
+    pub baseline_authors: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(timestamp: &str, author_id: &str) -> Message {
+        Message {
+            timestamp: timestamp.to_string(),
+            author_id: author_id.to_string(),
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let events = parse_events(
+            "# known incidents\n2024-03-02 server drama\n\n2024-04-01T12:00:00Z\n",
+            Duration::hours(6),
+            Tz::UTC,
+        )
+        .unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].label, "server drama (2024-03-02)");
+        assert_eq!(
+            events[0].window_start.to_rfc3339(),
+            "2024-03-01T18:00:00+00:00"
+        );
+        assert_eq!(events[1].label, "2024-04-01T12:00:00Z");
+        assert!(parse_events("March 2nd drama", Duration::hours(1), Tz::UTC).is_err());
+    }
+
+    #[test]
+    fn test_bucket_and_exclude() {
+        let events = Events {
+            events: parse_events("2024-03-02 drama", Duration::hours(12), Tz::UTC).unwrap(),
+            exclude: false,
+        };
+        let mut tally = events.tally().unwrap();
+        tally.record(&at("2024-03-02T20:00:00Z", "1"));
+        tally.record(&at("2024-03-03T06:00:00Z", "2"));
+        tally.record(&at("2024-03-10T06:00:00Z", "1"));
+        tally.record(&at("", "3"));
+        let summary = tally.summary();
+        assert_eq!(summary.events[0].messages, 2);
+        assert_eq!(summary.events[0].authors, 2);
+        assert_eq!(summary.baseline_messages, 2);
+        assert!(!events.excludes(&at("2024-03-02T20:00:00Z", "1")));
+
+        let excluding = Events {
+            exclude: true,
+            ..events
+        };
+        assert!(excluding.tally().is_none());
+        assert!(excluding.excludes(&at("2024-03-02T20:00:00Z", "1")));
+        assert!(!excluding.excludes(&at("2024-03-10T06:00:00Z", "1")));
+    }
+}
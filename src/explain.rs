@@ -0,0 +1,63 @@
+//! `--explain-matches`: for every analyzed message, the target rules that
+//! selected it, to debug unexpected counts.
+
+use crate::message::Message;
+use crate::paths;
+use crate::target::{MatchReason, Target};
+use crate::timeline::csv_field;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MatchExplanation {
+    pub message_id: String,
+    pub author_id: String,
+    pub author_name: String,
+    pub reasons: Vec<MatchReason>,
+}
+
+/// One explanation per message, in input order.
+pub fn build(target: &Target, msgs: &[Message]) -> Vec<MatchExplanation> {
+    msgs.iter()
+        .map(|msg| MatchExplanation {
+            message_id: msg.message_id.clone(),
+            author_id: msg.author_id.clone(),
+            author_name: msg.author_name.clone(),
+            reasons: target.explain(msg),
+        })
+        .collect()
+}
+
+/// Writes CSV (one row per reason) for a `.csv` path and a JSON array
+/// otherwise.
+pub fn write(explanations: &[MatchExplanation], path: &Path) -> anyhow::Result<()> {
+    let data = if paths::is_csv(path) {
+        to_csv(explanations)
+    } else {
+        serde_json::to_string_pretty(explanations)?
+    };
+    fs::write(paths::extended(path), data)?;
+    Ok(())
+}
+
+fn to_csv(explanations: &[MatchExplanation]) -> String {
+    let mut out = String::from("message_id,author_id,author_name,target,field,value,rule\n");
+    for entry in explanations {
+        for reason in &entry.reasons {
+            let fields = [
+                entry.message_id.as_str(),
+                &entry.author_id,
+                &entry.author_name,
+                reason.target.as_deref().unwrap_or(""),
+                reason.field,
+                &reason.value,
+                &reason.rule,
+            ];
+            let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+            out.push_str(&row.join(","));
+            out.push('\n');
+        }
+    }
+    out
+}
@@ -0,0 +1,573 @@
+//! `--filter`: a small expression language evaluated per message, e.g.
+//! `author_id != "123" && timestamp >= 2022-01-01 && content ~ "ban"`.
+//!
+//! Comparisons are `field op value`, combined with `&&`, `||`, `!` and
+//! parentheses. Values are double-quoted strings (`\"` and `\\` escape) or
+//! bare words such as dates and numbers.
+
+use crate::filter::TimeBound;
+use crate::message::Message;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+
+/// Fields accepted on the left of a comparison.
+const FIELDS: &[&str] = &[
+    "message_id",
+    "author_id",
+    "author_name",
+    "author_nickname",
+    "content",
+    "timestamp",
+    "channel_id",
+    "channel_name",
+    "mentioned_user_name",
+    "mentioned_user_nickname",
+    "type",
+    "role",
+    "is_bot",
+    "is_webhook",
+    "length",
+    "attachments",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+    NoMatch,
+}
+
+impl Op {
+    fn symbol(&self) -> &'static str {
+        match self {
+            Op::Eq => "==",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Match => "~",
+            Op::NoMatch => "!~",
+        }
+    }
+
+    fn is_ordering(&self) -> bool {
+        matches!(self, Op::Lt | Op::Le | Op::Gt | Op::Ge)
+    }
+
+    fn compare<T: PartialOrd>(&self, left: T, right: T) -> bool {
+        match self {
+            Op::Eq => left == right,
+            Op::Ne => left != right,
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+            Op::Match | Op::NoMatch => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+fn lex(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        chars.next();
+        let next = chars.peek().copied();
+        let token = match (c, next) {
+            ('(', _) => Token::Open,
+            (')', _) => Token::Close,
+            ('&', Some('&')) => {
+                chars.next();
+                Token::And
+            }
+            ('|', Some('|')) => {
+                chars.next();
+                Token::Or
+            }
+            ('=', Some('=')) => {
+                chars.next();
+                Token::Op(Op::Eq)
+            }
+            ('!', Some('=')) => {
+                chars.next();
+                Token::Op(Op::Ne)
+            }
+            ('!', Some('~')) => {
+                chars.next();
+                Token::Op(Op::NoMatch)
+            }
+            ('!', _) => Token::Not,
+            ('<', Some('=')) => {
+                chars.next();
+                Token::Op(Op::Le)
+            }
+            ('>', Some('=')) => {
+                chars.next();
+                Token::Op(Op::Ge)
+            }
+            ('<', _) => Token::Op(Op::Lt),
+            ('>', _) => Token::Op(Op::Gt),
+            ('~', _) => Token::Op(Op::Match),
+            ('"', _) => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => value.push(escaped),
+                            None => return Err("unterminated string".to_string()),
+                        },
+                        Some(ch) => value.push(ch),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                Token::Str(value)
+            }
+            _ if is_word_char(c) => {
+                let mut word = c.to_string();
+                while let Some(&ch) = chars.peek().filter(|ch| is_word_char(**ch)) {
+                    word.push(ch);
+                    chars.next();
+                }
+                Token::Word(word)
+            }
+            _ => return Err(format!("unexpected `{}`", c)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !"()!&|<>=~\"".contains(c)
+}
+
+/// A parsed `--filter` expression.
+#[derive(Debug, Clone)]
+pub struct Expr(Node);
+
+#[derive(Debug, Clone)]
+enum Node {
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Cond(Cond),
+}
+
+/// One comparison, checked against its field's type when parsed.
+#[derive(Debug, Clone)]
+enum Cond {
+    Text {
+        field: String,
+        op: Op,
+        value: String,
+    },
+    Regex {
+        field: String,
+        re: Regex,
+        negate: bool,
+    },
+    /// Ordering on a numeric ID; non-numeric IDs never match.
+    Id {
+        field: String,
+        op: Op,
+        value: u64,
+    },
+    /// A date covers its whole day: `>= 2022-01-01` starts at midnight,
+    /// `<= 2022-01-01` runs to the end of the day and `==` means that day.
+    /// The bounds are UTC until [`Expr::in_timezone`] moves them.
+    Time {
+        op: Op,
+        bound: TimeBound,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+    Number {
+        field: String,
+        op: Op,
+        value: usize,
+    },
+    Bool {
+        field: String,
+        value: bool,
+        negate: bool,
+    },
+}
+
+/// Parses a `--filter` expression.
+pub fn parse(input: &str) -> Result<Expr, String> {
+    let tokens = lex(input)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let node = parser.or()?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(Expr(node)),
+        Some(token) => Err(format!(
+            "unexpected {} after the expression",
+            describe(token)
+        )),
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("`{}`", word),
+        Token::Str(value) => format!("\"{}\"", value),
+        Token::Op(op) => format!("`{}`", op.symbol()),
+        Token::And => "`&&`".to_string(),
+        Token::Or => "`||`".to_string(),
+        Token::Not => "`!`".to_string(),
+        Token::Open => "`(`".to_string(),
+        Token::Close => "`)`".to_string(),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.tokens.get(self.pos) == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        let mut left = self.and()?;
+        while self.eat(&Token::Or) {
+            left = Node::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Node, String> {
+        let mut left = self.unary()?;
+        while self.eat(&Token::And) {
+            left = Node::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Node::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let node = self.or()?;
+                if !self.eat(&Token::Close) {
+                    return Err("missing `)`".to_string());
+                }
+                Ok(node)
+            }
+            Some(Token::Word(field)) => {
+                let op = match self.next() {
+                    Some(Token::Op(op)) => op,
+                    other => {
+                        return Err(format!(
+                            "expected a comparison after `{}`, found {}",
+                            field,
+                            other.as_ref().map_or("the end".to_string(), describe)
+                        ))
+                    }
+                };
+                let value = match self.next() {
+                    Some(Token::Word(value) | Token::Str(value)) => value,
+                    other => {
+                        return Err(format!(
+                            "expected a value after `{} {}`, found {}",
+                            field,
+                            op.symbol(),
+                            other.as_ref().map_or("the end".to_string(), describe)
+                        ))
+                    }
+                };
+                Ok(Node::Cond(Cond::new(&field, op, value)?))
+            }
+            Some(token) => Err(format!("expected a comparison, found {}", describe(&token))),
+            None => Err("expected a comparison, found the end".to_string()),
+        }
+    }
+}
+
+impl Cond {
+    fn new(field: &str, op: Op, value: String) -> Result<Self, String> {
+        if !FIELDS.contains(&field) {
+            return Err(format!(
+                "unknown field `{}`; expected one of {}",
+                field,
+                FIELDS.join(", ")
+            ));
+        }
+        let field_name = field.to_string();
+        if matches!(op, Op::Match | Op::NoMatch) {
+            if matches!(
+                field,
+                "timestamp" | "is_bot" | "is_webhook" | "length" | "attachments"
+            ) {
+                return Err(format!(
+                    "`{}` needs a text field, not `{}`",
+                    op.symbol(),
+                    field
+                ));
+            }
+            let re = Regex::new(&value).map_err(|err| format!("invalid regex: {}", err))?;
+            return Ok(Cond::Regex {
+                field: field_name,
+                re,
+                negate: op == Op::NoMatch,
+            });
+        }
+        match field {
+            "timestamp" => {
+                let bound = TimeBound::parse(&value)?;
+                Ok(Cond::Time {
+                    op,
+                    bound,
+                    start: bound.start(Tz::UTC),
+                    end: bound.end(Tz::UTC),
+                })
+            }
+            "length" | "attachments" => {
+                let value = value
+                    .parse()
+                    .map_err(|_| format!("`{}` needs a number, not `{}`", field, value))?;
+                Ok(Cond::Number {
+                    field: field_name,
+                    op,
+                    value,
+                })
+            }
+            "is_bot" | "is_webhook" => {
+                let value = value
+                    .parse()
+                    .map_err(|_| format!("`{}` needs true or false, not `{}`", field, value))?;
+                if op.is_ordering() {
+                    return Err(format!(
+                        "`{}` cannot be compared with `{}`",
+                        field,
+                        op.symbol()
+                    ));
+                }
+                Ok(Cond::Bool {
+                    field: field_name,
+                    value,
+                    negate: op == Op::Ne,
+                })
+            }
+            "message_id" | "author_id" | "channel_id" if op.is_ordering() => {
+                let value = value
+                    .parse()
+                    .map_err(|_| format!("`{}` needs a numeric ID, not `{}`", field, value))?;
+                Ok(Cond::Id {
+                    field: field_name,
+                    op,
+                    value,
+                })
+            }
+            _ if op.is_ordering() => Err(format!(
+                "`{}` cannot be compared with `{}`; use ==, != or ~",
+                field,
+                op.symbol()
+            )),
+            _ => Ok(Cond::Text {
+                field: field_name,
+                op,
+                value,
+            }),
+        }
+    }
+
+    fn matches(&self, msg: &Message) -> bool {
+        match self {
+            Cond::Text { field, op, value } if field == "role" => {
+                let has = msg
+                    .author_roles
+                    .iter()
+                    .any(|role| role.eq_ignore_ascii_case(value));
+                has == (*op == Op::Eq)
+            }
+            Cond::Text { field, op, value } => op.compare(text(msg, field), value.as_str()),
+            Cond::Regex { field, re, negate } if field == "role" => {
+                msg.author_roles.iter().any(|role| re.is_match(role)) != *negate
+            }
+            Cond::Regex { field, re, negate } => re.is_match(text(msg, field)) != *negate,
+            Cond::Id { field, op, value } => text(msg, field)
+                .parse::<u64>()
+                .is_ok_and(|id| op.compare(id, *value)),
+            Cond::Time { op, start, end, .. } => {
+                msg.parsed_timestamp().is_some_and(|ts| match op {
+                    Op::Eq => *start <= ts && ts <= *end,
+                    Op::Ne => ts < *start || *end < ts,
+                    Op::Lt => ts < *start,
+                    Op::Le => ts <= *end,
+                    Op::Gt => ts > *end,
+                    Op::Ge => ts >= *start,
+                    Op::Match | Op::NoMatch => false,
+                })
+            }
+            Cond::Number { field, op, value } => {
+                let actual = match field.as_str() {
+                    "length" => msg.content.trim().chars().count(),
+                    _ => msg.attachment_count,
+                };
+                op.compare(actual, *value)
+            }
+            Cond::Bool {
+                field,
+                value,
+                negate,
+            } => {
+                let actual = match field.as_str() {
+                    "is_bot" => msg.is_bot,
+                    _ => msg.is_webhook,
+                };
+                (actual == *value) != *negate
+            }
+        }
+    }
+}
+
+/// A text field's value; missing optional fields read as empty.
+fn text<'a>(msg: &'a Message, field: &str) -> &'a str {
+    match field {
+        "message_id" => &msg.message_id,
+        "author_id" => &msg.author_id,
+        "author_name" => &msg.author_name,
+        "author_nickname" => &msg.author_nickname,
+        "content" => &msg.content,
+        "channel_id" => msg.channel_id.as_deref().unwrap_or(""),
+        "channel_name" => msg.channel_name.as_deref().unwrap_or(""),
+        "mentioned_user_name" => msg.mentioned_user_name.as_deref().unwrap_or(""),
+        "mentioned_user_nickname" => msg.mentioned_user_nickname.as_deref().unwrap_or(""),
+        "type" => msg.message_type.as_deref().unwrap_or(""),
+        _ => "",
+    }
+}
+
+impl Expr {
+    pub fn matches(&self, msg: &Message) -> bool {
+        self.0.matches(msg)
+    }
+
+    /// Reads the expression's dates as days in `tz`.
+    pub fn in_timezone(mut self, tz: Tz) -> Self {
+        self.0.set_timezone(tz);
+        self
+    }
+}
+
+impl Node {
+    fn set_timezone(&mut self, tz: Tz) {
+        match self {
+            Node::And(left, right) | Node::Or(left, right) => {
+                left.set_timezone(tz);
+                right.set_timezone(tz);
+            }
+            Node::Not(inner) => inner.set_timezone(tz),
+            Node::Cond(Cond::Time {
+                bound, start, end, ..
+            }) => {
+                *start = bound.start(tz);
+                *end = bound.end(tz);
+            }
+            Node::Cond(_) => {}
+        }
+    }
+
+    fn matches(&self, msg: &Message) -> bool {
+        match self {
+            Node::And(left, right) => left.matches(msg) && right.matches(msg),
+            Node::Or(left, right) => left.matches(msg) || right.matches(msg),
+            Node::Not(inner) => !inner.matches(msg),
+            Node::Cond(cond) => cond.matches(msg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(author_id: &str, timestamp: &str, content: &str) -> Message {
+        Message {
+            author_id: author_id.to_string(),
+            timestamp: timestamp.to_string(),
+            content: content.to_string(),
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_and_evaluate() {
+        let expr = parse(r#"author_id != "123" && timestamp >= 2022-01-01 && content ~ "(?i)ban""#)
+            .unwrap();
+        assert!(expr.matches(&msg("7", "2022-01-01T00:00:00Z", "BAN them")));
+        assert!(!expr.matches(&msg("123", "2022-06-01T00:00:00Z", "ban")));
+        assert!(!expr.matches(&msg("7", "2021-12-31T23:59:59Z", "ban")));
+        assert!(!expr.matches(&msg("7", "2022-06-01T00:00:00Z", "hello")));
+
+        let expr = parse("!(length < 5 || is_bot == true) && timestamp <= 2022-01-01").unwrap();
+        assert!(expr.matches(&msg("1", "2022-01-01T23:00:00Z", "long enough")));
+        assert!(!expr.matches(&msg("1", "2022-01-01T23:00:00Z", "tiny")));
+        assert!(!expr.matches(&msg("1", "2022-01-02T00:00:00Z", "long enough")));
+        assert!(!expr.matches(&msg("1", "", "long enough")));
+
+        let expr = parse("author_id > 100 || role == mod").unwrap();
+        assert!(expr.matches(&msg("101", "", "")));
+        assert!(!expr.matches(&msg("abc", "", "")));
+        let moderator = Message {
+            author_roles: vec!["Mod".to_string()],
+            ..msg("1", "", "")
+        };
+        assert!(expr.matches(&moderator));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (input, error) in [
+            ("author == 1", "unknown field `author`"),
+            ("content < \"a\"", "cannot be compared with `<`"),
+            ("timestamp >= yesterday", "neither RFC 3339"),
+            ("content ~ \"(\"", "invalid regex"),
+            ("(is_bot == true", "missing `)`"),
+            ("content == \"open", "unterminated string"),
+            ("length >= 3 length", "unexpected `length`"),
+            ("content", "expected a comparison after `content`"),
+        ] {
+            let err = parse(input).unwrap_err();
+            assert!(err.contains(error), "{}: {}", input, err);
+        }
+    }
+}
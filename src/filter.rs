@@ -0,0 +1,692 @@
+//! Message filters applied on top of the deleted-user target check.
+
+use crate::content;
+use crate::message::Message;
+use anyhow::Context;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use whatlang::Lang;
+
+/// Author IDs from `--authors`/`--authors-file` and `--exclude-authors`.
+#[derive(Debug, Default)]
+pub struct AuthorFilter {
+    /// When set, only these authors are analyzed.
+    pub allow: Option<HashSet<String>>,
+    pub block: HashSet<String>,
+}
+
+impl AuthorFilter {
+    pub fn new(
+        authors: &[String],
+        authors_file: Option<&str>,
+        exclude: &[String],
+    ) -> anyhow::Result<Self> {
+        let mut allow: Option<HashSet<String>> = None;
+        if !authors.is_empty() {
+            allow
+                .get_or_insert_with(HashSet::new)
+                .extend(authors.iter().map(|id| id.trim().to_string()));
+        }
+        if let Some(path) = authors_file {
+            allow
+                .get_or_insert_with(HashSet::new)
+                .extend(read_id_list(path)?);
+        }
+        Ok(AuthorFilter {
+            allow,
+            block: exclude.iter().map(|id| id.trim().to_string()).collect(),
+        })
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.allow.is_some() || !self.block.is_empty()
+    }
+
+    pub fn contains(&self, msg: &Message) -> bool {
+        self.allow
+            .as_ref()
+            .is_none_or(|allow| allow.contains(&msg.author_id))
+            && !self.block.contains(&msg.author_id)
+    }
+}
+
+/// Channels from `--channel`, matched against `channel_id` or
+/// `channel_name` (case-insensitive, leading `#` optional).
+#[derive(Debug, Default)]
+pub struct ChannelFilter(Vec<String>);
+
+impl ChannelFilter {
+    pub fn new(channels: &[String]) -> Self {
+        ChannelFilter(channels.iter().map(|c| normalize_channel(c)).collect())
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.0.is_empty()
+    }
+
+    /// Messages without channel fields never match a non-empty filter.
+    pub fn contains(&self, msg: &Message) -> bool {
+        if self.0.is_empty() {
+            return true;
+        }
+        [&msg.channel_id, &msg.channel_name]
+            .into_iter()
+            .flatten()
+            .any(|value| self.0.contains(&normalize_channel(value)))
+    }
+}
+
+fn normalize_channel(channel: &str) -> String {
+    channel.trim().trim_start_matches('#').to_lowercase()
+}
+
+/// `--role` / `--exclude-role`, matched case-insensitively against
+/// `author_roles`.
+#[derive(Debug, Default)]
+pub struct RoleFilter {
+    pub allow: Vec<String>,
+    pub block: Vec<String>,
+}
+
+impl RoleFilter {
+    pub fn new(roles: &[String], exclude: &[String]) -> Self {
+        let normalize = |roles: &[String]| roles.iter().map(|role| normalize_role(role)).collect();
+        RoleFilter {
+            allow: normalize(roles),
+            block: normalize(exclude),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.allow.is_empty() || !self.block.is_empty()
+    }
+
+    /// Messages without role data never match a non-empty `--role` list and
+    /// are never dropped by `--exclude-role`.
+    pub fn contains(&self, msg: &Message) -> bool {
+        let has = |list: &[String]| {
+            msg.author_roles
+                .iter()
+                .any(|role| list.contains(&normalize_role(role)))
+        };
+        (self.allow.is_empty() || has(&self.allow)) && !has(&self.block)
+    }
+}
+
+fn normalize_role(role: &str) -> String {
+    role.trim().trim_start_matches('@').to_lowercase()
+}
+
+/// `--only-with-attachments` / `--text-only`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentFilter {
+    #[default]
+    Any,
+    With,
+    Without,
+}
+
+impl AttachmentFilter {
+    pub fn contains(&self, msg: &Message) -> bool {
+        match self {
+            AttachmentFilter::Any => true,
+            AttachmentFilter::With => msg.has_attachments(),
+            AttachmentFilter::Without => !msg.has_attachments(),
+        }
+    }
+}
+
+/// `--content-match` / `--content-exclude` patterns, `--exclude-keyword-file`
+/// keywords and content length bounds.
+#[derive(Debug, Default)]
+pub struct ContentFilter {
+    pub include: Option<Regex>,
+    /// Messages matching any of these are dropped.
+    pub exclude: Vec<Regex>,
+    /// Bounds on the trimmed content length, in characters.
+    pub min_chars: Option<usize>,
+    pub max_chars: Option<usize>,
+}
+
+impl ContentFilter {
+    pub fn is_active(&self) -> bool {
+        self.include.is_some()
+            || !self.exclude.is_empty()
+            || self.min_chars.is_some()
+            || self.max_chars.is_some()
+    }
+
+    pub fn contains(&self, msg: &Message) -> bool {
+        self.length_ok(&msg.content)
+            && self
+                .include
+                .as_ref()
+                .is_none_or(|re| re.is_match(&msg.content))
+            && !self.exclude.iter().any(|re| re.is_match(&msg.content))
+    }
+
+    fn length_ok(&self, content: &str) -> bool {
+        if self.min_chars.is_none() && self.max_chars.is_none() {
+            return true;
+        }
+        let len = content.trim().chars().count();
+        self.min_chars.is_none_or(|min| len >= min) && self.max_chars.is_none_or(|max| len <= max)
+    }
+}
+
+/// Reads an `--exclude-keyword-file`: one keyword per line, `#` starting a
+/// comment line. Matches any of them as a case-insensitive substring, or
+/// `None` when the file lists none.
+pub fn read_keyword_file(path: &str) -> anyhow::Result<Option<Regex>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("failed to read keyword file {}", path))?;
+    Ok(keyword_regex(&text))
+}
+
+fn keyword_regex(text: &str) -> Option<Regex> {
+    let keywords: Vec<String> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(regex::escape)
+        .collect();
+    if keywords.is_empty() {
+        return None;
+    }
+    Regex::new(&format!("(?i){}", keywords.join("|"))).ok()
+}
+
+/// `--language`: only messages detected as this language.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LanguageFilter(pub Option<Lang>);
+
+impl LanguageFilter {
+    /// Detects languages like `--detect-languages`, through
+    /// [`content::language`]. Messages whose language cannot be detected
+    /// (too short, only mentions or emoji) are excluded when a language is
+    /// set.
+    pub fn contains(&self, msg: &Message) -> bool {
+        self.0
+            .is_none_or(|lang| content::language(msg) == Some(lang))
+    }
+}
+
+/// ISO 639-1 codes for the languages the detector knows; it names them by
+/// their ISO 639-3 codes.
+const TWO_LETTER_CODES: &[(&str, &str)] = &[
+    ("af", "afr"),
+    ("am", "amh"),
+    ("ar", "ara"),
+    ("az", "aze"),
+    ("be", "bel"),
+    ("bg", "bul"),
+    ("bn", "ben"),
+    ("ca", "cat"),
+    ("cs", "ces"),
+    ("cy", "cym"),
+    ("da", "dan"),
+    ("de", "deu"),
+    ("el", "ell"),
+    ("en", "eng"),
+    ("eo", "epo"),
+    ("es", "spa"),
+    ("et", "est"),
+    ("fa", "pes"),
+    ("fi", "fin"),
+    ("fr", "fra"),
+    ("gu", "guj"),
+    ("he", "heb"),
+    ("hi", "hin"),
+    ("hr", "hrv"),
+    ("hu", "hun"),
+    ("hy", "hye"),
+    ("id", "ind"),
+    ("it", "ita"),
+    ("ja", "jpn"),
+    ("jv", "jav"),
+    ("ka", "kat"),
+    ("km", "khm"),
+    ("kn", "kan"),
+    ("ko", "kor"),
+    ("la", "lat"),
+    ("lt", "lit"),
+    ("lv", "lav"),
+    ("mk", "mkd"),
+    ("ml", "mal"),
+    ("mr", "mar"),
+    ("my", "mya"),
+    ("nb", "nob"),
+    ("ne", "nep"),
+    ("nl", "nld"),
+    ("no", "nob"),
+    ("or", "ori"),
+    ("pa", "pan"),
+    ("pl", "pol"),
+    ("pt", "por"),
+    ("ro", "ron"),
+    ("ru", "rus"),
+    ("si", "sin"),
+    ("sk", "slk"),
+    ("sl", "slv"),
+    ("sn", "sna"),
+    ("sr", "srp"),
+    ("sv", "swe"),
+    ("ta", "tam"),
+    ("te", "tel"),
+    ("th", "tha"),
+    ("tk", "tuk"),
+    ("tl", "tgl"),
+    ("tr", "tur"),
+    ("uk", "ukr"),
+    ("ur", "urd"),
+    ("uz", "uzb"),
+    ("vi", "vie"),
+    ("yi", "yid"),
+    ("zh", "cmn"),
+    ("zu", "zul"),
+];
+
+/// Parses `--language`: an ISO 639-1 or 639-3 code, or an English name.
+pub fn parse_language(value: &str) -> Result<Lang, String> {
+    let value = value.trim().to_lowercase();
+    let code = TWO_LETTER_CODES
+        .iter()
+        .find(|(two, _)| *two == value)
+        .map_or(value.as_str(), |(_, three)| three);
+    Lang::from_code(code)
+        .or_else(|| {
+            Lang::all()
+                .iter()
+                .copied()
+                .find(|lang| lang.eng_name().eq_ignore_ascii_case(&value))
+        })
+        .ok_or_else(|| format!("`{}` is not a supported language code or name", value))
+}
+
+/// Reads a file of IDs, one per line, ignoring blank lines and `#` comments.
+pub fn read_id_list(path: impl AsRef<Path>) -> anyhow::Result<HashSet<String>> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("failed to read ID list {}", path.display()))?;
+    Ok(parse_id_list(&text))
+}
+
+pub fn parse_id_list(text: &str) -> HashSet<String> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Inclusive time window from `--since` / `--until`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DateWindow {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl DateWindow {
+    pub fn is_unbounded(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+
+    /// Messages without a usable timestamp fall outside any bounded window.
+    pub fn contains(&self, msg: &Message) -> bool {
+        if self.is_unbounded() {
+            return true;
+        }
+        let Some(ts) = msg.parsed_timestamp() else {
+            return false;
+        };
+        self.since.is_none_or(|since| ts >= since) && self.until.is_none_or(|until| ts <= until)
+    }
+}
+
+/// Exclusive snowflake range from `--after-id` / `--before-id`, for the
+/// slice of a channel between two known messages.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IdRange {
+    pub after: Option<u64>,
+    pub before: Option<u64>,
+}
+
+impl IdRange {
+    pub fn is_active(&self) -> bool {
+        self.after.is_some() || self.before.is_some()
+    }
+
+    /// Non-numeric message IDs fall outside any bounded range.
+    pub fn contains(&self, msg: &Message) -> bool {
+        if !self.is_active() {
+            return true;
+        }
+        let Ok(id) = msg.message_id.trim().parse::<u64>() else {
+            return false;
+        };
+        self.after.is_none_or(|after| id > after) && self.before.is_none_or(|before| id < before)
+    }
+}
+
+/// A time given on the command line or in a file: an RFC 3339 instant, or
+/// a `YYYY-MM-DD` date whose bounds depend on `--timezone`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeBound {
+    Instant(DateTime<Utc>),
+    Date(NaiveDate),
+}
+
+impl TimeBound {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+            return Ok(TimeBound::Instant(ts.with_timezone(&Utc)));
+        }
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map(TimeBound::Date)
+            .map_err(|_| format!("`{}` is neither RFC 3339 nor YYYY-MM-DD", value))
+    }
+
+    /// The instant, or the first moment of the date in `tz`.
+    pub fn start(&self, tz: Tz) -> DateTime<Utc> {
+        match self {
+            TimeBound::Instant(ts) => *ts,
+            TimeBound::Date(date) => start_of_day(*date, tz),
+        }
+    }
+
+    /// The instant, or the last moment of the date in `tz`, so `--until
+    /// 2024-03-31` includes all of March 31st.
+    pub fn end(&self, tz: Tz) -> DateTime<Utc> {
+        match self {
+            TimeBound::Instant(ts) => *ts,
+            TimeBound::Date(date) => match date.succ_opt() {
+                Some(next) => start_of_day(next, tz) - Duration::nanoseconds(1),
+                None => DateTime::<Utc>::MAX_UTC,
+            },
+        }
+    }
+}
+
+/// Midnight in `tz`, or the first valid time when a DST change skips it.
+fn start_of_day(date: NaiveDate, tz: Tz) -> DateTime<Utc> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .unwrap_or_else(|| tz.from_utc_datetime(&midnight))
+        .with_timezone(&Utc)
+}
+
+/// Parses `--since`-style values: RFC 3339, or the start of a date in `tz`.
+pub fn parse_since(value: &str, tz: Tz) -> Result<DateTime<Utc>, String> {
+    TimeBound::parse(value).map(|bound| bound.start(tz))
+}
+
+/// Parses `--until`-style values: RFC 3339, or the end of a date in `tz`.
+pub fn parse_until(value: &str, tz: Tz) -> Result<DateTime<Utc>, String> {
+    TimeBound::parse(value).map(|bound| bound.end(tz))
+}
+
+/// Parses `--timezone`: an IANA name such as `Europe/Berlin`.
+pub fn parse_timezone(value: &str) -> Result<Tz, String> {
+    value
+        .parse()
+        .map_err(|_| format!("`{}` is not an IANA time zone such as Europe/Berlin", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message_by(author_id: &str) -> Message {
+        Message {
+            author_id: author_id.to_string(),
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_author_filter_allow_and_block() {
+        let ids = |list: &[&str]| list.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        let filter = AuthorFilter::new(&ids(&["1", "2"]), None, &ids(&["2"])).unwrap();
+        assert!(filter.contains(&message_by("1")));
+        assert!(!filter.contains(&message_by("2")));
+        assert!(!filter.contains(&message_by("3")));
+
+        let filter = AuthorFilter::new(&[], None, &ids(&["3"])).unwrap();
+        assert!(filter.contains(&message_by("1")));
+        assert!(!filter.contains(&message_by("3")));
+    }
+
+    #[test]
+    fn test_channel_filter_matches_id_or_name() {
+        let filter = ChannelFilter::new(&["#General".to_string(), "42".to_string()]);
+        let in_channel = |id: Option<&str>, name: Option<&str>| Message {
+            channel_id: id.map(str::to_string),
+            channel_name: name.map(str::to_string),
+            ..Message::default()
+        };
+        assert!(filter.contains(&in_channel(Some("1"), Some("general"))));
+        assert!(filter.contains(&in_channel(Some("42"), None)));
+        assert!(!filter.contains(&in_channel(Some("7"), Some("random"))));
+        assert!(!filter.contains(&in_channel(None, None)));
+        assert!(ChannelFilter::default().contains(&in_channel(None, None)));
+    }
+
+    #[test]
+    fn test_role_filter() {
+        let list = |roles: &[&str]| roles.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+        let with_roles = |roles: &[&str]| Message {
+            author_roles: list(roles),
+            ..Message::default()
+        };
+        let filter = RoleFilter::new(&list(&["@Moderator"]), &list(&["muted"]));
+        assert!(filter.contains(&with_roles(&["Member", "moderator"])));
+        assert!(!filter.contains(&with_roles(&["Moderator", "Muted"])));
+        assert!(!filter.contains(&with_roles(&["Member"])));
+        assert!(!filter.contains(&with_roles(&[])));
+
+        let filter = RoleFilter::new(&[], &list(&["Muted"]));
+        assert!(filter.contains(&with_roles(&[])));
+        assert!(!filter.contains(&with_roles(&["muted"])));
+    }
+
+    #[test]
+    fn test_attachment_filter() {
+        let listed = Message {
+            attachment_count: 2,
+            content: "look".to_string(),
+            ..Message::default()
+        };
+        let linked = Message {
+            content: "https://cdn.discordapp.com/attachments/1/2/a.png lol".to_string(),
+            ..Message::default()
+        };
+        let text = Message {
+            content: "just words".to_string(),
+            ..Message::default()
+        };
+        for msg in [&listed, &linked] {
+            assert!(AttachmentFilter::With.contains(msg));
+            assert!(!AttachmentFilter::Without.contains(msg));
+        }
+        assert!(!AttachmentFilter::With.contains(&text));
+        assert!(AttachmentFilter::Without.contains(&text));
+        assert!(AttachmentFilter::Any.contains(&text));
+    }
+
+    #[test]
+    fn test_keyword_file_excludes() {
+        let filter = ContentFilter {
+            exclude: keyword_regex("# bot commands\n!WARN\n\n?userinfo (.*)\n")
+                .into_iter()
+                .collect(),
+            ..ContentFilter::default()
+        };
+        let saying = |content: &str| Message {
+            content: content.to_string(),
+            ..Message::default()
+        };
+        assert!(!filter.contains(&saying("!warn @Deleted User spam")));
+        assert!(!filter.contains(&saying("?USERINFO (.*)")));
+        assert!(filter.contains(&saying("?userinfo 123")));
+        assert!(keyword_regex("# nothing\n").is_none());
+    }
+
+    #[test]
+    fn test_content_filter() {
+        let filter = ContentFilter {
+            include: Some(Regex::new("(?i)ban").unwrap()),
+            exclude: vec![Regex::new("joke").unwrap()],
+            ..ContentFilter::default()
+        };
+        let saying = |content: &str| Message {
+            content: content.to_string(),
+            ..Message::default()
+        };
+        assert!(filter.contains(&saying("you got BANNED")));
+        assert!(!filter.contains(&saying("ban joke")));
+        assert!(!filter.contains(&saying("hello")));
+        assert!(ContentFilter::default().contains(&saying("hello")));
+
+        let sized = ContentFilter {
+            min_chars: Some(2),
+            max_chars: Some(5),
+            ..ContentFilter::default()
+        };
+        assert!(!sized.contains(&saying(" k ")));
+        assert!(sized.contains(&saying("  héllo  ")));
+        assert!(!sized.contains(&saying("hello!")));
+    }
+
+    #[test]
+    fn test_parse_id_list_ignores_comments_and_blanks() {
+        let ids = parse_id_list(
+            "# noise accounts
+123
+
+  456  # bot
+",
+        );
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains("123") && ids.contains("456"));
+    }
+
+    fn message_at(timestamp: &str) -> Message {
+        Message {
+            message_id: "x".to_string(),
+            timestamp: timestamp.to_string(),
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_id_range_is_exclusive() {
+        let with_id = |id: &str| Message {
+            message_id: id.to_string(),
+            ..Message::default()
+        };
+        let range = IdRange {
+            after: Some(100),
+            before: Some(200),
+        };
+        assert!(!range.contains(&with_id("100")));
+        assert!(range.contains(&with_id("150")));
+        assert!(!range.contains(&with_id("200")));
+        assert!(!range.contains(&with_id("abc")));
+        assert!(IdRange::default().contains(&with_id("abc")));
+    }
+
+    #[test]
+    fn test_language_filter() {
+        assert_eq!(parse_language("en"), Ok(Lang::Eng));
+        assert_eq!(parse_language("DEU"), Ok(Lang::Deu));
+        assert_eq!(parse_language("French"), Ok(Lang::Fra));
+        assert!(parse_language("klingon").is_err());
+
+        let saying = |content: &str| Message {
+            content: content.to_string(),
+            ..Message::default()
+        };
+        let english = LanguageFilter(Some(Lang::Eng));
+        assert!(english.contains(&saying(
+            "I really miss talking to you every day, this server is not the same without you"
+        )));
+        assert!(!english.contains(&saying(
+            "wo bist du hin, wir haben uns alle gefragt, was los ist"
+        )));
+        assert!(!english.contains(&saying("")));
+        assert!(LanguageFilter(None).contains(&saying("")));
+
+        // Links are not words of any language: the filter sees the same
+        // text as the `--detect-languages` histogram.
+        let linked = saying(
+            "https://www.example.com/wp-content/uploads/struktur/tabelle.html \
+             <https://cdn.discordapp.com/attachments/1/2/bildschirmfoto.png> \
+             wo bist du hin, wir haben uns alle gefragt, was los ist",
+        );
+        assert_eq!(content::language(&linked), Some(Lang::Deu));
+        assert!(LanguageFilter(Some(Lang::Deu)).contains(&linked));
+        assert!(!english.contains(&linked));
+    }
+
+    #[test]
+    fn test_parse_bounds() {
+        assert_eq!(
+            parse_since("2024-03-01", Tz::UTC).unwrap().to_rfc3339(),
+            "2024-03-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_until("2024-03-31", Tz::UTC)
+                .unwrap()
+                .date_naive()
+                .to_string(),
+            "2024-03-31"
+        );
+        assert_eq!(
+            parse_since("2024-03-01T12:00:00+02:00", Tz::UTC)
+                .unwrap()
+                .to_rfc3339(),
+            "2024-03-01T10:00:00+00:00"
+        );
+        assert!(parse_since("March 1st", Tz::UTC).is_err());
+    }
+
+    #[test]
+    fn test_date_bounds_follow_timezone() {
+        let berlin = parse_timezone("Europe/Berlin").unwrap();
+        assert_eq!(
+            parse_since("2024-03-01", berlin).unwrap().to_rfc3339(),
+            "2024-02-29T23:00:00+00:00"
+        );
+        // March 31st 2024 is 23 hours long in Berlin.
+        assert_eq!(
+            parse_until("2024-03-31", berlin).unwrap().to_rfc3339(),
+            "2024-03-31T21:59:59.999999999+00:00"
+        );
+        assert_eq!(
+            parse_since("2024-03-01T12:00:00Z", berlin)
+                .unwrap()
+                .to_rfc3339(),
+            "2024-03-01T12:00:00+00:00"
+        );
+        assert!(parse_timezone("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn test_window_is_inclusive() {
+        let window = DateWindow {
+            since: Some(parse_since("2024-03-01", Tz::UTC).unwrap()),
+            until: Some(parse_until("2024-03-31", Tz::UTC).unwrap()),
+        };
+        assert!(window.contains(&message_at("2024-03-01T00:00:00Z")));
+        assert!(window.contains(&message_at("2024-03-31T23:59:59Z")));
+        assert!(!window.contains(&message_at("2024-04-01T00:00:00Z")));
+        assert!(!window.contains(&message_at("2024-02-29T23:59:59Z")));
+        assert!(!window.contains(&message_at("not a date")));
+        assert!(DateWindow::default().contains(&message_at("not a date")));
+    }
+}
@@ -0,0 +1,90 @@
+//! How many messages each filter removed, to tell which one emptied a
+//! result.
+//!
+//! Filters run in a fixed order and a message is charged to the first one
+//! that drops it, so the counts add up to everything that was filtered out.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Drop counts as messages go through [`crate::chain::FilterChain::rejected_by`].
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct FilterTally {
+    considered: usize,
+    dropped: BTreeMap<String, usize>,
+}
+
+impl FilterTally {
+    /// Records one message: kept when `rejected_by` is `None`.
+    pub fn record(&mut self, rejected_by: Option<&str>) {
+        self.considered += 1;
+        if let Some(filter) = rejected_by {
+            *self.dropped.entry(filter.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    /// Charges `count` messages that were already recorded as kept to
+    /// `filter`, for filters that can only run once every input is loaded.
+    pub fn drop_late(&mut self, filter: &str, count: usize) {
+        *self.dropped.entry(filter.to_string()).or_insert(0) += count;
+    }
+
+    /// Counts for the `active` filters in the order they run, including
+    /// those that removed nothing. `None` when no filter is active.
+    pub fn summary(&self, active: &[&str]) -> Option<FilterStats> {
+        if active.is_empty() {
+            return None;
+        }
+        let dropped: usize = self.dropped.values().sum();
+        Some(FilterStats {
+            considered: self.considered,
+            kept: self.considered - dropped,
+            filters: active
+                .iter()
+                .map(|filter| FilterDrops {
+                    filter: filter.to_string(),
+                    dropped: self.dropped.get(*filter).copied().unwrap_or(0),
+                })
+                .collect(),
+        })
+    }
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct FilterDrops {
+    pub filter: String,
+    pub dropped: usize,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct FilterStats {
+    /// Messages that reached the filters, after sampling and opt-outs.
+    pub considered: usize,
+    pub kept: usize,
+    pub filters: Vec<FilterDrops>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_lists_active_filters_in_order() {
+        let mut tally = FilterTally::default();
+        for rejected in [None, Some("target"), Some("target"), Some("content"), None] {
+            tally.record(rejected);
+        }
+        let stats = tally
+            .summary(&["target", "since/until", "content"])
+            .unwrap();
+        assert_eq!((stats.considered, stats.kept), (5, 2));
+        let drops: Vec<(&str, usize)> = stats
+            .filters
+            .iter()
+            .map(|f| (f.filter.as_str(), f.dropped))
+            .collect();
+        assert_eq!(drops, [("target", 2), ("since/until", 0), ("content", 1)]);
+        assert!(tally.summary(&[]).is_none());
+    }
+}
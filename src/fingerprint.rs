@@ -0,0 +1,53 @@
+//! Stable per-message fingerprints: a hash of the normalized content, so
+//! external systems can refer to a message across runs and re-exports
+//! without keeping its text.
+//!
+//! The hash is 64-bit FNV-1a, fixed here rather than taken from `std`, whose
+//! hasher may change between Rust releases.
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Lowercased, with runs of whitespace collapsed to one space and the ends
+/// trimmed, so re-exports that reflow or recase a message agree.
+pub fn normalize(content: &str) -> String {
+    content
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The fingerprint of `content`, as 16 lowercase hex digits.
+pub fn fingerprint(content: &str) -> String {
+    hash(&normalize(content))
+}
+
+/// The hash of `text` as is, as 16 lowercase hex digits.
+pub fn hash(text: &str) -> String {
+    let hash = text.bytes().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_is_stable_and_normalized() {
+        // Pinned so an accidental change to the hash or normalization, which
+        // would break references stored by other systems, fails loudly.
+        assert_eq!(fingerprint(""), "cbf29ce484222325");
+        assert_eq!(fingerprint("a"), "af63dc4c8601ec8c");
+        assert_eq!(
+            fingerprint("  Where   did\nthey GO "),
+            fingerprint("where did they go")
+        );
+        assert_ne!(
+            fingerprint("where did they go"),
+            fingerprint("where did they go?")
+        );
+    }
+}
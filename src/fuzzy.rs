@@ -0,0 +1,313 @@
+//! `--fuzzy-words`: counts misspellings ("definately", "recieve") as the
+//! word they were meant to be, so typos do not split one word's count
+//! across several entries.
+//!
+//! Words are taken from the most frequent down; each word not yet merged
+//! keeps its spelling and takes in every rarer word within a small edit
+//! distance of it, counting a swap of two neighbouring letters as one edit.
+//! The majority spelling therefore wins, and a word is only ever merged
+//! into one spelling, never along a chain of typos. Short words are left
+//! alone, since "think" and "thing" are one edit apart: words need at least
+//! 6 letters, and 10 for two edits. Only words made wholly of letters are
+//! merged. The spellings behind each merged word are listed in the result's
+//! `word_variants`. Everything else counted per word (n-grams, collocation
+//! pairs, time buckets, co-occurrence and topic documents) is counted under
+//! the merged spelling too.
+
+use crate::richness;
+use crate::AuthorAnalysis;
+use std::collections::{HashMap, HashSet};
+
+/// Words shorter than this are never merged.
+const MIN_LENGTH: usize = 6;
+
+/// Words at least this long may be two edits apart.
+const TWO_EDIT_LENGTH: usize = 10;
+
+fn max_edits(length: usize) -> usize {
+    if length >= TWO_EDIT_LENGTH {
+        2
+    } else {
+        1
+    }
+}
+
+/// The optimal string alignment distance between `a` and `b`, or `None`
+/// once it is certain to exceed `limit`.
+fn distance(a: &[char], b: &[char], limit: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > limit {
+        return None;
+    }
+    let width = b.len() + 1;
+    let mut rows = vec![vec![0usize; width]; 3];
+    for (j, cell) in rows[1].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        let (current, previous, before) = (i % 3, (i + 2) % 3, (i + 1) % 3);
+        rows[current][0] = i;
+        let mut best = i;
+        for j in 1..width {
+            let substitution = rows[previous][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut cell = substitution
+                .min(rows[previous][j] + 1)
+                .min(rows[current][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                cell = cell.min(rows[before][j - 2] + 1);
+            }
+            rows[current][j] = cell;
+            best = best.min(cell);
+        }
+        if best > limit {
+            return None;
+        }
+    }
+    let distance = rows[a.len() % 3][b.len()];
+    (distance <= limit).then_some(distance)
+}
+
+/// Maps each merged spelling to the word it is counted as.
+fn canonical_forms(totals: &HashMap<&str, usize>) -> HashMap<String, String> {
+    let mut words: Vec<(&str, usize)> = totals
+        .iter()
+        .filter(|(word, _)| word.chars().all(char::is_alphabetic))
+        .map(|(word, count)| (*word, *count))
+        .collect();
+    words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    let letters: Vec<Vec<char>> = words
+        .iter()
+        .map(|(word, _)| word.chars().collect())
+        .collect();
+
+    // Candidates by length, so each word is only compared with words of a
+    // length it could be within reach of.
+    let mut by_length: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, chars) in letters.iter().enumerate() {
+        if chars.len() >= MIN_LENGTH {
+            by_length.entry(chars.len()).or_default().push(i);
+        }
+    }
+
+    let mut merged = vec![false; words.len()];
+    let mut canonical = HashMap::new();
+    for i in 0..words.len() {
+        let length = letters[i].len();
+        if merged[i] || length < MIN_LENGTH {
+            continue;
+        }
+        let reach = max_edits(length);
+        for other_length in length.saturating_sub(reach)..=length + reach {
+            for &j in by_length.get(&other_length).into_iter().flatten() {
+                if j <= i || merged[j] {
+                    continue;
+                }
+                let limit = reach.min(max_edits(other_length));
+                if distance(&letters[i], &letters[j], limit).is_some() {
+                    merged[j] = true;
+                    canonical.insert(words[j].0.to_string(), words[i].0.to_string());
+                }
+            }
+        }
+    }
+    canonical
+}
+
+/// Merges misspelled words across `authors`, recounting each author's
+/// words and noting the merged spellings in their `word_variants`.
+pub fn merge(authors: &mut [AuthorAnalysis]) {
+    let mut totals: HashMap<&str, usize> = HashMap::new();
+    for author in authors.iter() {
+        for (word, count) in &author.word_frequency {
+            *totals.entry(word).or_insert(0) += count;
+        }
+    }
+    let canonical = canonical_forms(&totals);
+    if canonical.is_empty() {
+        return;
+    }
+
+    let targets: HashSet<&str> = canonical.values().map(String::as_str).collect();
+    let rename = |word: &str| {
+        canonical
+            .get(word)
+            .cloned()
+            .unwrap_or_else(|| word.to_string())
+    };
+    for author in authors.iter_mut() {
+        // Counted before stopwords are dropped and the vocabulary is capped,
+        // so these may hold merged spellings the word counts do not.
+        let mut grams: HashMap<String, usize> = HashMap::new();
+        for (gram, count) in std::mem::take(&mut author.ngram_frequency) {
+            let gram = gram.split(' ').map(rename).collect::<Vec<_>>().join(" ");
+            *grams.entry(gram).or_insert(0) += count;
+        }
+        author.most_common_ngrams = crate::most_common(&grams);
+        author.ngram_frequency = grams.into_iter().collect();
+        author.pair_counts.map_words(rename);
+        author.bucket_counts.map_words(rename);
+        author.word_buckets = author.bucket_counts.top_words();
+        author.cooccurrence.map_words(rename);
+        for document in &mut author.documents {
+            for word in document.iter_mut() {
+                if let Some(target) = canonical.get(word.as_str()) {
+                    *word = target.clone();
+                }
+            }
+        }
+
+        if !author
+            .word_frequency
+            .keys()
+            .any(|word| canonical.contains_key(word))
+        {
+            continue;
+        }
+        // Stemming and lemmatizing record the forms behind every word, so
+        // those move over; words as written are recorded here.
+        let as_written = author.word_variants.is_empty();
+        let mut words: HashMap<String, usize> = HashMap::new();
+        for (word, count) in std::mem::take(&mut author.word_frequency) {
+            let target = match canonical.get(&word) {
+                Some(target) if as_written => {
+                    author.word_variants.add(target, word, count);
+                    target.clone()
+                }
+                Some(target) => {
+                    author.word_variants.rename(&word, target);
+                    target.clone()
+                }
+                None => {
+                    if as_written && targets.contains(word.as_str()) {
+                        author.word_variants.add(&word, word.clone(), count);
+                    }
+                    word
+                }
+            };
+            *words.entry(target).or_insert(0) += count;
+        }
+
+        let richness = richness::measure(&words);
+        author.vocabulary_size = richness.vocabulary_size;
+        author.type_token_ratio = richness.type_token_ratio;
+        author.average_word_length = richness.average_word_length;
+        author.most_common_words = crate::most_common(&words);
+        author.word_frequency = words.into_iter().collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_author_analysis, MessageCounts};
+    use clap::Parser;
+
+    fn author(words: &[(&str, usize)]) -> AuthorAnalysis {
+        let words: HashMap<String, usize> = words
+            .iter()
+            .map(|(word, count)| (word.to_string(), *count))
+            .collect();
+        let counts = MessageCounts::default();
+        build_author_analysis(String::new(), String::new(), String::new(), counts, words)
+    }
+
+    #[test]
+    fn test_typos_merge_into_majority_spelling() {
+        let chars = |word: &str| word.chars().collect::<Vec<_>>();
+        assert_eq!(distance(&chars("recieve"), &chars("receive"), 1), Some(1));
+        assert_eq!(distance(&chars("banned"), &chars("bonnet"), 1), None);
+
+        let mut authors = vec![
+            author(&[("definitely", 3), ("recieve", 1), ("thing", 2)]),
+            author(&[
+                ("definately", 2),
+                ("definitly", 1),
+                ("receive", 2),
+                ("think", 2),
+            ]),
+        ];
+        merge(&mut authors);
+        assert_eq!(authors[0].word_frequency["definitely"], 3);
+        assert_eq!(authors[1].word_frequency["definitely"], 3);
+        assert_eq!(authors[0].word_frequency["receive"], 1);
+        // Too short to merge.
+        assert_eq!(authors[1].word_frequency["think"], 2);
+        assert_eq!(authors[1].vocabulary_size, 3);
+        let variants = std::mem::take(&mut authors[1].word_variants).into_map();
+        assert_eq!(variants["definitely"], ["definately", "definitly"]);
+        assert!(!variants.contains_key("receive"));
+    }
+
+    #[test]
+    fn test_merged_spellings_reach_every_word_statistic() {
+        let message = |id: usize, author: &str, content: &str| {
+            serde_json::json!({
+                "message_id": id.to_string(),
+                "content": content,
+                "timestamp": "2024-03-01T12:00:00+00:00",
+                "author_name": author,
+                "author_id": author,
+                "mentioned_user_name": "Deleted User",
+            })
+        };
+        let messages = serde_json::json!([
+            message(1, "a", "definitely happening today"),
+            message(2, "a", "definitely happening tonight"),
+            message(3, "a", "definitely happening again"),
+            message(4, "b", "definately happening soon"),
+        ]);
+        let path = std::env::temp_dir().join(format!("dua-fuzzy-{}.json", std::process::id()));
+        std::fs::write(&path, messages.to_string()).unwrap();
+        let args = crate::Args::try_parse_from([
+            "deleted-user-analyzer",
+            "-i",
+            path.to_str().unwrap(),
+            "--fuzzy-words",
+            "--collocations",
+            "pmi",
+            "--collocation-min-count",
+            "1",
+            "--ngrams",
+            "2",
+            "--word-buckets",
+            "day",
+            "--word-trends",
+            "definitely,definately",
+        ])
+        .unwrap();
+        let pipeline = crate::pipeline::Pipeline::from_args(&args).unwrap();
+        let result = crate::analyze_in_memory(&args, &pipeline).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let pair = result
+            .collocations
+            .iter()
+            .find(|c| c.words == "definitely happening")
+            .unwrap();
+        assert_eq!(pair.count, 4);
+        assert!(result
+            .collocations
+            .iter()
+            .all(|c| !c.words.contains("definately")));
+        assert_eq!(result.global_ngram_frequency["definitely happening"], 4);
+        assert!(!result
+            .global_ngram_frequency
+            .contains_key("definately happening"));
+
+        let trends = result.word_trends.unwrap();
+        let counts = |word: &str| {
+            let trend = trends.words.iter().find(|t| t.word == word).unwrap();
+            trend.counts.clone()
+        };
+        assert_eq!(counts("definitely"), [4]);
+        assert_eq!(counts("definately"), [0]);
+        let b = &result
+            .authors_analysis
+            .iter()
+            .find(|a| a.author_id == "b")
+            .unwrap();
+        assert!(b.word_buckets[0]
+            .top_words
+            .iter()
+            .any(|(word, _)| word == "definitely"));
+    }
+}
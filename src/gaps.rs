@@ -0,0 +1,233 @@
+//! `--deletion-gaps`: for exports whose message IDs count up by one per
+//! channel, the IDs missing between exported messages are messages that were
+//! deleted before the export. Comparing how many are missing around the
+//! analyzed messages with the channel as a whole hints at cleaned-up
+//! conversations.
+//!
+//! Discord snowflakes are not sequential, so channels using them are only
+//! counted as skipped.
+
+use crate::message::Message;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Default for `--gap-window`, in IDs on each side of an analyzed message.
+pub const DEFAULT_WINDOW: u64 = 10;
+
+/// A window counts as elevated when its share of missing IDs is at least
+/// this many times the channel's...
+const ELEVATED_RATIO: f64 = 2.0;
+/// ...and at least this many IDs are missing from it.
+const ELEVATED_MIN_MISSING: u64 = 2;
+
+/// Every exported message ID per channel, whether or not it matched.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct GapTracker(BTreeMap<String, ChannelIds>);
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+struct ChannelIds {
+    channel_id: Option<String>,
+    channel_name: Option<String>,
+    ids: Vec<u64>,
+}
+
+impl GapTracker {
+    /// Records a loaded message. Messages without a channel or a numeric ID
+    /// are ignored.
+    pub fn record(&mut self, msg: &Message) {
+        let Some(key) = msg.channel_id.as_ref().or(msg.channel_name.as_ref()) else {
+            return;
+        };
+        let Ok(id) = msg.message_id.trim().parse::<u64>() else {
+            return;
+        };
+        let entry = self.0.entry(key.clone()).or_insert_with(|| ChannelIds {
+            channel_id: msg.channel_id.clone(),
+            ..ChannelIds::default()
+        });
+        entry.ids.push(id);
+        if entry.channel_name.is_none() {
+            entry.channel_name = msg.channel_name.clone();
+        }
+    }
+
+    /// Gaps per sequential channel, around the `matched` messages.
+    pub fn summary(mut self, matched: &[Message], window: u64) -> DeletionGaps {
+        let mut targets: BTreeMap<&String, Vec<u64>> = BTreeMap::new();
+        for msg in matched {
+            let Some(key) = msg.channel_id.as_ref().or(msg.channel_name.as_ref()) else {
+                continue;
+            };
+            if let Ok(id) = msg.message_id.trim().parse::<u64>() {
+                targets.entry(key).or_default().push(id);
+            }
+        }
+
+        let mut result = DeletionGaps {
+            window,
+            ..DeletionGaps::default()
+        };
+        for (key, channel) in &mut self.0 {
+            channel.ids.sort_unstable();
+            channel.ids.dedup();
+            if !is_sequential(&channel.ids) {
+                result.non_sequential_channels += 1;
+                continue;
+            }
+            let mut around = targets.remove(key).unwrap_or_default();
+            around.sort_unstable();
+            around.dedup();
+            result.channels.push(channel_gaps(channel, &around, window));
+        }
+        result.channels.sort_by(|a, b| {
+            b.window_missing
+                .cmp(&a.window_missing)
+                .then(b.missing.cmp(&a.missing))
+        });
+        result
+    }
+}
+
+/// Whether at least half of the steps between consecutive IDs are one.
+fn is_sequential(ids: &[u64]) -> bool {
+    if ids.len() < 2 {
+        return false;
+    }
+    let ones = ids.windows(2).filter(|pair| pair[1] - pair[0] == 1).count();
+    ones * 2 >= ids.len() - 1
+}
+
+/// Exported IDs in `first..=last`.
+fn present(ids: &[u64], first: u64, last: u64) -> u64 {
+    (ids.partition_point(|&id| id <= last) - ids.partition_point(|&id| id < first)) as u64
+}
+
+fn channel_gaps(channel: &ChannelIds, around: &[u64], window: u64) -> ChannelGaps {
+    let ids = &channel.ids;
+    let (first, last) = (ids[0], ids[ids.len() - 1]);
+    let span = last - first + 1;
+    let missing = span - ids.len() as u64;
+
+    // Windows of neighbouring analyzed messages overlap; merge them so no ID
+    // is counted twice.
+    let mut ranges: Vec<(u64, u64)> = Vec::new();
+    for &id in around {
+        let start = id.saturating_sub(window).max(first);
+        let end = id.saturating_add(window).min(last);
+        match ranges.last_mut() {
+            Some(prev) if start <= prev.1.saturating_add(1) => prev.1 = prev.1.max(end),
+            _ => ranges.push((start, end)),
+        }
+    }
+    let window_ids: u64 = ranges.iter().map(|(start, end)| end - start + 1).sum();
+    let window_present: u64 = ranges
+        .iter()
+        .map(|&(start, end)| present(ids, start, end))
+        .sum();
+    let window_missing = window_ids - window_present;
+
+    let density = missing as f64 / span as f64;
+    let window_density = if window_ids == 0 {
+        0.0
+    } else {
+        window_missing as f64 / window_ids as f64
+    };
+    ChannelGaps {
+        channel_id: channel.channel_id.clone(),
+        channel_name: channel.channel_name.clone(),
+        first_id: first,
+        last_id: last,
+        exported_messages: ids.len(),
+        missing,
+        density,
+        analyzed_messages: around.len(),
+        window_ids,
+        window_missing,
+        window_density,
+        elevated: window_missing >= ELEVATED_MIN_MISSING
+            && window_density >= density * ELEVATED_RATIO,
+    }
+}
+
+/// Missing message IDs in one channel.
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct ChannelGaps {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_name: Option<String>,
+    pub first_id: u64,
+    pub last_id: u64,
+    pub exported_messages: usize,
+    /// IDs between the first and last that are not in the export.
+    pub missing: u64,
+    /// `missing` as a share of the ID span.
+    pub density: f64,
+    /// Analyzed messages in this channel.
+    pub analyzed_messages: usize,
+    /// IDs within `--gap-window` of an analyzed message.
+    pub window_ids: u64,
+    pub window_missing: u64,
+    pub window_density: f64,
+    /// Whether the windows are missing far more IDs than the channel overall.
+    pub elevated: bool,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone, Default)]
+pub struct DeletionGaps {
+    pub window: u64,
+    /// Sequential channels, most missing IDs near analyzed messages first.
+    pub channels: Vec<ChannelGaps>,
+    /// Channels skipped because their IDs are not sequential.
+    pub non_sequential_channels: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(channel: &str, id: u64) -> Message {
+        Message {
+            message_id: id.to_string(),
+            channel_id: Some(channel.to_string()),
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_gaps_around_analyzed_messages() {
+        let mut tracker = GapTracker::default();
+        // 1..=100 with 48..=52 deleted except 50, and 90 deleted.
+        for id in (1..=100).filter(|id| ![48, 49, 51, 52, 90].contains(id)) {
+            tracker.record(&msg("seq", id));
+        }
+        for id in [1_222_277_081_971_294_297, 1_222_277_081_971_294_999] {
+            tracker.record(&msg("snowflake", id));
+        }
+
+        let gaps = tracker.summary(&[msg("seq", 50)], 5);
+        assert_eq!(gaps.non_sequential_channels, 1);
+        let channel = &gaps.channels[0];
+        assert_eq!((channel.first_id, channel.last_id), (1, 100));
+        assert_eq!(channel.exported_messages, 95);
+        assert_eq!(channel.missing, 5);
+        assert_eq!(channel.window_ids, 11);
+        assert_eq!(channel.window_missing, 4);
+        assert!(channel.elevated);
+    }
+
+    #[test]
+    fn test_overlapping_windows_and_edges() {
+        let mut tracker = GapTracker::default();
+        for id in [1, 2, 4, 5, 6, 7, 9, 10] {
+            tracker.record(&msg("c", id));
+        }
+        let gaps = tracker.summary(&[msg("c", 2), msg("c", 4)], 2);
+        let channel = &gaps.channels[0];
+        // 1..=6: the windows are clipped to the first ID and merged.
+        assert_eq!(channel.window_ids, 6);
+        assert_eq!(channel.window_missing, 1);
+        assert!(!channel.elevated);
+    }
+}
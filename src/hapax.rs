@@ -0,0 +1,44 @@
+//! `--hapax`: words used exactly once, per author and over the whole
+//! result. One-off terms (names, slurs, fragments of personal details) are
+//! often what an investigation is after, and they never reach the top of
+//! a frequency table.
+
+use crate::AnalysisResult;
+use std::collections::BTreeMap;
+
+/// The words counted once, in alphabetical order.
+pub fn words(frequency: &BTreeMap<String, usize>) -> Vec<String> {
+    frequency
+        .iter()
+        .filter(|(_, &count)| count == 1)
+        .map(|(word, _)| word.clone())
+        .collect()
+}
+
+/// Fills in `hapax_legomena` for the result and every author in it.
+pub fn annotate(result: &mut AnalysisResult) {
+    result.hapax_legomena = words(&result.global_word_frequency);
+    let sections = result
+        .target_sections
+        .iter_mut()
+        .flat_map(|s| &mut s.authors_analysis);
+    for author in result.authors_analysis.iter_mut().chain(sections) {
+        author.hapax_legomena = words(&author.word_frequency);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words_used_once() {
+        let frequency = BTreeMap::from([
+            ("where".to_string(), 4),
+            ("street".to_string(), 1),
+            ("elm".to_string(), 1),
+        ]);
+        assert_eq!(words(&frequency), ["elm", "street"]);
+        assert!(words(&BTreeMap::new()).is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! `--inactive-targets`: targets are users mentioned or replied to after
+//! they stopped writing, which catches harassment of members who left
+//! without deleting their account.
+//!
+//! A user's last message is only known once every input is loaded, so
+//! messages pass the target stage during ingest and are narrowed down in
+//! `summarize_state`. Mentions carry names rather than IDs, so a mentioned
+//! user is found by matching the mention against the names and nicknames
+//! authors used; replies carry the author ID directly.
+
+use crate::message::Message;
+use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// When each author last wrote, and under which names.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ActivityTracker {
+    last_message: HashMap<String, DateTime<Utc>>,
+    author_names: HashMap<String, String>,
+    /// Normalized names and nicknames to the author IDs that used them.
+    ids_by_name: HashMap<String, BTreeSet<String>>,
+}
+
+/// A user who was mentioned after going quiet.
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct InactiveUser {
+    pub author_id: String,
+    pub author_name: String,
+    pub last_message_at: DateTime<Utc>,
+    /// Analyzed messages to them sent at least the window after
+    /// `last_message_at`.
+    pub messages: usize,
+}
+
+impl ActivityTracker {
+    pub fn record(&mut self, msg: &Message) {
+        let Some(ts) = msg.parsed_timestamp() else {
+            return;
+        };
+        let last = self.last_message.entry(msg.author_id.clone()).or_insert(ts);
+        if ts >= *last {
+            *last = ts;
+            self.author_names
+                .insert(msg.author_id.clone(), msg.author_name.clone());
+        }
+        for name in [&msg.author_name, &msg.author_nickname] {
+            self.ids_by_name
+                .entry(normalize(name))
+                .or_default()
+                .insert(msg.author_id.clone());
+        }
+    }
+
+    /// The author IDs a message is addressed to.
+    fn recipients<'a>(&'a self, msg: &'a Message) -> BTreeSet<&'a str> {
+        let mut ids = BTreeSet::new();
+        for name in [&msg.mentioned_user_name, &msg.mentioned_user_nickname]
+            .into_iter()
+            .flatten()
+        {
+            if let Some(found) = self.ids_by_name.get(&normalize(name)) {
+                ids.extend(found.iter().map(String::as_str));
+            }
+        }
+        if let Some(id) = msg
+            .referenced_message
+            .as_ref()
+            .and_then(|reply| reply.author_id.as_deref())
+        {
+            ids.insert(id);
+        }
+        ids
+    }
+
+    /// Keeps the messages sent to a user at least `days` after that user's
+    /// last message, and lists those users, most messaged first.
+    pub fn select(&self, msgs: Vec<Message>, days: u32) -> (Vec<Message>, Vec<InactiveUser>) {
+        let window = Duration::days(days.into());
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        let kept = msgs
+            .into_iter()
+            .filter(|msg| {
+                let Some(sent) = msg.parsed_timestamp() else {
+                    return false;
+                };
+                let inactive: Vec<&str> = self
+                    .recipients(msg)
+                    .into_iter()
+                    .filter(|id| *id != msg.author_id)
+                    .filter(|id| {
+                        self.last_message
+                            .get(*id)
+                            .is_some_and(|last| sent - *last >= window)
+                    })
+                    .collect();
+                for id in &inactive {
+                    *counts.entry(id.to_string()).or_insert(0) += 1;
+                }
+                !inactive.is_empty()
+            })
+            .collect();
+
+        let mut users: Vec<InactiveUser> = counts
+            .into_iter()
+            .map(|(author_id, messages)| InactiveUser {
+                author_name: self.author_names[&author_id].clone(),
+                last_message_at: self.last_message[&author_id],
+                author_id,
+                messages,
+            })
+            .collect();
+        users.sort_by(|a, b| {
+            b.messages
+                .cmp(&a.messages)
+                .then_with(|| a.author_id.cmp(&b.author_id))
+        });
+        (kept, users)
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::ReferencedMessage;
+
+    fn msg(author: &str, name: &str, day: u32, mentioned: Option<&str>) -> Message {
+        Message {
+            message_id: format!("{}-{}", author, day),
+            author_id: author.to_string(),
+            author_name: name.to_string(),
+            author_nickname: name.to_string(),
+            timestamp: format!("2024-03-{:02}T12:00:00Z", day),
+            mentioned_user_name: mentioned.map(str::to_string),
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_selects_mentions_after_inactivity() {
+        let quiet_reply = Message {
+            referenced_message: Some(ReferencedMessage {
+                author_id: Some("1".to_string()),
+                ..ReferencedMessage::default()
+            }),
+            ..msg("3", "carol", 25, None)
+        };
+        let msgs = vec![
+            msg("1", "alice", 1, None),
+            msg("2", "bob", 2, Some("Alice")),
+            msg("2", "bob", 20, Some("alice")),
+            // Bob keeps writing, so mentioning him never counts.
+            msg("3", "carol", 21, Some("bob")),
+            msg("2", "bob", 22, None),
+            quiet_reply,
+            msg("3", "carol", 26, Some("nobody")),
+        ];
+        let mut tracker = ActivityTracker::default();
+        for msg in &msgs {
+            tracker.record(msg);
+        }
+
+        let (kept, users) = tracker.select(msgs, 7);
+        let ids: Vec<&str> = kept.iter().map(|m| m.message_id.as_str()).collect();
+        assert_eq!(ids, ["2-20", "3-25"]);
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].author_id, "1");
+        assert_eq!(users[0].messages, 2);
+        assert_eq!(
+            users[0].last_message_at.to_rfc3339(),
+            "2024-03-01T12:00:00+00:00"
+        );
+    }
+}
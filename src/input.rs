@@ -0,0 +1,622 @@
+use crate::encoding::{self, TextEncoding};
+use crate::message::{RawMessage, ReferencedMessage};
+use crate::paths;
+use anyhow::Context;
+use rayon::prelude::*;
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// NDJSON texts with at least this many lines are parsed in parallel.
+const PARALLEL_PARSE_LINES: usize = 1024;
+
+/// Streamed NDJSON is parsed in batches of this many lines.
+const STREAM_BATCH_LINES: usize = 8 * PARALLEL_PARSE_LINES;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+/// Where an unexpected key was first encountered.
+#[derive(Debug, Clone)]
+pub struct FirstOccurrence {
+    pub file: String,
+    pub record: usize,
+    pub message_id: Option<String>,
+}
+
+/// Unexpected keys found in strict mode, keyed by field name.
+#[derive(Default, Debug)]
+pub struct UnknownFields(BTreeMap<String, FirstOccurrence>);
+
+impl UnknownFields {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn scan(&mut self, file: &str, records: &[Value]) {
+        for (record, value) in records.iter().enumerate() {
+            self.scan_record(file, record, value);
+        }
+    }
+
+    fn scan_record(&mut self, file: &str, record: usize, value: &Value) {
+        let Some(obj) = value.as_object() else {
+            return;
+        };
+        let first = FirstOccurrence {
+            file: file.to_string(),
+            record,
+            message_id: obj
+                .get("message_id")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        };
+        self.scan_keys(obj, RawMessage::FIELDS, "", &first);
+        if let Some(Value::Object(reference)) = obj.get("referenced_message") {
+            self.scan_keys(
+                reference,
+                ReferencedMessage::FIELDS,
+                "referenced_message.",
+                &first,
+            );
+        }
+    }
+
+    /// Records the keys of `obj` outside `fields`, named with `prefix`.
+    fn scan_keys(
+        &mut self,
+        obj: &serde_json::Map<String, Value>,
+        fields: &[&str],
+        prefix: &str,
+        first: &FirstOccurrence,
+    ) {
+        for key in obj.keys() {
+            if fields.contains(&key.as_str()) {
+                continue;
+            }
+            self.0
+                .entry(format!("{}{}", prefix, key))
+                .or_insert_with(|| first.clone());
+        }
+    }
+}
+
+impl fmt::Display for UnknownFields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "strict mode: {} unexpected field(s) in input",
+            self.0.len()
+        )?;
+        for (key, first) in &self.0 {
+            write!(
+                f,
+                "  - \"{}\" first seen in {} record #{}",
+                key, first.file, first.record
+            )?;
+            if let Some(id) = &first.message_id {
+                write!(f, " (message_id {})", id)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// File extensions picked up when expanding a directory.
+const INPUT_EXTENSIONS: &[&str] = &["json", "ndjson", "jsonl"];
+
+/// Whether the text is newline-delimited JSON rather than a single array.
+pub fn is_ndjson(text: &str) -> bool {
+    !text.trim_start().starts_with('[')
+}
+
+/// Expands directories among the input paths into the JSON files they
+/// contain (`.json`, `.ndjson`, `.jsonl`), recursively and in sorted order. Plain file paths are kept as is.
+pub fn expand_inputs(paths: &[PathBuf]) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = paths::extended(path);
+        if path.is_dir() {
+            collect_dir(&path, &mut files)?;
+        } else {
+            files.push(path.into_owned());
+        }
+    }
+    Ok(files)
+}
+
+fn collect_dir(dir: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("failed to read directory {}", dir.display()))?
+        .collect::<Result<_, _>>()?;
+    entries.sort_by_key(|entry| entry.path());
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_dir(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| INPUT_EXTENSIONS.iter().any(|known| ext == *known))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Records read from the end of a growing NDJSON file.
+pub struct NdjsonTail {
+    pub records: Vec<RawMessage>,
+    /// Byte offset just past the last complete line.
+    pub offset: u64,
+    /// Number of complete lines consumed.
+    pub lines: usize,
+}
+
+/// Reads export files and keeps track of data-quality findings across them.
+#[derive(Default)]
+pub struct Loader {
+    pub strict: bool,
+    pub unknown_fields: UnknownFields,
+    /// Files that were not clean UTF-8, with the encoding used to read them.
+    pub decoded_files: Vec<(String, TextEncoding)>,
+    pub replaced_bytes: usize,
+    /// Unparseable NDJSON lines as `path:line`; they are skipped, not fatal.
+    pub skipped_records: Vec<String>,
+}
+
+impl Loader {
+    pub fn new(strict: bool) -> Self {
+        Loader {
+            strict,
+            ..Default::default()
+        }
+    }
+
+    /// Reads one export file, either a JSON array or NDJSON. In strict mode
+    /// every record is checked for keys that are not part of the message
+    /// schema before it is deserialized.
+    pub fn read_raw_messages(&mut self, path: &Path) -> anyhow::Result<Vec<RawMessage>> {
+        let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        self.parse_raw_messages(&path.display().to_string(), bytes)
+    }
+
+    /// [`Loader::read_raw_messages`] for bytes that were already read.
+    /// `path` only labels findings.
+    pub fn parse_raw_messages(
+        &mut self,
+        path: &str,
+        bytes: Vec<u8>,
+    ) -> anyhow::Result<Vec<RawMessage>> {
+        let file_stuff = self.decode(path, bytes);
+
+        if is_ndjson(&file_stuff) {
+            return Ok(self.parse_ndjson(path, &file_stuff, 0));
+        }
+
+        if !self.strict {
+            return serde_json::from_str(&file_stuff)
+                .with_context(|| format!("failed to parse {}", path));
+        }
+
+        let records: Vec<Value> = serde_json::from_str(&file_stuff)
+            .with_context(|| format!("failed to parse {}", path))?;
+        self.unknown_fields.scan(path, &records);
+        records
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| {
+                serde_json::from_value(value)
+                    .with_context(|| format!("failed to parse {} record #{}", path, i))
+            })
+            .collect()
+    }
+
+    /// [`Loader::read_raw_messages`] for the chunks of `path` coming from a
+    /// [`crate::prefetch`] reader thread, parsed as they arrive. Files that
+    /// are not clean UTF-8, or have records that do not parse, are read
+    /// again whole, so they are decoded and reported exactly as by
+    /// `read_raw_messages`.
+    pub fn read_prefetched(
+        &mut self,
+        path: &Path,
+        chunks: impl BufRead,
+    ) -> anyhow::Result<Vec<RawMessage>> {
+        let skipped = self.skipped_records.len();
+        let mut records = Vec::new();
+        let streamed = self.stream_records(&path.display().to_string(), chunks, |raw| {
+            records.push(raw);
+            Ok(())
+        });
+        if streamed.is_ok() && self.skipped_records.len() == skipped {
+            return Ok(records);
+        }
+        self.skipped_records.truncate(skipped);
+        self.read_raw_messages(path)
+    }
+
+    /// Reads the complete lines appended to an NDJSON file since `offset`.
+    /// A trailing line without a newline is left for the next call, since a
+    /// writer may still be in the middle of it.
+    pub fn read_ndjson_tail(
+        &mut self,
+        path: &Path,
+        offset: u64,
+        first_line: usize,
+    ) -> anyhow::Result<NdjsonTail> {
+        let mut file =
+            File::open(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let path = &path.display().to_string();
+        file.seek(SeekFrom::Start(offset))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        let complete = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        bytes.truncate(complete);
+        let lines = bytes.iter().filter(|&&b| b == b'\n').count();
+        let text = self.decode(path, bytes);
+        Ok(NdjsonTail {
+            records: self.parse_ndjson(path, &text, first_line),
+            offset: offset + complete as u64,
+            lines,
+        })
+    }
+
+    fn decode(&mut self, path: &str, bytes: Vec<u8>) -> String {
+        let decoded = encoding::decode(bytes);
+        if decoded.encoding != TextEncoding::Utf8 || decoded.replaced > 0 {
+            self.decoded_files
+                .push((path.to_string(), decoded.encoding));
+            self.replaced_bytes += decoded.replaced;
+        }
+        decoded.text
+    }
+
+    /// Parses one record per line; `first_line` is the zero-based line number
+    /// of the first line of `text` within the file. Large texts are parsed
+    /// on the rayon pool; findings are still recorded in line order.
+    fn parse_ndjson(&mut self, path: &str, text: &str, first_line: usize) -> Vec<RawMessage> {
+        let lines: Vec<(usize, &str)> = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (first_line + i, line.trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .collect();
+
+        let parsed: Vec<serde_json::Result<RawMessage>> = if self.strict {
+            let values = parse_lines::<Value>(&lines);
+            lines
+                .iter()
+                .zip(values)
+                .map(|((line_no, _), value)| {
+                    value.and_then(|value| {
+                        self.unknown_fields.scan_record(path, *line_no, &value);
+                        serde_json::from_value(value)
+                    })
+                })
+                .collect()
+        } else {
+            parse_lines::<RawMessage>(&lines)
+        };
+
+        let mut records = Vec::with_capacity(parsed.len());
+        for ((line_no, _), result) in lines.iter().zip(parsed) {
+            match result {
+                Ok(raw) => records.push(raw),
+                Err(_) => self
+                    .skipped_records
+                    .push(format!("{}:{}", path, line_no + 1)),
+            }
+        }
+        records
+    }
+
+    /// Streams the records of one export file into `on_message` without
+    /// holding the whole file in memory. Returns the number of records read.
+    ///
+    /// Unlike [`Loader::read_raw_messages`] this requires UTF-8 input, since
+    /// encoding detection needs the complete file.
+    pub fn stream_raw_messages<F>(&mut self, path: &Path, on_message: F) -> anyhow::Result<usize>
+    where
+        F: FnMut(RawMessage) -> anyhow::Result<()>,
+    {
+        let file =
+            File::open(path).with_context(|| format!("failed to read {}", path.display()))?;
+        self.stream_records(
+            &path.display().to_string(),
+            BufReader::new(file),
+            on_message,
+        )
+    }
+
+    /// [`Loader::stream_raw_messages`] over any reader; `path` only labels
+    /// findings.
+    fn stream_records<F>(
+        &mut self,
+        path: &str,
+        mut reader: impl BufRead,
+        mut on_message: F,
+    ) -> anyhow::Result<usize>
+    where
+        F: FnMut(RawMessage) -> anyhow::Result<()>,
+    {
+        let start = reader.fill_buf()?;
+        if start.starts_with(&[0xFF, 0xFE]) || start.starts_with(&[0xFE, 0xFF]) {
+            anyhow::bail!("{} is UTF-16 (streaming requires UTF-8 input)", path);
+        }
+        if start.starts_with(UTF8_BOM) {
+            reader.consume(UTF8_BOM.len());
+        }
+        let first = loop {
+            let buf = reader.fill_buf()?;
+            match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+                Some(i) => break Some(buf[i]),
+                None if buf.is_empty() => break None,
+                None => {
+                    let len = buf.len();
+                    reader.consume(len);
+                }
+            }
+        };
+
+        if first != Some(b'[') {
+            let mut count = 0;
+            let mut batch = String::new();
+            let mut batch_start = 0;
+            let mut lines = reader.lines().enumerate().peekable();
+            while let Some((line_no, line)) = lines.next() {
+                let line = line.with_context(|| {
+                    format!("failed to read {} (streaming requires UTF-8 input)", path)
+                })?;
+                batch.push_str(&line);
+                batch.push('\n');
+                if line_no + 1 - batch_start < STREAM_BATCH_LINES && lines.peek().is_some() {
+                    continue;
+                }
+                for raw in self.parse_ndjson(path, &batch, batch_start) {
+                    on_message(raw)?;
+                    count += 1;
+                }
+                batch.clear();
+                batch_start = line_no + 1;
+            }
+            return Ok(count);
+        }
+
+        let mut de = serde_json::Deserializer::from_reader(reader);
+        let visitor = StreamVisitor {
+            path,
+            strict: self.strict,
+            unknown_fields: &mut self.unknown_fields,
+            on_message,
+        };
+        let count = de.deserialize_seq(visitor).with_context(|| {
+            format!("failed to parse {} (streaming requires UTF-8 input)", path)
+        })?;
+        de.end()
+            .with_context(|| format!("failed to parse {}", path))?;
+        Ok(count)
+    }
+}
+
+/// Parses lines in order, on the rayon pool when there are many of them.
+fn parse_lines<T>(lines: &[(usize, &str)]) -> Vec<serde_json::Result<T>>
+where
+    T: for<'de> Deserialize<'de> + Send,
+{
+    if lines.len() >= PARALLEL_PARSE_LINES {
+        lines
+            .par_iter()
+            .map(|(_, line)| serde_json::from_str(line))
+            .collect()
+    } else {
+        lines
+            .iter()
+            .map(|(_, line)| serde_json::from_str(line))
+            .collect()
+    }
+}
+
+struct StreamVisitor<'a, F> {
+    path: &'a str,
+    strict: bool,
+    unknown_fields: &'a mut UnknownFields,
+    on_message: F,
+}
+
+impl<'de, F> Visitor<'de> for StreamVisitor<'_, F>
+where
+    F: FnMut(RawMessage) -> anyhow::Result<()>,
+{
+    type Value = usize;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of messages")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<usize, A::Error> {
+        let mut count = 0;
+        loop {
+            let raw = if self.strict {
+                let Some(value) = seq.next_element::<Value>()? else {
+                    break;
+                };
+                self.unknown_fields.scan_record(self.path, count, &value);
+                RawMessage::deserialize(value).map_err(de::Error::custom)?
+            } else {
+                let Some(raw) = seq.next_element::<RawMessage>()? else {
+                    break;
+                };
+                raw
+            };
+            (self.on_message)(raw).map_err(de::Error::custom)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::FallbackStats;
+
+    #[test]
+    fn test_ndjson_skips_bad_lines() {
+        let mut loader = Loader::new(false);
+        let text = "{\"message_id\": \"1\", \"content\": \"a\", \"author_id\": \"9\"}\n\nnot json\n{\"message_id\": \"2\", \"content\": \"b\", \"author_id\": \"9\"}\n";
+        assert!(is_ndjson(text));
+        let records = loader.parse_ndjson("feed.ndjson", text, 0);
+        assert_eq!(records.len(), 2);
+        assert_eq!(loader.skipped_records, vec!["feed.ndjson:3"]);
+    }
+
+    #[test]
+    fn test_read_prefetched_parses_chunks_like_whole_files() {
+        let dir = std::env::temp_dir().join(format!("dua-chunks-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let record = |i: usize| {
+            format!(
+                "{{\"message_id\": \"{}\", \"content\": \"héllo {}\", \"author_id\": \"9\"}}",
+                i, i
+            )
+        };
+        let array = format!(
+            "\u{FEFF}[{}]",
+            (0..50).map(record).collect::<Vec<_>>().join(",")
+        );
+        let ndjson: String = (0..STREAM_BATCH_LINES + 10)
+            .map(|i| record(i) + "\n")
+            .collect();
+        let latin1 =
+            b"[{\"message_id\": \"1\", \"content\": \"caf\xe9\", \"author_id\": \"9\"}]".to_vec();
+
+        for (name, bytes, count) in [
+            ("a.json", array.into_bytes(), 50),
+            ("b.ndjson", ndjson.into_bytes(), STREAM_BATCH_LINES + 10),
+            ("c.json", latin1, 1),
+        ] {
+            let path = dir.join(name);
+            fs::write(&path, &bytes).unwrap();
+            let mut loader = Loader::new(true);
+            let chunks = BufReader::with_capacity(7, bytes.as_slice());
+            let streamed = loader.read_prefetched(&path, chunks).unwrap();
+            let whole = Loader::new(true).read_raw_messages(&path).unwrap();
+            assert_eq!(streamed.len(), count);
+            let texts = |records: Vec<RawMessage>| -> Vec<(String, String)> {
+                records
+                    .into_iter()
+                    .map(|raw| raw.into_message(&mut FallbackStats::default()))
+                    .map(|msg| (msg.message_id, msg.content))
+                    .collect()
+            };
+            assert_eq!(texts(streamed), texts(whole));
+            assert!(loader.unknown_fields.is_empty());
+        }
+
+        // Not UTF-8: read again whole and decoded.
+        let mut loader = Loader::new(false);
+        let path = dir.join("c.json");
+        let bytes = fs::read(&path).unwrap();
+        let records = loader
+            .read_prefetched(&path, BufReader::new(bytes.as_slice()))
+            .unwrap();
+        let msg = records.into_iter().next().unwrap();
+        assert_eq!(
+            msg.into_message(&mut FallbackStats::default()).content,
+            "café"
+        );
+        assert_eq!(loader.decoded_files[0].1, TextEncoding::Latin1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_ndjson_tail_leaves_partial_line() {
+        let path = std::env::temp_dir().join(format!("dua-tail-{}.ndjson", std::process::id()));
+        let line = "{\"message_id\": \"1\", \"content\": \"a\", \"author_id\": \"9\"}\n";
+        fs::write(&path, format!("{}{{\"message_id\": \"2\"", line)).unwrap();
+        let mut loader = Loader::new(false);
+        let tail = loader.read_ndjson_tail(&path, 0, 0).unwrap();
+        assert_eq!(tail.records.len(), 1);
+        assert_eq!(tail.offset, line.len() as u64);
+        assert_eq!(tail.lines, 1);
+
+        let next = loader.read_ndjson_tail(&path, tail.offset, 1).unwrap();
+        assert!(next.records.is_empty());
+        assert_eq!(next.offset, tail.offset);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_expand_inputs_walks_directories() {
+        let dir = std::env::temp_dir().join(format!("dua-expand-{}", std::process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("b.json"), "[]").unwrap();
+        fs::write(dir.join("a.json"), "[]").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        fs::write(dir.join("nested").join("c.json"), "[]").unwrap();
+
+        let files = expand_inputs(std::slice::from_ref(&dir)).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|f| f.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["a.json", "b.json", "c.json"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_fields_first_occurrence() {
+        let records: Vec<Value> = serde_json::from_str(
+            r#"[
+                {"message_id": "1", "content": "a", "author_id": "9"},
+                {"message_id": "2", "content": "b", "author_id": "9", "channel": "x"},
+                {"message_id": "3", "content": "c", "author_id": "9", "channel": "y", "bot": true}
+            ]"#,
+        )
+        .unwrap();
+        let mut unknown = UnknownFields::default();
+        unknown.scan("data.json", &records);
+
+        assert_eq!(unknown.0.len(), 2);
+        assert_eq!(unknown.0["channel"].record, 1);
+        assert_eq!(unknown.0["channel"].message_id.as_deref(), Some("2"));
+        assert_eq!(unknown.0["bot"].record, 2);
+    }
+
+    #[test]
+    fn test_unknown_fields_in_referenced_message() {
+        let records: Vec<Value> = serde_json::from_str(
+            r#"[
+                {"message_id": "1", "referenced_message": {"author_name": "a", "embeds": []}},
+                {"message_id": "2", "referenced_message": null}
+            ]"#,
+        )
+        .unwrap();
+        let mut unknown = UnknownFields::default();
+        unknown.scan("data.json", &records);
+
+        assert_eq!(unknown.0.len(), 1);
+        assert_eq!(
+            unknown.0["referenced_message.embeds"].message_id.as_deref(),
+            Some("1")
+        );
+    }
+
+    #[test]
+    fn test_known_fields_are_not_reported() {
+        let records: Vec<Value> = serde_json::from_str(
+            r#"[{"message_id": "1", "content": "a", "timestamp": "t", "author_name": "n",
+                 "author_nickname": "k", "author_id": "9", "mentioned_user_name": null,
+                 "mentioned_user_nickname": null}]"#,
+        )
+        .unwrap();
+        let mut unknown = UnknownFields::default();
+        unknown.scan("data.json", &records);
+        assert!(unknown.is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+//! `--keyness`: the words each author uses significantly more often than
+//! everyone else, by comparing their word counts with the other authors'
+//! combined (the reference corpus).
+//!
+//! Both measures test the 2×2 table of a word's count and all other words,
+//! in the author's text and in the reference:
+//!
+//! - `ll`: Dunning's log-likelihood (G²), the usual choice in corpus
+//!   linguistics, which stays reliable for rare words.
+//! - `chi2`: Pearson's chi-square, which overrates rare words.
+//!
+//! Both follow a chi-square distribution with one degree of freedom, so
+//! words scoring below 3.84 (p ≥ 0.05) are left out, as are words the
+//! author uses no more often than the reference.
+
+use crate::AuthorAnalysis;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Keywords listed per author.
+const LIMIT: usize = 10;
+
+/// The 5% critical value of chi-square with one degree of freedom.
+const CRITICAL: f64 = 3.84;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measure {
+    LogLikelihood,
+    ChiSquare,
+}
+
+impl FromStr for Measure {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "ll" => Ok(Measure::LogLikelihood),
+            "chi2" => Ok(Measure::ChiSquare),
+            other => Err(format!("`{}` is not ll or chi2", other)),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Keyword {
+    pub word: String,
+    /// Uses by the author.
+    pub count: usize,
+    /// Uses by every other author.
+    pub reference_count: usize,
+    pub score: f64,
+}
+
+/// Fills in `keywords` for every author. A lone author has no reference
+/// and gets none.
+pub fn score(authors: &mut [AuthorAnalysis], measure: Measure) {
+    let mut global: HashMap<&str, usize> = HashMap::new();
+    for author in authors.iter() {
+        for (word, &count) in &author.word_frequency {
+            *global.entry(word).or_insert(0) += count;
+        }
+    }
+    let global_total: usize = global.values().sum();
+
+    let ranked: Vec<Vec<Keyword>> = authors
+        .iter()
+        .map(|author| {
+            let total: usize = author.word_frequency.values().sum();
+            let reference_total = global_total - total;
+            if total == 0 || reference_total == 0 {
+                return Vec::new();
+            }
+            let mut keywords: Vec<Keyword> = author
+                .word_frequency
+                .iter()
+                .filter_map(|(word, &count)| {
+                    let reference_count = global[word.as_str()] - count;
+                    // Over-use only: the author's rate must beat the reference's.
+                    if count * reference_total <= reference_count * total {
+                        return None;
+                    }
+                    let score = test(measure, count, total, reference_count, reference_total);
+                    (score >= CRITICAL).then(|| Keyword {
+                        word: word.clone(),
+                        count,
+                        reference_count,
+                        score,
+                    })
+                })
+                .collect();
+            keywords.sort_by(|a, b| {
+                b.score
+                    .total_cmp(&a.score)
+                    .then_with(|| a.word.cmp(&b.word))
+            });
+            keywords.truncate(LIMIT);
+            keywords
+        })
+        .collect();
+    for (author, keywords) in authors.iter_mut().zip(ranked) {
+        author.keywords = keywords;
+    }
+}
+
+/// The statistic for a word seen `a` times in `c` author words and `b`
+/// times in `d` reference words.
+fn test(measure: Measure, a: usize, c: usize, b: usize, d: usize) -> f64 {
+    let (a, b, c, d) = (a as f64, b as f64, c as f64, d as f64);
+    let n = c + d;
+    match measure {
+        Measure::LogLikelihood => {
+            let e1 = c * (a + b) / n;
+            let e2 = d * (a + b) / n;
+            let term = |observed: f64, expected: f64| {
+                if observed > 0.0 {
+                    observed * (observed / expected).ln()
+                } else {
+                    0.0
+                }
+            };
+            2.0 * (term(a, e1) + term(b, e2))
+        }
+        Measure::ChiSquare => {
+            // Cells: word and other words, in the author's text and the reference.
+            let (other_a, other_b) = (c - a, d - b);
+            let diff = a * other_b - b * other_a;
+            n * diff * diff / ((a + b) * (other_a + other_b) * c * d)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_author_analysis, MessageCounts};
+
+    fn author(words: &[(&str, usize)]) -> AuthorAnalysis {
+        let counts = MessageCounts {
+            total: 1,
+            ..MessageCounts::default()
+        };
+        let words = words.iter().map(|(w, c)| (w.to_string(), *c)).collect();
+        build_author_analysis(String::new(), String::new(), String::new(), counts, words)
+    }
+
+    #[test]
+    fn test_overused_words_are_keywords() {
+        let mut authors = vec![
+            author(&[("refund", 12), ("where", 10), ("hello", 2)]),
+            author(&[("where", 30), ("hello", 20), ("miss", 1)]),
+        ];
+        score(&mut authors, Measure::LogLikelihood);
+        let keywords: Vec<&str> = authors[0]
+            .keywords
+            .iter()
+            .map(|k| k.word.as_str())
+            .collect();
+        assert_eq!(keywords, ["refund"]);
+        assert_eq!(authors[0].keywords[0].reference_count, 0);
+        // "miss" is over-used, but a single use is not significant.
+        assert!(authors[1].keywords.iter().all(|k| k.word != "miss"));
+
+        score(&mut authors, Measure::ChiSquare);
+        assert_eq!(authors[0].keywords[0].word, "refund");
+        let mut lone = vec![author(&[("hi", 5)])];
+        score(&mut lone, Measure::LogLikelihood);
+        assert!(lone[0].keywords.is_empty());
+        assert!("tfidf".parse::<Measure>().is_err());
+    }
+}
@@ -0,0 +1,104 @@
+//! `--lemmatize` / `--lemma-file`: dictionary lemmatization, so inflected
+//! words are counted under their dictionary form ("was" under "be",
+//! "children" under "child").
+//!
+//! Unlike `--stem`, every counted word is a real word, but only listed
+//! forms are mapped; anything else is counted as written. Dictionaries hold
+//! one `form lemma` pair per line, separated by whitespace, with `#`
+//! starting a comment. The surface forms behind each lemma are reported in
+//! `word_variants`, as for stems.
+
+use crate::stem::Variants;
+use anyhow::Context;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use whatlang::Lang;
+
+/// Built-in dictionaries, by language.
+const BUILT_IN: &[(Lang, &str)] = &[(Lang::Eng, include_str!("../data/lemmas/en.tsv"))];
+
+#[derive(Debug, Clone, Default)]
+pub struct Lemmatizer(HashMap<String, String>);
+
+impl Lemmatizer {
+    /// The built-in dictionary for `lang`, extended (and overridden) by the
+    /// pairs in `file`.
+    pub fn load(lang: Option<Lang>, file: Option<&Path>) -> anyhow::Result<Self> {
+        let mut lemmatizer = Lemmatizer::default();
+        if let Some(lang) = lang {
+            let Some((_, dictionary)) = BUILT_IN.iter().find(|(built_in, _)| *built_in == lang)
+            else {
+                anyhow::bail!(
+                    "no built-in lemma dictionary for {}; use --lemma-file",
+                    lang.eng_name()
+                );
+            };
+            lemmatizer.add(dictionary, "built-in dictionary")?;
+        }
+        if let Some(path) = file {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("failed to read lemmas {}", path.display()))?;
+            lemmatizer.add(&text, &path.display().to_string())?;
+        }
+        Ok(lemmatizer)
+    }
+
+    fn add(&mut self, dictionary: &str, source: &str) -> anyhow::Result<()> {
+        for (i, line) in dictionary.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next(), fields.next()) {
+                (None, _, _) => {}
+                (Some(form), Some(lemma), None) => {
+                    self.0.insert(form.to_lowercase(), lemma.to_lowercase());
+                }
+                _ => anyhow::bail!("{} line {}: expected `form lemma`", source, i + 1),
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces each listed word with its lemma, recording every word under
+    /// the lemma it is counted as.
+    pub fn apply(&self, words: &mut [String], variants: &mut Variants) {
+        for word in words {
+            match self.0.get(word.as_str()) {
+                Some(lemma) => {
+                    let surface = std::mem::replace(word, lemma.clone());
+                    variants.record(word, surface);
+                }
+                None => variants.record(word, word.clone()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_built_in_and_custom_dictionaries() {
+        let dir = std::env::temp_dir().join(format!("dua-lemmas-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let extra = dir.join("lemmas.txt");
+        fs::write(&extra, "# slang\nu you\nwas was\n").unwrap();
+
+        let lemmatizer = Lemmatizer::load(Some(Lang::Eng), Some(&extra)).unwrap();
+        let mut words: Vec<String> = ["was", "children", "banned", "u", "server", "saw"]
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let mut variants = Variants::default();
+        lemmatizer.apply(&mut words, &mut variants);
+        // The custom file overrides the built-in `was be`.
+        assert_eq!(words, ["was", "child", "ban", "you", "server", "see"]);
+        assert_eq!(variants.into_map()["child"], ["children"]);
+
+        fs::write(&extra, "form lemma extra\n").unwrap();
+        assert!(Lemmatizer::load(None, Some(&extra)).is_err());
+        assert!(Lemmatizer::load(Some(Lang::Deu), None).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,13 @@
+//! The message model and filters behind `deleted-user-analyzer`, for
+//! programs that want to select messages the same way without the command
+//! line. [`chain::MessageFilter`] is the extension point: the target check
+//! and every built-in filter implement it, and so does any closure.
+
+pub mod chain;
+pub mod content;
+pub mod expr;
+pub mod filter;
+pub mod fingerprint;
+pub mod message;
+pub mod snowflake;
+pub mod target;
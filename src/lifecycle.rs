@@ -0,0 +1,141 @@
+//! Coarse classification of how an author's messages to the target are
+//! spread over time, so long reports can be skimmed by pattern.
+
+use crate::message::Message;
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashSet;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Authors need at least this many messages to count as escalating.
+const ESCALATING_MIN_MESSAGES: usize = 5;
+/// The last third of the author's active span must hold this many times
+/// the messages of the first third.
+const ESCALATING_RATIO: usize = 2;
+/// Sustained authors message on at least this share of the days in their
+/// active span...
+const SUSTAINED_DAY_SHARE: f64 = 0.25;
+/// ...and on at least this many distinct days.
+const SUSTAINED_MIN_DAYS: usize = 3;
+
+#[derive(Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Lifecycle {
+    /// One message, or all of them within a single day.
+    OneOff,
+    /// A handful of active days spread thinly over a long span.
+    Sporadic,
+    /// Regular messages over a span of days.
+    Sustained,
+    /// Message rate rising sharply toward the end of the span.
+    Escalating,
+}
+
+impl Lifecycle {
+    pub const ALL: [Lifecycle; 4] = [
+        Lifecycle::OneOff,
+        Lifecycle::Sporadic,
+        Lifecycle::Sustained,
+        Lifecycle::Escalating,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lifecycle::OneOff => "one-off",
+            Lifecycle::Sporadic => "sporadic",
+            Lifecycle::Sustained => "sustained",
+            Lifecycle::Escalating => "escalating",
+        }
+    }
+}
+
+/// Classifies an author's messages, counting calendar days in `tz`. `None`
+/// when none has a usable time.
+pub fn classify(msgs: &[Message], tz: Tz) -> Option<Lifecycle> {
+    let mut times: Vec<i64> = msgs
+        .iter()
+        .filter_map(Message::parsed_timestamp)
+        .map(|ts| ts.timestamp_millis())
+        .collect();
+    times.sort_unstable();
+    classify_times(&times, tz)
+}
+
+/// Classifies an author by the sorted Unix-millisecond times of their
+/// messages.
+pub fn classify_times(times: &[i64], tz: Tz) -> Option<Lifecycle> {
+    let (&first, &last) = (times.first()?, times.last()?);
+    let span = last - first;
+    if span < DAY_MS {
+        return Some(Lifecycle::OneOff);
+    }
+
+    if times.len() >= ESCALATING_MIN_MESSAGES {
+        let third = span / 3;
+        let early = times.iter().filter(|&&t| t <= first + third).count();
+        let late = times.iter().filter(|&&t| t >= last - third).count();
+        if late >= early * ESCALATING_RATIO {
+            return Some(Lifecycle::Escalating);
+        }
+    }
+
+    let day = |ms: i64| -> NaiveDate {
+        DateTime::<Utc>::from_timestamp_millis(ms)
+            .unwrap_or_default()
+            .with_timezone(&tz)
+            .date_naive()
+    };
+    let active_days = times.iter().map(|&t| day(t)).collect::<HashSet<_>>().len();
+    let (first_day, last_day) = (day(first), day(last));
+    let span_days = ((last_day - first_day).num_days() + 1) as f64;
+    if active_days >= SUSTAINED_MIN_DAYS && active_days as f64 / span_days >= SUSTAINED_DAY_SHARE {
+        return Some(Lifecycle::Sustained);
+    }
+    Some(Lifecycle::Sporadic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(days: &[f64]) -> Vec<Message> {
+        days.iter()
+            .map(|day| Message {
+                message_id: "x".to_string(),
+                timestamp: DateTime::from_timestamp_millis((day * DAY_MS as f64) as i64)
+                    .unwrap()
+                    .to_rfc3339(),
+                ..Message::default()
+            })
+            .collect()
+    }
+
+    fn classify_utc(msgs: &[Message]) -> Option<Lifecycle> {
+        classify(msgs, Tz::UTC)
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify_utc(&[]), None);
+        assert_eq!(classify_utc(&at(&[10.0])), Some(Lifecycle::OneOff));
+        assert_eq!(
+            classify_utc(&at(&[10.0, 10.2, 10.5])),
+            Some(Lifecycle::OneOff)
+        );
+        assert_eq!(
+            classify_utc(&at(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0])),
+            Some(Lifecycle::Sustained)
+        );
+        assert_eq!(
+            classify_utc(&at(&[0.0, 40.0, 90.0])),
+            Some(Lifecycle::Sporadic)
+        );
+        assert_eq!(
+            classify_utc(&at(&[0.0, 20.0, 28.0, 29.0, 29.5, 30.0])),
+            Some(Lifecycle::Escalating)
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! `link_stats`: the links sent to the target, by domain, for spotting
+//! coordinated link spam.
+//!
+//! Links are counted in every message, repeats included, since spam is
+//! often the same link posted again and again.
+
+use crate::content;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Domains listed in `link_stats`.
+const LIMIT: usize = 20;
+
+/// One author's links.
+#[derive(Debug, Clone, Default)]
+pub struct AuthorLinks {
+    /// Links by domain.
+    pub domains: BTreeMap<String, usize>,
+    /// Messages with at least one link.
+    pub messages: usize,
+}
+
+impl AuthorLinks {
+    pub fn record(&mut self, text: &str) {
+        let mut found = false;
+        for domain in content::urls(text)
+            .into_iter()
+            .filter_map(content::url_domain)
+        {
+            *self.domains.entry(domain).or_insert(0) += 1;
+            found = true;
+        }
+        if found {
+            self.messages += 1;
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct LinkStats {
+    pub total_links: usize,
+    pub messages_with_links: usize,
+    /// The most linked domains, most links first.
+    pub domains: Vec<DomainCount>,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct DomainCount {
+    pub domain: String,
+    pub links: usize,
+    /// Authors who linked the domain.
+    pub authors: usize,
+}
+
+/// Totals over every author's links, or `None` when nobody sent one.
+pub fn summarize<'a>(
+    authors: impl IntoIterator<Item = (&'a BTreeMap<String, usize>, usize)>,
+) -> Option<LinkStats> {
+    let mut by_domain: HashMap<&str, DomainCount> = HashMap::new();
+    let mut messages_with_links = 0;
+    for (domains, messages) in authors {
+        messages_with_links += messages;
+        for (domain, &links) in domains {
+            let entry = by_domain.entry(domain).or_insert_with(|| DomainCount {
+                domain: domain.clone(),
+                links: 0,
+                authors: 0,
+            });
+            entry.links += links;
+            entry.authors += 1;
+        }
+    }
+    if by_domain.is_empty() {
+        return None;
+    }
+    let mut domains: Vec<DomainCount> = by_domain.into_values().collect();
+    let total_links = domains.iter().map(|d| d.links).sum();
+    domains.sort_by(|a, b| b.links.cmp(&a.links).then_with(|| a.domain.cmp(&b.domain)));
+    domains.truncate(LIMIT);
+    Some(LinkStats {
+        total_links,
+        messages_with_links,
+        domains,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domains_counted_per_author() {
+        let mut alice = AuthorLinks::default();
+        alice.record("https://spam.example/a https://www.spam.example/b");
+        alice.record("https://spam.example/a");
+        alice.record("no links here");
+        let mut bob = AuthorLinks::default();
+        bob.record("look <https://news.site/story>");
+        bob.record("https://spam.example/c");
+
+        assert_eq!(alice.messages, 2);
+        assert_eq!(alice.domains["spam.example"], 3);
+        let stats = summarize([
+            (&alice.domains, alice.messages),
+            (&bob.domains, bob.messages),
+        ])
+        .unwrap();
+        assert_eq!((stats.total_links, stats.messages_with_links), (5, 4));
+        let top = &stats.domains[0];
+        assert_eq!(
+            (top.domain.as_str(), top.links, top.authors),
+            ("spam.example", 4, 2)
+        );
+        assert!(summarize([(&BTreeMap::new(), 0)]).is_none());
+    }
+}
@@ -0,0 +1,142 @@
+//! Locale-aware formatting of counts, percentages and dates for console
+//! output. JSON output is never localized.
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    thousands: &'static str,
+    decimal: char,
+    date_format: &'static str,
+}
+
+/// Supported tags and their conventions. Lookups fall back from
+/// `language-REGION` to the first entry for the language.
+const LOCALES: &[(&str, Locale)] = &[
+    ("en-us", Locale::new(",", '.', "%m/%d/%Y")),
+    ("en-gb", Locale::new(",", '.', "%d/%m/%Y")),
+    ("de-de", Locale::new(".", ',', "%d.%m.%Y")),
+    ("fr-fr", Locale::new("\u{202F}", ',', "%d/%m/%Y")),
+    ("es-es", Locale::new(".", ',', "%d/%m/%Y")),
+    ("it-it", Locale::new(".", ',', "%d/%m/%Y")),
+    ("nl-nl", Locale::new(".", ',', "%d-%m-%Y")),
+    ("pt-br", Locale::new(".", ',', "%d/%m/%Y")),
+    ("pl-pl", Locale::new("\u{00A0}", ',', "%d.%m.%Y")),
+    ("ru-ru", Locale::new("\u{00A0}", ',', "%d.%m.%Y")),
+    ("sv-se", Locale::new("\u{00A0}", ',', "%Y-%m-%d")),
+    ("ja-jp", Locale::new(",", '.', "%Y/%m/%d")),
+];
+
+impl Locale {
+    const fn new(thousands: &'static str, decimal: char, date_format: &'static str) -> Self {
+        Locale {
+            thousands,
+            decimal,
+            date_format,
+        }
+    }
+
+    /// Unlocalized output: plain digits and ISO dates.
+    pub const fn raw() -> Self {
+        Locale::new("", '.', "%Y-%m-%d")
+    }
+
+    pub fn count(&self, n: usize) -> String {
+        let digits = n.to_string();
+        if self.thousands.is_empty() {
+            return digits;
+        }
+        let mut out = String::with_capacity(digits.len() + digits.len() / 3 * self.thousands.len());
+        for (i, c) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push_str(self.thousands);
+            }
+            out.push(c);
+        }
+        out
+    }
+
+    pub fn decimal(&self, value: f64, places: usize) -> String {
+        let formatted = format!("{:.*}", places, value);
+        if self.decimal == '.' {
+            formatted
+        } else {
+            formatted.replace('.', &self.decimal.to_string())
+        }
+    }
+
+    /// Formats a 0-1 share as a percentage with one decimal place.
+    pub fn percent(&self, share: f64) -> String {
+        format!("{}%", self.decimal(share * 100.0, 1))
+    }
+
+    /// The date of `ts` in `tz`.
+    pub fn date(&self, ts: &DateTime<Utc>, tz: Tz) -> String {
+        ts.with_timezone(&tz).format(self.date_format).to_string()
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale::raw()
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        let tag = tag.trim().to_lowercase().replace('_', "-");
+        let language = tag.split('-').next().unwrap_or_default();
+        LOCALES
+            .iter()
+            .find(|(known, _)| *known == tag)
+            .or_else(|| {
+                LOCALES
+                    .iter()
+                    .find(|(known, _)| known.split('-').next() == Some(language))
+            })
+            .map(|(_, locale)| *locale)
+            .ok_or_else(|| {
+                let known: Vec<&str> = LOCALES.iter().map(|(tag, _)| *tag).collect();
+                format!("unsupported locale; expected one of {}", known.join(", "))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_separators() {
+        let us: Locale = "en-US".parse().unwrap();
+        let de: Locale = "de_DE".parse().unwrap();
+        assert_eq!(us.count(1234567), "1,234,567");
+        assert_eq!(us.count(999), "999");
+        assert_eq!(de.count(1234567), "1.234.567");
+        assert_eq!(Locale::raw().count(1234567), "1234567");
+    }
+
+    #[test]
+    fn test_decimal_and_dates() {
+        let de: Locale = "de".parse().unwrap();
+        let ts = DateTime::parse_from_rfc3339("2024-03-26T20:12:45Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(de.percent(0.125), "12,5%");
+        assert_eq!(de.date(&ts, Tz::UTC), "26.03.2024");
+        assert_eq!(
+            "en-US".parse::<Locale>().unwrap().date(&ts, Tz::UTC),
+            "03/26/2024"
+        );
+        assert_eq!(de.date(&ts, Tz::Asia__Tokyo), "27.03.2024");
+    }
+
+    #[test]
+    fn test_unknown_locale_is_rejected() {
+        assert!("xx-YY".parse::<Locale>().is_err());
+    }
+}
@@ -1,286 +1,1550 @@
-use clap::Parser;
+mod batch;
+mod bounded;
+mod buckets;
+mod channels;
+mod checkpoint;
+mod cjk;
+mod cluster;
+mod collocations;
+mod concordance;
+mod conflicts;
+mod cooccurrence;
+mod demo;
+mod diff;
+mod encoding;
+mod events;
+mod explain;
+mod filterstats;
+mod fuzzy;
+mod gaps;
+mod hapax;
+mod inactive;
+mod input;
+mod keyness;
+mod leet;
+mod lemma;
+mod lifecycle;
+mod links;
+mod locale;
+mod ngram;
+mod nicknames;
+mod optout;
+mod output;
+mod paths;
+mod pings;
+mod pipeline;
+mod pivot;
+mod prefetch;
+mod radar;
+mod readability;
+mod readonly;
+mod references;
+mod report;
+mod richness;
+mod sampling;
+mod sanitize;
+mod schema;
+mod sentiment;
+mod simd;
+mod similarity;
+mod stem;
+mod stopwords;
+mod summary;
+mod tfidf;
+mod timeline;
+mod tokenize;
+mod topics;
+mod toxicity;
+mod update;
+mod usage;
+mod vocab;
+mod warnings;
+mod watch;
+mod zipf;
+
+use deleted_user_analyzer::{
+    chain, content, expr, filter, fingerprint, message, snowflake, target,
+};
+
+use batch::BatchResult;
+use buckets::{BucketCounts, Period, WordBucket, WordTrends};
+use channels::{ChannelCount, ChannelTally};
+use checkpoint::RunState;
+use chrono::{DateTime, SecondsFormat, Utc};
+use cjk::CjkMode;
+use clap::{Parser, Subcommand};
+use cluster::AuthorCluster;
+use collocations::{Collocation, Measure, PairCounts};
+use concordance::ConcordanceLine;
+use conflicts::AuthorConflict;
+use content::{BlankCounts, CustomEmojiCount, CustomEmojiTally};
+use cooccurrence::Cooccurrence;
+use events::EventSummary;
+use explain::MatchExplanation;
+use filter::TimeBound;
+use filterstats::FilterStats;
+use gaps::DeletionGaps;
+use inactive::InactiveUser;
+use input::Loader;
+use keyness::Keyword;
+use lifecycle::Lifecycle;
+use links::{AuthorLinks, LinkStats};
+use locale::Locale;
+use message::Message;
+use optout::OptOutSummary;
+use output::OutputGuard;
+use pings::TargetIds;
+use pipeline::Pipeline;
+use pivot::Pivot;
+use radar::{RadarInputs, RadarProfile};
 use rayon::prelude::*;
-use serde::{Deserialize, Serialize};
+use readability::ReadabilityTally;
+use references::{ReferenceTally, References};
+use regex::Regex;
+use report::display_results;
+use sampling::{SampleSpec, Sampler, SamplingInfo};
+use schemars::JsonSchema;
+use sentiment::{Sentiment, SentimentTally};
+use serde::Serialize;
+use similarity::SimilarAuthors;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use stem::Variants;
+use target::TargetGroup;
+use timeline::TimelineEntry;
+use tokenize::TokenizerMode;
+use topics::Topic;
+use toxicity::{FlagTally, FlaggedMessage};
+use usage::UsageRecord;
+use warnings::{Warning, WarningKind, Warnings};
+use zipf::RankFrequency;
 
 #[derive(Parser)]
 #[command(name = "deleted-user-analyzer")]
 #[command(about = "Efficiently analyze JSON files for deleted user mentions")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(short, long, required_unless_present = "emit_output_schema", num_args = 1..)]
+    input: Vec<PathBuf>,
     #[arg(short, long)]
-    input: String,
-    #[arg(short, long)]
-    output: Option<String>,
+    output: Option<PathBuf>,
     #[arg(short, long)]
     verbose: bool,
     #[arg(long, default_value = "3")]
     min_word_length: usize,
+    /// Drop longer words (in characters), such as base64 blobs and keyboard mashes; 0 keeps every word
+    #[arg(long, default_value = "40")]
+    max_word_length: usize,
+    /// Drop words made only of digits, such as "456"
+    #[arg(long)]
+    drop_numbers: bool,
+    /// Drop words this regex matches (checked against the lowercased word; anchor with ^...$ to match whole words)
+    #[arg(long, value_name = "REGEX")]
+    drop_tokens_matching: Option<Regex>,
+    /// Characters kept inside words besides letters and digits, e.g. "'-_"
+    #[arg(long, value_name = "CHARS", default_value = "")]
+    token_chars: String,
+    /// How content is split into words: simple, or unicode for UAX #29 word boundaries
+    #[arg(long, value_name = "MODE", default_value = "simple")]
+    tokenizer: TokenizerMode,
+    /// Segment Chinese and Japanese text into words: jieba (dictionary) or bigram
+    #[arg(long, value_name = "MODE")]
+    cjk: Option<CjkMode>,
+    /// Count words by their Snowball stem in this language (ISO code, e.g. en), listing the forms seen in word_variants
+    #[arg(long, value_name = "LANG", value_parser = filter::parse_language, conflicts_with = "max_memory")]
+    stem: Option<whatlang::Lang>,
+    /// Count words by their dictionary form (lemma) in this language: en
+    #[arg(long, value_name = "LANG", value_parser = filter::parse_language, conflicts_with_all = ["stem", "max_memory"])]
+    lemmatize: Option<whatlang::Lang>,
+    /// Lemma dictionary of `form lemma` lines, extending or replacing the built-in one
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["stem", "max_memory"])]
+    lemma_file: Option<PathBuf>,
+    /// Count misspellings within a small edit distance of a more frequent word as that word
+    #[arg(long)]
+    fuzzy_words: bool,
+    /// Also count word sequences of these lengths, e.g. 2,3 for bigrams and trigrams
+    #[arg(long, value_name = "N", value_delimiter = ',', value_parser = ngram::parse_size, conflicts_with = "max_memory")]
+    ngrams: Vec<usize>,
+    /// Rank each author's characteristic word pairs by pmi (mutual information) or llr (log-likelihood)
+    #[arg(long, value_name = "MEASURE", conflicts_with = "max_memory")]
+    collocations: Option<Measure>,
+    /// Leave word pairs seen fewer times than this out of --collocations
+    #[arg(long, value_name = "N", default_value_t = collocations::DEFAULT_MIN_COUNT)]
+    collocation_min_count: usize,
+    /// List the words each author over-uses compared with all other authors, by ll (log-likelihood) or chi2 (chi-square)
+    #[arg(long, value_name = "MEASURE")]
+    keyness: Option<keyness::Measure>,
+    /// List the words each author, and the whole result, used exactly once
+    #[arg(long)]
+    hapax: bool,
+    /// Add the global vocabulary as a rank-frequency table with its fitted Zipf exponent
+    #[arg(long)]
+    rank_frequency: bool,
+    /// List every use of these words with the words around it, per author (repeatable or comma-separated)
+    #[arg(long, value_name = "WORD", value_delimiter = ',')]
+    concordance: Vec<String>,
+    /// Words of context on each side for --concordance
+    #[arg(long, value_name = "N", default_value_t = concordance::DEFAULT_CONTEXT)]
+    concordance_context: usize,
+    /// Group the messages' words into N topics (LDA), with each author's share of every topic
+    #[arg(long, value_name = "N", value_parser = topics::parse_count, conflicts_with = "max_memory")]
+    topics: Option<usize>,
+    /// Gibbs sampling passes for --topics
+    #[arg(long, value_name = "N", default_value_t = topics::DEFAULT_ITERATIONS)]
+    topic_iterations: usize,
+    /// Detect each unique message's language, listing the languages per author and overall
+    #[arg(long)]
+    detect_languages: bool,
+    /// Score each author's messages for sentiment with the bundled English lexicon, listing the most negative
+    #[arg(long)]
+    sentiment: bool,
+    /// Drop these languages' built-in stopwords before counting words (comma-separated ISO codes, e.g. en,de)
+    #[arg(long, value_name = "LANGS", value_delimiter = ',', value_parser = filter::parse_language)]
+    stopwords: Vec<whatlang::Lang>,
+    /// Also drop the words in this file (whitespace-separated, # comments)
+    #[arg(long, value_name = "FILE")]
+    stopwords_file: Option<PathBuf>,
+    /// Ignore blockquoted lines (`> ...`, `>>> ...`) when counting words
+    #[arg(long)]
+    strip_quotes: bool,
+    /// Ignore links when counting words
+    #[arg(long)]
+    strip_urls: bool,
+    /// Ignore user, role and channel mentions when counting words
+    #[arg(long)]
+    strip_mentions: bool,
+    /// Count words inside fenced and inline code, which are ignored by default
+    #[arg(long)]
+    include_code: bool,
+    /// No effect: code is ignored by default. Kept so existing scripts still run
+    #[arg(long, hide = true, conflicts_with = "include_code")]
+    strip_code_blocks: bool,
+    /// Ignore Unicode emoji when counting words (custom emoji never count)
+    #[arg(long)]
+    strip_emoji: bool,
+    /// Ignore spoilered text (`||...||`) when counting words
+    #[arg(long)]
+    strip_spoilers: bool,
+    /// Map leetspeak (`h4t3`) and lookalike letters from other scripts onto plain letters before counting words
+    #[arg(long)]
+    normalize_leet: bool,
+    /// Write nothing but --output, in place and outside the inputs; for mounted evidence
+    #[arg(long, conflicts_with_all = readonly::WRITING_OPTIONS.iter().copied())]
+    readonly: bool,
+    /// Reject input records containing fields outside the message schema
+    #[arg(long)]
+    strict: bool,
+    /// Stream inputs and spill word counts to disk to stay near this many MB
+    #[arg(long, value_name = "MB", conflicts_with_all = ["checkpoint", "resume"])]
+    max_memory: Option<usize>,
+    /// Write a resumable state file after every processed input file
+    #[arg(long, value_name = "STATE")]
+    checkpoint: Option<PathBuf>,
+    /// Resume an interrupted run from a state file written by --checkpoint
+    #[arg(long, value_name = "STATE")]
+    resume: Option<PathBuf>,
+    /// Append a line with this run's duration and input sizes to a local file
+    #[arg(long, value_name = "FILE", conflicts_with = "watch")]
+    usage_log: Option<PathBuf>,
+    /// Format console numbers and dates for this locale (e.g. en-US, de-DE)
+    #[arg(long)]
+    locale: Option<Locale>,
+    /// Keep running and re-analyze whenever the inputs change
+    #[arg(long, conflicts_with_all = ["max_memory", "checkpoint", "resume"])]
+    watch: bool,
+    /// Analyze a reproducible random fraction of the messages (e.g. 0.05)
+    #[arg(long, value_name = "FRACTION", value_parser = sampling::parse_fraction)]
+    sample: Option<f64>,
+    /// Analyze a reproducible random sample of exactly N messages
+    #[arg(long, value_name = "N", conflicts_with_all = ["sample", "max_memory"])]
+    sample_n: Option<usize>,
+    /// Seed for --sample, --sample-n and --topics
+    #[arg(long, default_value = "0")]
+    seed: u64,
+    /// File of author IDs (one per line) to exclude from all outputs
+    #[arg(long, value_name = "FILE")]
+    opt_out: Option<PathBuf>,
+    /// Only analyze messages matching this expression, e.g.
+    /// 'author_id != "123" && timestamp >= 2022-01-01 && content ~ "ban"'
+    #[arg(long, value_name = "EXPR", value_parser = expr::parse)]
+    filter: Option<expr::Expr>,
+    /// Only analyze messages sent at or after this time (RFC 3339 or YYYY-MM-DD)
+    #[arg(long, value_parser = TimeBound::parse)]
+    since: Option<TimeBound>,
+    /// Only analyze messages sent at or before this time (RFC 3339 or YYYY-MM-DD)
+    #[arg(long, value_parser = TimeBound::parse)]
+    until: Option<TimeBound>,
+    /// Time zone for dates in filters and for day-based statistics (IANA name, e.g. Europe/Berlin)
+    #[arg(long, value_name = "TZ", value_parser = filter::parse_timezone)]
+    timezone: Option<chrono_tz::Tz>,
+    /// Only analyze messages with a snowflake ID greater than this
+    #[arg(long, value_name = "ID")]
+    after_id: Option<u64>,
+    /// Only analyze messages with a snowflake ID less than this
+    #[arg(long, value_name = "ID")]
+    before_id: Option<u64>,
+    /// User name or nickname to analyze messages to (repeatable; default: deleted users)
+    #[arg(long, value_name = "NAME")]
+    target: Vec<String>,
+    /// Regex matched against mentioned user names (repeatable; replaces the default pattern)
+    #[arg(long, value_name = "REGEX")]
+    target_regex: Vec<String>,
+    /// Compare --target names case-sensitively
+    #[arg(long)]
+    match_case: bool,
+    /// NFKC-normalize names before matching targets, so styled and fullwidth lookalikes match
+    #[arg(long)]
+    normalize_unicode: bool,
+    /// Analyze every message, not just those to the target
+    #[arg(long, conflicts_with_all = ["target", "target_regex", "targets_file"])]
+    no_target_filter: bool,
+    /// TOML file of labeled targets, each reported in its own section
+    #[arg(long, value_name = "FILE", conflicts_with = "max_memory")]
+    targets_file: Option<String>,
+    /// Target users mentioned or replied to DAYS or more after their last message in the inputs, instead of deleted users
+    #[arg(
+        long,
+        value_name = "DAYS",
+        conflicts_with_all = ["target", "target_regex", "targets_file", "no_target_filter", "max_memory"]
+    )]
+    inactive_targets: Option<u32>,
+    /// Only analyze messages in these channels (ID or name; repeatable or comma-separated)
+    #[arg(long, value_name = "CHANNEL", value_delimiter = ',')]
+    channel: Vec<String>,
+    /// Only analyze messages whose author has one of these roles (repeatable or comma-separated)
+    #[arg(long, value_name = "ROLE", value_delimiter = ',')]
+    role: Vec<String>,
+    /// Skip messages whose author has any of these roles (repeatable or comma-separated)
+    #[arg(long, value_name = "ROLE", value_delimiter = ',')]
+    exclude_role: Vec<String>,
+    /// Only analyze messages with attachments
+    #[arg(long, conflicts_with = "text_only")]
+    only_with_attachments: bool,
+    /// Skip messages with attachments
+    #[arg(long)]
+    text_only: bool,
+    /// Only analyze messages whose content matches this regex
+    #[arg(long, value_name = "REGEX")]
+    content_match: Option<Regex>,
+    /// Skip messages whose content matches this regex (repeatable)
+    #[arg(long, value_name = "REGEX", visible_alias = "exclude-content-regex")]
+    content_exclude: Vec<Regex>,
+    /// Skip messages containing any keyword listed in this file (one per line)
+    #[arg(long, value_name = "FILE")]
+    exclude_keyword_file: Option<String>,
+    /// Leave authors with fewer than N matching messages out of `authors_analysis`
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    min_author_messages: usize,
+    /// File of known events, one `YYYY-MM-DD description` per line, counted apart from the baseline
+    #[arg(long, value_name = "FILE")]
+    events: Option<PathBuf>,
+    /// Hours on each side of an event that count as part of it
+    #[arg(long, value_name = "HOURS", default_value_t = events::DEFAULT_WINDOW_HOURS)]
+    event_window: u32,
+    /// Drop messages inside event windows instead of bucketing them
+    #[arg(long, requires = "events")]
+    exclude_events: bool,
+    /// Count message IDs missing around the analyzed messages, for exports with sequential IDs
+    #[arg(long, conflicts_with = "max_memory")]
+    deletion_gaps: bool,
+    /// IDs on each side of an analyzed message that --deletion-gaps inspects
+    #[arg(long, value_name = "IDS", default_value_t = gaps::DEFAULT_WINDOW)]
+    gap_window: u64,
+    /// Only analyze authors who posted under a nickname other than their user name
+    #[arg(long, conflicts_with = "max_memory")]
+    nickname_differs: bool,
+    /// Only analyze authors seen under more than one nickname across the inputs
+    #[arg(long, conflicts_with = "max_memory")]
+    multiple_nicknames: bool,
+    /// Analyze system messages (pins, joins, boosts), which are skipped by default
+    #[arg(long)]
+    include_system: bool,
+    /// Skip messages flagged `is_bot` or `is_webhook`
+    #[arg(long)]
+    exclude_bots: bool,
+    /// Skip messages shorter than this many characters (after trimming)
+    #[arg(long, value_name = "N")]
+    min_content_chars: Option<usize>,
+    /// Skip messages longer than this many characters (after trimming)
+    #[arg(long, value_name = "N")]
+    max_content_chars: Option<usize>,
+    /// Only analyze messages detected as this language (ISO code or English name, e.g. en)
+    #[arg(long, value_name = "LANG", value_parser = filter::parse_language)]
+    language: Option<whatlang::Lang>,
+    /// Only analyze these author IDs (comma-separated)
+    #[arg(long, value_name = "IDS", value_delimiter = ',')]
+    authors: Vec<String>,
+    /// Only analyze the author IDs listed in this file (one per line)
+    #[arg(long, value_name = "FILE")]
+    authors_file: Option<String>,
+    /// Drop these author IDs before aggregation (comma-separated)
+    #[arg(long, value_name = "IDS", value_delimiter = ',')]
+    exclude_authors: Vec<String>,
+    /// Write a chronological timeline of the analyzed messages (.csv or .json)
+    #[arg(long, value_name = "FILE", conflicts_with = "max_memory")]
+    timeline: Option<PathBuf>,
+    /// List top words per period: day, week, month, quarter, year or a day count like 14d
+    #[arg(long, value_name = "PERIOD", conflicts_with = "max_memory")]
+    word_buckets: Option<Period>,
+    /// Count these words (comma-separated; the top 20 when none are given) per --word-buckets period
+    #[arg(long, value_name = "WORD", num_args = 0.., value_delimiter = ',', requires = "word_buckets")]
+    word_trends: Option<Vec<String>>,
+    /// Write an authors × channels matrix of message counts (.csv or .html)
+    #[arg(long, value_name = "FILE", conflicts_with = "max_memory")]
+    pivot: Option<PathBuf>,
+    /// Write which target rules matched each analyzed message (.csv or .json)
+    #[arg(long, value_name = "FILE", conflicts_with = "max_memory")]
+    explain_matches: Option<PathBuf>,
+    /// Group authors with similar vocabularies into K clusters (k-means), with each cluster's characteristic words
+    #[arg(long, value_name = "K", value_parser = cluster::parse_count, conflicts_with = "watch")]
+    cluster: Option<usize>,
+    /// Write the authors × authors cosine similarity of their word frequencies (.csv or .json)
+    #[arg(long, value_name = "FILE", conflicts_with = "watch")]
+    similarity: Option<PathBuf>,
+    /// Write a sparse word × word co-occurrence matrix of the counted words (.csv or .json)
+    #[arg(long, value_name = "FILE", conflicts_with = "max_memory")]
+    cooccurrence: Option<PathBuf>,
+    /// Words at most this many positions apart in a message co-occur for --cooccurrence; 0 for the whole message
+    #[arg(long, value_name = "N", default_value_t = cooccurrence::DEFAULT_WINDOW)]
+    cooccurrence_window: usize,
+    /// Also write one result per target and channel under DIR, with an index.json manifest
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["max_memory", "watch"])]
+    output_dir: Option<PathBuf>,
+    /// Mask a built-in list of common profanity in all outputs
+    #[arg(long)]
+    mask_profanity: bool,
+    /// File of extra terms to mask (one per line, trailing `*` for prefixes)
+    #[arg(long, value_name = "FILE")]
+    mask_words: Option<String>,
+    /// Flag messages containing built-in profanity or insult terms, and authors with many of them
+    #[arg(long)]
+    flag_toxicity: bool,
+    /// File of extra terms for --flag-toxicity (one per line, trailing `*` for prefixes)
+    #[arg(long, value_name = "FILE", requires = "flag_toxicity")]
+    flag_terms: Option<String>,
+    /// Share of an author's unique messages that must be flagged to flag the author
+    #[arg(long, value_name = "SHARE", default_value_t = toxicity::DEFAULT_THRESHOLD)]
+    flag_threshold: f64,
+    /// Refuse to write `--output` larger than this (MB) unless `--force` is given
+    #[arg(long, value_name = "MB", default_value_t = output::DEFAULT_MAX_OUTPUT_MB)]
+    max_output_mb: u64,
+    /// Write `--output` even when it exceeds `--max-output-mb`
+    #[arg(long)]
+    force: bool,
+    /// Leave the full word maps out of `--output`, keeping only the top words
+    #[arg(long)]
+    no_word_maps: bool,
+    /// Leave words seen fewer times than this out of the `--output` word maps
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    min_word_count: usize,
+    /// Count at most N distinct words per author, evicting the rarest
+    #[arg(long, value_name = "N")]
+    max_author_vocab: Option<usize>,
+    /// Print the JSON Schema of the --output format and exit; written to --output when given
+    #[arg(long)]
+    emit_output_schema: bool,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-struct Message {
-    message_id: String,
-    content: String,
-    timestamp: String,
-    author_name: String,
-    author_nickname: String,
-    author_id: String,
-    mentioned_user_name: Option<String>,
-    mentioned_user_nickname: Option<String>,
+#[derive(Subcommand)]
+enum Command {
+    /// Compare two exports by message ID: missing messages, edited content,
+    /// and authors appearing or disappearing
+    DiffExports {
+        /// The original export (file or directory)
+        a: PathBuf,
+        /// The re-export to check against it
+        b: PathBuf,
+        /// Write the full diff as JSON
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Analyze a small bundled synthetic export, as a reference for the
+    /// input schema and the output
+    Demo {
+        /// Write the full results as JSON
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Enable detailed output
+        #[arg(short, long)]
+        verbose: bool,
+        /// Also save the bundled export, to compare with your own data
+        #[arg(long, value_name = "FILE")]
+        export: Option<PathBuf>,
+    },
+    /// Replace this binary with the latest GitHub release, after checking
+    /// its SHA-256 against the release's SHA256SUMS
+    SelfUpdate {
+        /// Only report whether a newer release exists
+        #[arg(long)]
+        check: bool,
+    },
 }
 
-#[derive(Serialize, Debug, Clone)]
+impl Args {
+    fn sampler(&self) -> Option<Sampler> {
+        let spec = match (self.sample, self.sample_n) {
+            (Some(fraction), _) => SampleSpec::Fraction(fraction),
+            (None, Some(count)) => SampleSpec::Count(count),
+            (None, None) => return None,
+        };
+        Some(Sampler {
+            spec,
+            seed: self.seed,
+        })
+    }
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
 struct AuthorAnalysis {
     author_id: String,
     author_name: String,
     author_nickname: String,
+    /// Every nickname seen for the author across the inputs, under
+    /// `--nickname-differs` / `--multiple-nicknames`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    nicknames: Vec<String>,
     total_messages_to_deleted_user: usize,
     unique_message_count: usize,
+    blank_message_count: usize,
+    blank_message_share: f64,
+    mentions_per_message: f64,
+    mention_only_count: usize,
+    mention_only_share: f64,
+    /// Messages that tag the target (`<@id>`, `<@!id>` or `@name`).
+    direct_ping_count: usize,
+    /// Messages that reach the target without tagging them, such as
+    /// replies sent without a ping.
+    talked_about_count: usize,
+    /// Distinct counted words.
+    vocabulary_size: usize,
+    /// Distinct words per counted word; low for authors repeating
+    /// themselves.
+    type_token_ratio: f64,
+    /// Characters per counted word.
+    average_word_length: f64,
+    /// Words per sentence, over the messages as written.
+    average_sentence_length: f64,
+    /// Flesch reading ease: higher reads more easily.
+    flesch_reading_ease: f64,
+    /// Capitals per cased letter.
+    caps_ratio: f64,
+    /// Unique messages written wholly in capitals.
+    shouted_message_count: usize,
     word_frequency: BTreeMap<String, usize>,
     most_common_words: Vec<(String, usize)>,
+    /// `--ngrams` counts, keyed by the space-joined words.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    ngram_frequency: BTreeMap<String, usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    most_common_ngrams: Vec<(String, usize)>,
+    /// The author's words weighted by TF-IDF against every other author's,
+    /// most distinctive first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    top_tfidf_words: Vec<(String, f64)>,
+    /// `--keyness`: words the author uses significantly more often than
+    /// every other author, highest score first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    keywords: Vec<Keyword>,
+    /// `--hapax`: words the author used exactly once, alphabetically.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hapax_legomena: Vec<String>,
+    /// `--concordance`: each use of the words with its context.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    concordance: Vec<ConcordanceLine>,
+    /// `--sentiment`: lexicon scores over the author's unique messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sentiment: Option<Sentiment>,
+    /// `--flag-toxicity`: the author's flagged share reached `--flag-threshold`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    flagged: bool,
+    /// `--flag-toxicity`: unique messages containing a flagged term.
+    #[serde(skip_serializing_if = "is_zero")]
+    flagged_message_count: usize,
+    /// The oldest flagged messages, quoted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    flagged_excerpts: Vec<FlaggedMessage>,
+    /// Unicode emoji used, by base codepoint.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    emoji_frequency: BTreeMap<String, usize>,
+    /// Custom Discord emoji used, most used first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    custom_emoji: Vec<CustomEmojiCount>,
+    /// `--detect-languages`: unique messages by ISO 639-3 language code,
+    /// `und` where undetermined.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    language_frequency: BTreeMap<String, usize>,
+    /// Links sent, by domain, counting repeated messages.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    link_domains: BTreeMap<String, usize>,
+    #[serde(skip_serializing_if = "is_zero")]
+    messages_with_links: usize,
+    /// Channels the author pointed the target to.
+    #[serde(skip_serializing_if = "References::is_empty")]
+    references: References,
+    /// `--collocations`: the author's most characteristic word pairs.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    collocations: Vec<Collocation>,
+    /// Pair counts behind `collocations`, merged for the global ranking.
+    #[serde(skip)]
+    pair_counts: PairCounts,
+    /// `--cooccurrence` counts, merged into the written matrix.
+    #[serde(skip)]
+    cooccurrence: Cooccurrence,
+    /// `--topics`: the counted words of each unique message, until the
+    /// topics are fitted.
+    #[serde(skip)]
+    documents: Vec<Vec<String>>,
+    /// `--topics`: the share of the author's words in each topic, by topic
+    /// number.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    topic_proportions: Vec<f64>,
+    /// `--cluster`: the number of the author's cluster.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cluster: Option<usize>,
+    /// Words dropped from `word_frequency` by `--max-author-vocab`.
+    #[serde(skip_serializing_if = "is_zero")]
+    evicted_words: usize,
+    /// Top words per `--word-buckets` period.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    word_buckets: Vec<WordBucket>,
+    /// Full per-period counts behind `word_buckets`, merged into the global
+    /// series.
+    #[serde(skip)]
+    bucket_counts: BucketCounts,
+    /// Surface forms behind the stemmed, lemmatized or fuzzy-merged words,
+    /// merged into the global `word_variants`.
+    #[serde(skip)]
+    word_variants: Variants,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_message_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_message_at: Option<DateTime<Utc>>,
+    /// How the author's messages are spread over time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lifecycle: Option<Lifecycle>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    radar: Option<RadarProfile>,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, JsonSchema, Debug)]
 struct AnalysisResult {
+    /// Plain-language overview; filled in when the output is written.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    summary: String,
     total_messages: usize,
     messages_to_deleted_users: usize,
     unique_authors: usize,
+    /// Names and `/patterns/` matched instead of the default deleted-user
+    /// pattern, when targets are given.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    targets: Vec<String>,
+    /// One section per labeled target from `--targets-file`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    target_sections: Vec<TargetSection>,
+    blank_messages: BlankCounts,
     authors_analysis: Vec<AuthorAnalysis>,
     global_word_frequency: BTreeMap<String, usize>,
+    /// `--ngrams` counts over every author.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    global_ngram_frequency: BTreeMap<String, usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    most_common_ngrams: Vec<(String, usize)>,
+    /// Unicode emoji over every author, by base codepoint.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    emoji_frequency: BTreeMap<String, usize>,
+    /// Custom Discord emoji over every author, most used first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    custom_emoji: Vec<CustomEmojiCount>,
+    /// `--detect-languages` over every author.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    language_frequency: BTreeMap<String, usize>,
+    /// Links over every author, most linked domains first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link_stats: Option<LinkStats>,
+    /// `--collocations` over every author's pairs.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    collocations: Vec<Collocation>,
+    /// `--stem` / `--lemmatize` / `--fuzzy-words`: the words as written
+    /// behind each stem, lemma or merged spelling in the word maps, most
+    /// frequent first.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    word_variants: BTreeMap<String, Vec<String>>,
+    /// `--hapax`: words used exactly once over every author.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hapax_legomena: Vec<String>,
+    /// `--rank-frequency`: the global vocabulary by rank.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rank_frequency: Option<RankFrequency>,
+    /// Top words per `--word-buckets` period across all authors.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    word_buckets: Vec<WordBucket>,
+    /// `--cluster`: authors grouped by vocabulary, largest cluster first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    clusters: Vec<AuthorCluster>,
+    /// `--similarity`: the author pairs with the most similar vocabularies.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    similar_authors: Vec<SimilarAuthors>,
+    /// `--topics`: the fitted topics, largest first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    topics: Vec<Topic>,
+    /// `--word-trends`: chosen words counted per period.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    word_trends: Option<WordTrends>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    channels: Vec<ChannelCount>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    author_conflicts: Vec<AuthorConflict>,
+    /// Authors left out of `authors_analysis` by `--min-author-messages`.
+    #[serde(skip_serializing_if = "is_zero")]
+    omitted_authors: usize,
+    #[serde(skip_serializing_if = "is_zero")]
+    fallback_records: usize,
+    /// Messages counted because they reply to a deleted user without
+    /// mentioning them.
+    #[serde(skip_serializing_if = "is_zero")]
+    reply_only_matches: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<Warning>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sampling: Option<SamplingInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    opted_out: Option<OptOutSummary>,
+    /// Messages per `--events` window, and outside all of them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    events: Option<EventSummary>,
+    /// Messages removed by each active filter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filter_stats: Option<FilterStats>,
+    /// IDs missing from the export around the analyzed messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deletion_gaps: Option<DeletionGaps>,
+    /// Users the analyzed messages went to under `--inactive-targets`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    inactive_users: Vec<InactiveUser>,
+    /// `--timezone`, when not UTC: the zone in which calendar days were
+    /// counted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    timezone: Option<chrono_tz::Tz>,
+    /// Written separately by `--timeline`, never part of the JSON output.
+    #[serde(skip)]
+    timeline: Vec<TimelineEntry>,
+    /// Written separately by `--pivot`.
+    #[serde(skip)]
+    pivot: Pivot,
+    /// Written separately by `--explain-matches`.
+    #[serde(skip)]
+    explanations: Vec<MatchExplanation>,
+    /// Written separately by `--output-dir`.
+    #[serde(skip)]
+    batch: Vec<BatchResult>,
+    /// Written separately by `--cooccurrence`.
+    #[serde(skip)]
+    cooccurrence: Cooccurrence,
+}
+
+/// The per-author breakdown restricted to messages to one labeled target.
+#[derive(Serialize, JsonSchema, Debug)]
+struct TargetSection {
+    label: String,
+    names: Vec<String>,
+    total_messages: usize,
+    messages_to_target: usize,
+    unique_authors: usize,
+    authors_analysis: Vec<AuthorAnalysis>,
+}
+
+impl TargetSection {
+    fn new(group: &TargetGroup, authors_analysis: Vec<AuthorAnalysis>) -> Self {
+        TargetSection {
+            label: group.label.clone().unwrap_or_default(),
+            names: group.describe(),
+            total_messages: authors_analysis
+                .iter()
+                .map(|a| a.total_messages_to_deleted_user)
+                .sum(),
+            messages_to_target: authors_analysis
+                .iter()
+                .map(|a| a.unique_message_count)
+                .sum(),
+            unique_authors: authors_analysis.len(),
+            authors_analysis,
+        }
+    }
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    match &args.command {
+        Some(Command::DiffExports { a, b, output }) => return diff::run(a, b, output.as_deref()),
+        Some(Command::Demo {
+            output,
+            verbose,
+            export,
+        }) => return demo::run(output.as_deref(), *verbose, export.as_deref()),
+        Some(Command::SelfUpdate { check }) => return update::run(*check),
+        None => {}
+    }
+    // Before the schema too, which is written to --output.
+    if args.readonly {
+        readonly::check(&args)?;
+    }
+    if args.emit_output_schema {
+        return schema::emit(args.output.as_deref());
+    }
 
     if args.verbose {
-        println!("Starting analysis of: {}", args.input);
+        let inputs: Vec<String> = args
+            .input
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        println!("Starting analysis of: {}", inputs.join(", "));
     }
 
-    let file_stuff = fs::read_to_string(&args.input)?;
-    let msgs: Vec<Message> = serde_json::from_str(&file_stuff)?;
+    let pipeline = Pipeline::from_args(&args)?;
+    if args.watch {
+        return watch::run(&args, &pipeline);
+    }
 
-    if args.verbose {
-        println!("Loaded {} messages", msgs.len());
+    let (started_at, started) = (Utc::now(), Instant::now());
+    let result = match args.max_memory {
+        Some(max_memory_mb) => bounded::analyze(&args, &pipeline, max_memory_mb),
+        None => analyze_in_memory(&args, &pipeline),
+    };
+    if let Some(path) = &args.usage_log {
+        let bounded = args.max_memory.is_some();
+        UsageRecord::new(
+            &args.input,
+            bounded,
+            started_at,
+            started.elapsed(),
+            result.as_ref().ok(),
+        )
+        .append(path)?;
+    }
+    let mut result = result?;
+    let mut similarity = compare_authors(&args, &mut result);
+    pipeline.sanitizer.apply(&mut result);
+    if let Some(matrix) = &mut similarity {
+        pipeline.sanitizer.mask_similarity(matrix);
     }
 
-    let deleted_msgs: Vec<Message> = msgs
-        .into_par_iter()
-        .filter(|msg| {
-            msg.mentioned_user_name
-                .as_ref()
-                .map_or(false, |name| name.to_lowercase().contains("deleted user"))
-                || msg
-                    .mentioned_user_nickname
-                    .as_ref()
-                    .map_or(false, |nickname| {
-                        nickname.to_lowercase().contains("deleted user")
-                    })
-        })
+    display_results(&result, args.verbose, &args.locale.unwrap_or_default());
+
+    if let Some(output_path) = &args.output {
+        write_output(&mut result, output_path, &pipeline.output)?;
+        println!("Results saved to: {}", output_path.display());
+    }
+    if let Some(timeline_path) = &args.timeline {
+        timeline::write(&result.timeline, timeline_path)?;
+        println!("Timeline saved to: {}", timeline_path.display());
+    }
+    if let Some(pivot_path) = &args.pivot {
+        pivot::write(&result.pivot, pivot_path)?;
+        println!("Pivot table saved to: {}", pivot_path.display());
+    }
+    if let Some(path) = &args.explain_matches {
+        explain::write(&result.explanations, path)?;
+        println!("Match explanations saved to: {}", path.display());
+    }
+    if let (Some(path), Some(matrix)) = (&args.similarity, &similarity) {
+        similarity::write(matrix, path)?;
+        println!("Similarity matrix saved to: {}", path.display());
+    }
+    if let Some(path) = &args.cooccurrence {
+        cooccurrence::write(&result.cooccurrence, path)?;
+        println!("Co-occurrence matrix saved to: {}", path.display());
+    }
+    if let Some(dir) = &args.output_dir {
+        batch::write(dir, &mut result, &pipeline)?;
+        println!(
+            "Per-target and per-channel results saved to: {}",
+            dir.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Writes the JSON output, trimmed and size-checked by `guard`, via a
+/// temporary file and a rename, so readers never see a half-written output
+/// (`--watch` rewrites it while consumers poll it). Under `--readonly`,
+/// which allows no other file to be created, it is written in place.
+fn write_output(
+    result: &mut AnalysisResult,
+    output_path: &Path,
+    guard: &OutputGuard,
+) -> anyhow::Result<()> {
+    let output_path = paths::extended(output_path);
+    result.summary = summary::paragraph(result, &Locale::default());
+    guard.prune(result);
+    guard.check(result)?;
+    let output_json = serde_json::to_string_pretty(result)?;
+    if guard.in_place {
+        fs::write(&output_path, output_json)?;
+        return Ok(());
+    }
+    let tmp_path = paths::with_suffix(&output_path, ".tmp");
+    fs::write(&tmp_path, output_json)?;
+    fs::rename(&tmp_path, &output_path)?;
+    Ok(())
+}
+
+fn analyze_in_memory(args: &Args, pipeline: &Pipeline) -> anyhow::Result<AnalysisResult> {
+    let mut state = match &args.resume {
+        Some(path) => RunState::load(path, args)?,
+        None => RunState::new(args),
+    };
+    if args.verbose && args.resume.is_some() {
+        println!(
+            "Resuming after {} already processed files",
+            state.processed_files.len()
+        );
+    }
+    let checkpoint_path = args.checkpoint.as_ref().or(args.resume.as_ref());
+
+    let mut loader = Loader::new(args.strict);
+    // Files are recorded by their display name, which the JSON state file can
+    // hold whatever the file name's encoding.
+    let pending: Vec<PathBuf> = input::expand_inputs(&args.input)?
+        .into_iter()
+        .filter(|path| !state.processed_files.contains(&path.display().to_string()))
         .collect();
+    let mut prefetch = prefetch::files(pending);
+    while let Some((path, chunks)) = prefetch.next_file() {
+        let name = path.display().to_string();
+        let raw_msgs = loader.read_prefetched(&path, chunks)?;
+        pipeline.ingest(&mut state, &name, raw_msgs);
+        state.processed_files.insert(name);
+
+        // A strict run that found unknown fields must not checkpoint, or a
+        // resume would silently skip the offending files.
+        if let Some(checkpoint_path) = checkpoint_path.filter(|_| loader.unknown_fields.is_empty())
+        {
+            state.save(checkpoint_path)?;
+        }
+    }
+    if !loader.unknown_fields.is_empty() {
+        anyhow::bail!("{}", loader.unknown_fields);
+    }
+
+    Ok(summarize_state(args, pipeline, state, &loader))
+}
+
+/// Turns the ingested messages into the final per-author analysis.
+fn summarize_state(
+    args: &Args,
+    pipeline: &Pipeline,
+    state: RunState,
+    loader: &Loader,
+) -> AnalysisResult {
+    let RunState {
+        loaded,
+        mut sampled,
+        sample_keys,
+        fallbacks,
+        conflicts,
+        opted_out,
+        mut warnings,
+        gaps,
+        nicknames,
+        activity,
+        mut filter_stats,
+        matched: mut deleted_msgs,
+        ..
+    } = state;
+    if let (Some(sampler), Some(keys)) = (&pipeline.sampler, &sample_keys) {
+        if let Some(threshold) = keys.threshold() {
+            deleted_msgs.retain(|msg| sampler.key(msg) <= threshold);
+        }
+        sampled = keys.len();
+    }
+    let mut inactive_users = Vec::new();
+    if let Some(days) = pipeline.target.inactive_days() {
+        let before = deleted_msgs.len();
+        (deleted_msgs, inactive_users) = activity.unwrap_or_default().select(deleted_msgs, days);
+        filter_stats.drop_late("target", before - deleted_msgs.len());
+    }
+    if let Some(tracker) = &nicknames {
+        let before = deleted_msgs.len();
+        deleted_msgs.retain(|msg| pipeline.nicknames.keeps(tracker, msg));
+        filter_stats.drop_late("nickname", before - deleted_msgs.len());
+    }
+    let author_conflicts = conflicts.conflicts();
+    warnings.record_load(loader, &fallbacks);
+    if args.verbose {
+        println!("Loaded {} messages", loaded);
+        report::print_missing_fields(&fallbacks);
+    }
 
     if args.verbose {
         println!(
-            "Found {} messages mentioning deleted users",
-            deleted_msgs.len()
+            "Found {} messages mentioning {}",
+            deleted_msgs.len(),
+            pipeline.target.label()
         );
     }
 
-    let mut author_msg_map: HashMap<String, Vec<Message>> = HashMap::new();
+    let mut blank_messages = BlankCounts::default();
+    let mut channels = ChannelTally::default();
+    let mut reply_only_matches = 0;
+    for msg in &deleted_msgs {
+        if let Some(kind) = content::classify_blank(&msg.content) {
+            blank_messages.add(kind);
+        }
+        channels.record(msg);
+        if pipeline.target.matched_by_reply_only(msg) {
+            reply_only_matches += 1;
+        }
+    }
 
-    for msg in deleted_msgs {
-        author_msg_map
-            .entry(msg.author_id.clone())
-            .or_insert_with(Vec::new)
-            .push(msg);
+    let timeline = match args.timeline {
+        Some(_) => timeline::build(&deleted_msgs),
+        None => Vec::new(),
+    };
+    let events = pipeline.events.tally().map(|mut tally| {
+        for msg in &deleted_msgs {
+            tally.record(msg);
+        }
+        tally.summary()
+    });
+    let deletion_gaps = gaps.map(|gaps| gaps.summary(&deleted_msgs, args.gap_window));
+    let pivot = match args.pivot {
+        Some(_) => Pivot::build(&deleted_msgs),
+        None => Pivot::default(),
+    };
+
+    let explanations = match args.explain_matches {
+        Some(_) => explain::build(&pipeline.target, &deleted_msgs),
+        None => Vec::new(),
+    };
+    let batch = match args.output_dir {
+        Some(_) => batch::build(args, pipeline, &deleted_msgs),
+        None => Vec::new(),
+    };
+
+    let mut target_sections: Vec<TargetSection> = pipeline
+        .target
+        .labeled()
+        .map(|group| {
+            let msgs: Vec<Message> = deleted_msgs
+                .iter()
+                .filter(|msg| group.matches(msg))
+                .cloned()
+                .collect();
+            TargetSection::new(group, analyze_authors(args, pipeline, msgs))
+        })
+        .collect();
+
+    let mut analysis_data = analyze_authors(args, pipeline, deleted_msgs);
+    if args.verbose {
+        println!("Found {} unique authors", analysis_data.len());
+    }
+    if let Some(tracker) = &nicknames {
+        let sections = target_sections
+            .iter_mut()
+            .flat_map(|s| &mut s.authors_analysis);
+        for author in analysis_data.iter_mut().chain(sections) {
+            author.nicknames = tracker.nicknames(&author.author_id);
+        }
     }
 
-    for (_, msgs) in author_msg_map.iter_mut() {
-        msgs.sort_by(|a, b| a.content.cmp(&b.content));
-        msgs.dedup_by(|a, b| a.content == b.content);
+    let mut result = build_result(
+        analysis_data,
+        blank_messages,
+        author_conflicts,
+        fallbacks.records,
+        warnings,
+    );
+    result.channels = channels.into_vec();
+    references::resolve_names(&mut result);
+    result.reply_only_matches = reply_only_matches;
+    result.timeline = timeline;
+    result.pivot = pivot;
+    result.explanations = explanations;
+    result.batch = batch;
+    result.events = events;
+    result.deletion_gaps = deletion_gaps;
+    result.inactive_users = inactive_users;
+    result.filter_stats = filter_stats.summary(&pipeline.active_filters());
+    result.target_sections = target_sections;
+    annotate_words(args, &mut result);
+    pipeline.annotate(&mut result);
+    if let Some(sampler) = &pipeline.sampler {
+        attach_sampling(&mut result, SamplingInfo::new(sampler, loaded, sampled));
     }
+    result.opted_out = opted_out.summary();
+    result
+}
 
-    if args.verbose {
-        println!("Found {} unique authors", author_msg_map.len());
+/// Fills in the statistics built from every author's words together, for
+/// the overall result and each `--output-dir` one alike.
+fn annotate_words(args: &Args, result: &mut AnalysisResult) {
+    let mut word_variants = Variants::default();
+    let mut pair_counts = PairCounts::default();
+    let mut cooccurrence = Cooccurrence::default();
+    for author in &mut result.authors_analysis {
+        word_variants.merge(std::mem::take(&mut author.word_variants));
+        pair_counts.merge(&std::mem::take(&mut author.pair_counts));
+        cooccurrence.merge(&std::mem::take(&mut author.cooccurrence));
+    }
+    result.word_variants = word_variants.into_map();
+    result.cooccurrence = cooccurrence;
+    if let Some(measure) = args.collocations {
+        result.collocations = pair_counts.rank(measure, args.collocation_min_count);
+    }
+    if args.hapax {
+        hapax::annotate(result);
+    }
+    if let Some(k) = args.topics {
+        topics::annotate(result, k, args.topic_iterations, args.seed);
     }
+    if let Some(words) = &args.word_trends {
+        let mut buckets = BucketCounts::default();
+        for author in &result.authors_analysis {
+            buckets.merge(&author.bucket_counts);
+        }
+        result.word_trends = Some(buckets.trends(words));
+    }
+    if args.rank_frequency {
+        result.rank_frequency = Some(zipf::rank_frequency(&result.global_word_frequency));
+    }
+}
+
+/// `--similarity` and `--cluster`, returning the similarity matrix. Run
+/// before masking, which can merge words.
+fn compare_authors(args: &Args, result: &mut AnalysisResult) -> Option<similarity::Matrix> {
+    let matrix = args.similarity.as_ref().map(|_| {
+        let matrix = similarity::Matrix::build(&result.authors_analysis);
+        result.similar_authors = matrix.most_similar();
+        matrix
+    });
+    if let Some(k) = args.cluster {
+        cluster::annotate(result, k);
+    }
+    matrix
+}
 
-    let analysis_data: Vec<AuthorAnalysis> = author_msg_map
+/// Groups messages by author and builds each author's analysis, including
+/// radar profiles normalized across the returned authors.
+fn analyze_authors(args: &Args, pipeline: &Pipeline, msgs: Vec<Message>) -> Vec<AuthorAnalysis> {
+    let mut author_msg_map: HashMap<String, Vec<Message>> = HashMap::new();
+    let mut target_ids = TargetIds::default();
+
+    for msg in msgs {
+        target_ids.learn(&msg, &pipeline.target);
+        author_msg_map
+            .entry(msg.author_id.clone())
+            .or_default()
+            .push(msg);
+    }
+
+    let (mut analysis_data, radar_inputs): (Vec<AuthorAnalysis>, Vec<RadarInputs>) = author_msg_map
         .into_iter()
         .collect::<Vec<_>>()
         .into_par_iter()
-        .map(|(author_id, msgs)| {
+        .map(|(author_id, mut msgs)| {
             let total_msgs = msgs.len();
+            let mut counts = MessageCounts {
+                total: total_msgs,
+                ..MessageCounts::default()
+            };
+            let mut links = AuthorLinks::default();
+            let mut references = ReferenceTally::default();
+            for msg in &msgs {
+                counts.add_mentions(msg, &target_ids);
+                links.record(&msg.content);
+                references.record(&msg.content);
+            }
             let author_name = msgs[0].author_name.clone();
             let author_nickname = msgs[0].author_nickname.clone();
+            let radar_inputs = RadarInputs::from_messages(&msgs);
+            let lifecycle = lifecycle::classify(&msgs, pipeline.timezone);
+            let mut times = msgs.iter().filter_map(Message::parsed_timestamp);
+            let first_time = times.next();
+            let (first_message_at, last_message_at) = times
+                .fold((first_time, first_time), |(first, last), ts| {
+                    (first.min(Some(ts)), last.max(Some(ts)))
+                });
+
+            // Blank messages are counted but never reach dedup or word stats,
+            // otherwise every empty message collapses into one "unique" entry.
+            msgs.retain(|msg| content::classify_blank(&msg.content).is_none());
+            counts.blank = total_msgs - msgs.len();
+            msgs.sort_by(|a, b| a.content.cmp(&b.content));
+            msgs.dedup_by(|a, b| a.content == b.content);
 
             let mut word_freq_map: HashMap<String, usize> = HashMap::with_capacity(64);
+            let mut bucket_counts = BucketCounts::default();
+            let mut evicted_words = 0;
+            let mut word_variants = Variants::default();
+            let mut ngram_freq_map: HashMap<String, usize> = HashMap::new();
+            let mut pair_counts = PairCounts::default();
+            let mut cooccurrence = Cooccurrence::default();
+            let mut documents = Vec::new();
+            let mut emoji_frequency: BTreeMap<String, usize> = BTreeMap::new();
+            let mut custom_emoji = CustomEmojiTally::default();
+            let mut language_frequency: BTreeMap<String, usize> = BTreeMap::new();
+            let mut concordance = Vec::new();
+            let mut sentiment = SentimentTally::default();
+            let mut flags = FlagTally::default();
+            let mut readability = ReadabilityTally::default();
 
             for msg in &msgs {
-                let words = tokenize_content(&msg.content, args.min_word_length);
+                if let Some(kwic) = &pipeline.concordance {
+                    concordance.extend(kwic.lines(msg));
+                }
+                readability.record(&msg.content);
+                if args.sentiment {
+                    sentiment.record(msg);
+                }
+                if let Some(flagger) = &pipeline.flagger {
+                    flags.record(msg, flagger);
+                }
+                for emoji in content::emoji(&msg.content) {
+                    *emoji_frequency.entry(emoji).or_insert(0) += 1;
+                }
+                custom_emoji.record(&msg.content);
+                if args.detect_languages {
+                    *language_frequency
+                        .entry(language_code(msg).to_string())
+                        .or_insert(0) += 1;
+                }
+                let mut words = pipeline.tokenizer.tokenize_message_with_stopwords(msg);
+                if args.collocations.is_some() {
+                    pair_counts.record(&words, &pipeline.tokenizer.stopwords);
+                }
+                if !args.ngrams.is_empty() {
+                    let stopwords = &pipeline.tokenizer.stopwords;
+                    for gram in ngram::ngrams(&words, &args.ngrams, stopwords) {
+                        *ngram_freq_map.entry(gram).or_insert(0) += 1;
+                    }
+                }
+                pipeline.tokenizer.drop_stopwords(&mut words);
+                if let Some(stem) = pipeline.stem {
+                    stem.apply(&mut words, &mut word_variants);
+                }
+                if let Some(lemmatizer) = &pipeline.lemmatizer {
+                    lemmatizer.apply(&mut words, &mut word_variants);
+                }
+                if args.cooccurrence.is_some() {
+                    cooccurrence.record(&words, args.cooccurrence_window);
+                }
+                if args.topics.is_some() && !words.is_empty() {
+                    documents.push(words.clone());
+                }
+                if let (Some(period), Some(ts)) = (args.word_buckets, msg.parsed_timestamp()) {
+                    bucket_counts
+                        .record(period.label(ts.with_timezone(&pipeline.timezone)), &words);
+                }
                 for word in words {
                     *word_freq_map.entry(word).or_insert(0) += 1;
                 }
+                if let Some(cap) = pipeline.vocab_cap {
+                    evicted_words += cap.trim(&mut word_freq_map);
+                }
+            }
+            if let Some(cap) = pipeline.vocab_cap {
+                evicted_words += cap.evict(&mut word_freq_map);
             }
 
-            let word_frequency: BTreeMap<String, usize> = word_freq_map.into_iter().collect();
-            let mut common_words: Vec<(String, usize)> = word_frequency
-                .iter()
-                .map(|(k, v)| (k.clone(), *v))
-                .collect();
-
-            common_words.sort_by(|a, b| b.1.cmp(&a.1));
-            common_words.truncate(10);
-
-            AuthorAnalysis {
+            counts.unique = msgs.len();
+            let mut analysis = build_author_analysis(
                 author_id,
                 author_name,
                 author_nickname,
-                total_messages_to_deleted_user: total_msgs,
-                unique_message_count: msgs.len(),
-                word_frequency,
-                most_common_words: common_words,
+                counts,
+                word_freq_map,
+            );
+            analysis.evicted_words = evicted_words;
+            analysis.word_variants = word_variants;
+            analysis.emoji_frequency = emoji_frequency;
+            analysis.custom_emoji = custom_emoji.into_vec();
+            analysis.language_frequency = language_frequency;
+            concordance::sort(&mut concordance);
+            analysis.concordance = concordance;
+            readability.apply(&mut analysis);
+            analysis.sentiment = sentiment.into_sentiment();
+            if let Some(flagger) = &pipeline.flagger {
+                flags.apply(&mut analysis, flagger);
             }
+            analysis.link_domains = links.domains;
+            analysis.messages_with_links = links.messages;
+            analysis.references = references.into_references();
+            analysis.most_common_ngrams = most_common(&ngram_freq_map);
+            analysis.ngram_frequency = ngram_freq_map.into_iter().collect();
+            analysis.pair_counts = pair_counts;
+            analysis.cooccurrence = cooccurrence;
+            analysis.documents = documents;
+            analysis.first_message_at = first_message_at;
+            analysis.last_message_at = last_message_at;
+            analysis.lifecycle = lifecycle;
+            analysis.word_buckets = bucket_counts.top_words();
+            analysis.bucket_counts = bucket_counts;
+            (analysis, radar_inputs)
         })
-        .collect();
+        .unzip();
+
+    for (analysis, profile) in analysis_data
+        .iter_mut()
+        .zip(radar::normalize(&radar_inputs))
+    {
+        analysis.radar = Some(profile);
+    }
+    if args.fuzzy_words {
+        fuzzy::merge(&mut analysis_data);
+    }
+    // Ranked after `--fuzzy-words` has merged the pairs' spellings.
+    if let Some(measure) = args.collocations {
+        for analysis in &mut analysis_data {
+            analysis.collocations = analysis
+                .pair_counts
+                .rank(measure, args.collocation_min_count);
+        }
+    }
+    tfidf::score(&mut analysis_data);
+    if let Some(measure) = args.keyness {
+        keyness::score(&mut analysis_data, measure);
+    }
+    analysis_data
+}
+
+/// Records how the result was sampled, with the message total scaled up to
+/// the full input.
+fn attach_sampling(result: &mut AnalysisResult, mut info: SamplingInfo) {
+    info.estimated_total_messages = info.scale(result.total_messages);
+    (
+        info.estimated_total_messages_low,
+        info.estimated_total_messages_high,
+    ) = info.interval(result.total_messages);
+    result.sampling = Some(info);
+}
+
+/// Per-author message tallies gathered before word statistics are built.
+#[derive(Debug, Clone, Copy, Default)]
+struct MessageCounts {
+    total: usize,
+    unique: usize,
+    blank: usize,
+    mentions: usize,
+    mention_only: usize,
+    direct_pings: usize,
+}
+
+impl MessageCounts {
+    fn add_mentions(&mut self, msg: &Message, target_ids: &TargetIds) {
+        let stats = content::message_mentions(msg);
+        self.mentions += stats.mentions;
+        if stats.mention_only {
+            self.mention_only += 1;
+        }
+        if target_ids.pings(msg) {
+            self.direct_pings += 1;
+        }
+    }
+}
+
+fn build_author_analysis(
+    author_id: String,
+    author_name: String,
+    author_nickname: String,
+    counts: MessageCounts,
+    word_freq_map: HashMap<String, usize>,
+) -> AuthorAnalysis {
+    let common_words = most_common(&word_freq_map);
+    let richness = richness::measure(&word_freq_map);
+    let word_frequency: BTreeMap<String, usize> = word_freq_map.into_iter().collect();
+
+    AuthorAnalysis {
+        author_id,
+        author_name,
+        author_nickname,
+        nicknames: Vec::new(),
+        total_messages_to_deleted_user: counts.total,
+        unique_message_count: counts.unique,
+        blank_message_count: counts.blank,
+        blank_message_share: counts.blank as f64 / counts.total as f64,
+        mentions_per_message: counts.mentions as f64 / counts.total as f64,
+        mention_only_count: counts.mention_only,
+        mention_only_share: counts.mention_only as f64 / counts.total as f64,
+        direct_ping_count: counts.direct_pings,
+        talked_about_count: counts.total - counts.direct_pings,
+        vocabulary_size: richness.vocabulary_size,
+        type_token_ratio: richness.type_token_ratio,
+        average_word_length: richness.average_word_length,
+        average_sentence_length: 0.0,
+        flesch_reading_ease: 0.0,
+        caps_ratio: 0.0,
+        shouted_message_count: 0,
+        word_frequency,
+        most_common_words: common_words,
+        ngram_frequency: BTreeMap::new(),
+        most_common_ngrams: Vec::new(),
+        top_tfidf_words: Vec::new(),
+        keywords: Vec::new(),
+        hapax_legomena: Vec::new(),
+        concordance: Vec::new(),
+        sentiment: None,
+        flagged: false,
+        flagged_message_count: 0,
+        flagged_excerpts: Vec::new(),
+        emoji_frequency: BTreeMap::new(),
+        custom_emoji: Vec::new(),
+        language_frequency: BTreeMap::new(),
+        link_domains: BTreeMap::new(),
+        messages_with_links: 0,
+        references: References::default(),
+        collocations: Vec::new(),
+        pair_counts: PairCounts::default(),
+        cooccurrence: Cooccurrence::default(),
+        documents: Vec::new(),
+        topic_proportions: Vec::new(),
+        cluster: None,
+        evicted_words: 0,
+        word_variants: Variants::default(),
+        word_buckets: Vec::new(),
+        bucket_counts: BucketCounts::default(),
+        first_message_at: None,
+        last_message_at: None,
+        lifecycle: None,
+        radar: None,
+    }
+}
+
+/// The ten most frequent entries, ties in word order.
+fn most_common(counts: &HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut common: Vec<(String, usize)> = counts.iter().map(|(k, v)| (k.clone(), *v)).collect();
+    common.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    common.truncate(10);
+    common
+}
+
+fn build_result(
+    analysis_data: Vec<AuthorAnalysis>,
+    blank_messages: BlankCounts,
+    author_conflicts: Vec<AuthorConflict>,
+    fallback_records: usize,
+    mut warnings: Warnings,
+) -> AnalysisResult {
+    warnings.record_conflicts(&author_conflicts);
+    for analysis in analysis_data.iter().filter(|a| a.evicted_words > 0) {
+        warnings.add(WarningKind::VocabularyCapped, analysis.author_id.clone());
+    }
 
     let mut global_freq: HashMap<String, usize> = HashMap::with_capacity(256);
+    let mut global_ngrams: HashMap<String, usize> = HashMap::new();
+    let mut emoji_frequency: BTreeMap<String, usize> = BTreeMap::new();
+    let mut custom_emoji = CustomEmojiTally::default();
+    let mut language_frequency: BTreeMap<String, usize> = BTreeMap::new();
+    let mut global_buckets = BucketCounts::default();
     for analysis in &analysis_data {
         for (word, count) in &analysis.word_frequency {
             *global_freq.entry(word.clone()).or_insert(0) += count;
         }
+        for (gram, count) in &analysis.ngram_frequency {
+            *global_ngrams.entry(gram.clone()).or_insert(0) += count;
+        }
+        for (emoji, count) in &analysis.emoji_frequency {
+            *emoji_frequency.entry(emoji.clone()).or_insert(0) += count;
+        }
+        custom_emoji.merge(&analysis.custom_emoji);
+        for (lang, count) in &analysis.language_frequency {
+            *language_frequency.entry(lang.clone()).or_insert(0) += count;
+        }
+        global_buckets.merge(&analysis.bucket_counts);
     }
     let global_word_frequency: BTreeMap<String, usize> = global_freq.into_iter().collect();
+    let link_stats = links::summarize(
+        analysis_data
+            .iter()
+            .map(|a| (&a.link_domains, a.messages_with_links)),
+    );
 
-    let result = AnalysisResult {
+    AnalysisResult {
+        summary: String::new(),
         total_messages: analysis_data
             .iter()
             .map(|a| a.total_messages_to_deleted_user)
             .sum(),
         messages_to_deleted_users: analysis_data.iter().map(|a| a.unique_message_count).sum(),
         unique_authors: analysis_data.len(),
+        targets: Vec::new(),
+        target_sections: Vec::new(),
+        blank_messages,
         authors_analysis: analysis_data,
         global_word_frequency,
-    };
-
-    display_results(&result, args.verbose);
-
-    if let Some(output_path) = args.output {
-        let output_json = serde_json::to_string_pretty(&result)?;
-        fs::write(&output_path, output_json)?;
-        println!("Results saved to: {}", output_path);
+        most_common_ngrams: most_common(&global_ngrams),
+        global_ngram_frequency: global_ngrams.into_iter().collect(),
+        emoji_frequency,
+        custom_emoji: custom_emoji.into_vec(),
+        language_frequency,
+        link_stats,
+        collocations: Vec::new(),
+        word_buckets: global_buckets.top_words(),
+        word_trends: None,
+        topics: Vec::new(),
+        similar_authors: Vec::new(),
+        clusters: Vec::new(),
+        channels: Vec::new(),
+        author_conflicts,
+        omitted_authors: 0,
+        fallback_records,
+        reply_only_matches: 0,
+        warnings: warnings.into_vec(),
+        sampling: None,
+        opted_out: None,
+        timeline: Vec::new(),
+        pivot: Pivot::default(),
+        explanations: Vec::new(),
+        cooccurrence: Cooccurrence::default(),
+        batch: Vec::new(),
+        events: None,
+        filter_stats: None,
+        deletion_gaps: None,
+        inactive_users: Vec::new(),
+        word_variants: BTreeMap::new(),
+        hapax_legomena: Vec::new(),
+        rank_frequency: None,
+        timezone: None,
     }
-
-    Ok(())
 }
 
-fn tokenize_content(content: &str, min_len: usize) -> Vec<String> {
-    content
-        .to_lowercase()
-        .split_whitespace()
-        .filter_map(|w| {
-            let clean_w: String = w.chars().filter(|c| c.is_alphanumeric()).collect();
-
-            if clean_w.len() >= min_len {
-                Some(clean_w)
-            } else {
-                None
+/// Rewrites unparseable timestamps using the snowflake creation time of the
+/// message ID, and flags parseable ones that look wrong: more than a day away
+/// from the ID's own creation time, or in the future.
+fn repair_timestamps(msgs: &mut [Message], warnings: &mut Warnings) {
+    let now = Utc::now();
+    for msg in msgs.iter_mut() {
+        let from_id = snowflake::snowflake_to_datetime(&msg.message_id);
+        match DateTime::parse_from_rfc3339(msg.timestamp.trim()) {
+            Ok(ts) => {
+                let ts = ts.with_timezone(&Utc);
+                let disagrees = from_id.is_some_and(|id_ts| (ts - id_ts).num_hours().abs() > 24);
+                if disagrees || ts > now + chrono::Duration::days(1) {
+                    warnings.add(WarningKind::SuspiciousTimestamp, msg.message_id.clone());
+                }
             }
-        })
-        .collect()
-}
-
-fn display_results(result: &AnalysisResult, verbose: bool) {
-    println!("\nANALYSIS RESULTS");
-    println!("==================");
-    println!("Total messages to deleted users: {}", result.total_messages);
-    println!(
-        "Unique messages (after deduplication): {}",
-        result.messages_to_deleted_users
-    );
-    println!("Unique authors: {}", result.unique_authors);
-
-    if verbose {
-        println!("\nAUTHORS ANALYSIS");
-        println!("===================");
-
-        let mut sorted_auth = result.authors_analysis.clone();
-        sorted_auth.sort_by(|a, b| {
-            b.total_messages_to_deleted_user
-                .cmp(&a.total_messages_to_deleted_user)
-        });
-
-        for (i, auth) in sorted_auth.iter().enumerate().take(10) {
-            println!(
-                "\n{}. {} ({})",
-                i + 1,
-                auth.author_name,
-                auth.author_nickname
-            );
-            println!("   Author ID: {}", auth.author_id);
-            println!(
-                "   Messages to deleted user: {}",
-                auth.total_messages_to_deleted_user
-            );
-            println!("   Unique messages: {}", auth.unique_message_count);
-
-            if !auth.most_common_words.is_empty() {
-                println!("   Most common words:");
-                for (word, count) in auth.most_common_words.iter().take(5) {
-                    println!("     - {}: {}", word, count);
+            Err(_) => {
+                if let Some(ts) = from_id {
+                    msg.timestamp = ts.to_rfc3339_opts(SecondsFormat::Millis, false);
+                    warnings.add(WarningKind::ReconstructedTimestamp, msg.message_id.clone());
                 }
             }
         }
-
-        println!("\nGLOBAL WORD FREQUENCY (TOP 20)");
-        println!("==================================");
-        let mut global_w: Vec<(String, usize)> = result
-            .global_word_frequency
-            .iter()
-            .map(|(k, v)| (k.clone(), *v))
-            .collect();
-
-        global_w.sort_by(|a, b| b.1.cmp(&a.1));
-
-        for (i, (word, count)) in global_w.iter().enumerate().take(20) {
-            println!("{}. {}: {}", i + 1, word, count);
-        }
     }
-
-    println!("\nAnalysis complete!");
-    println!("Use --output <filename.json> to save detailed results");
-    println!("Use --verbose for detailed output");
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_tokenize_content() {
-        let content = "Hello, World! This is a test.";
-        let tokens = tokenize_content(content, 3);
-        assert_eq!(tokens, vec!["hello", "world", "this", "test"]);
-    }
-
-    #[test]
-    fn test_tokenize_content_with_numbers() {
-        let content = "Test123 with numbers 456!";
-        let tokens = tokenize_content(content, 3);
-        assert_eq!(tokens, vec!["test123", "with", "numbers", "456"]);
-    }
-
-    #[test]
-    fn test_tokenize_content_filters_short_words() {
-        let content = "a an the is to of";
-        let tokens = tokenize_content(content, 3);
-        assert_eq!(tokens, vec!["the"]);
-    }
+/// `--detect-languages` key for `msg`: its ISO 639-3 code, or `und`.
+fn language_code(msg: &Message) -> &'static str {
+    content::language(msg).map_or("und", |lang| lang.code())
+}
 
-    #[test]
-    fn test_tokenize_content_min_length() {
-        let content = "a bb ccc dddd";
-        let tokens = tokenize_content(content, 2);
-        assert_eq!(tokens, vec!["bb", "ccc", "dddd"]);
-    }
+fn is_zero(n: &usize) -> bool {
+    *n == 0
 }
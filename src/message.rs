@@ -0,0 +1,339 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Message {
+    pub message_id: String,
+    pub content: String,
+    pub timestamp: String,
+    pub author_name: String,
+    pub author_nickname: String,
+    pub author_id: String,
+    pub mentioned_user_name: Option<String>,
+    pub mentioned_user_nickname: Option<String>,
+    pub channel_id: Option<String>,
+    pub channel_name: Option<String>,
+    #[serde(default)]
+    pub referenced_message: Option<ReferencedMessage>,
+    #[serde(default)]
+    pub is_bot: bool,
+    #[serde(default)]
+    pub is_webhook: bool,
+    /// Discord message type as exported: a numeric code or a name such as
+    /// `Default`, `Reply` or `GuildMemberJoin`.
+    #[serde(default)]
+    pub message_type: Option<String>,
+    /// Role names of the author, when the export includes them.
+    #[serde(default)]
+    pub author_roles: Vec<String>,
+    /// Number of entries in the export's `attachments` list.
+    #[serde(default)]
+    pub attachment_count: usize,
+}
+
+/// Message types written by users; every other type is a system message
+/// (pins, joins, boosts, ...).
+const USER_MESSAGE_TYPES: &[&str] = &["0", "19", "default", "reply"];
+
+/// The message a reply points at, as far as the export includes it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ReferencedMessage {
+    #[serde(default)]
+    pub message_id: Option<String>,
+    #[serde(default)]
+    pub author_id: Option<String>,
+    #[serde(default)]
+    pub author_name: Option<String>,
+    #[serde(default)]
+    pub author_nickname: Option<String>,
+}
+
+impl ReferencedMessage {
+    /// Every key accepted inside a `referenced_message`.
+    pub const FIELDS: &'static [&'static str] =
+        &["message_id", "author_id", "author_name", "author_nickname"];
+}
+
+impl Message {
+    /// Parses `timestamp`, falling back to the time encoded in `message_id`.
+    pub fn parsed_timestamp(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(self.timestamp.trim())
+            .map(|ts| ts.with_timezone(&Utc))
+            .ok()
+            .or_else(|| crate::snowflake::snowflake_to_datetime(&self.message_id))
+    }
+
+    /// The stable fingerprint of the content; see [`crate::fingerprint`].
+    pub fn fingerprint(&self) -> String {
+        crate::fingerprint::fingerprint(&self.content)
+    }
+
+    /// Has attachments, either listed in `attachments` or linked in the
+    /// content as Discord CDN URLs.
+    pub fn has_attachments(&self) -> bool {
+        self.attachment_count > 0 || crate::content::has_attachment_link(&self.content)
+    }
+
+    /// Sent by a bot account or through a webhook.
+    pub fn is_automated(&self) -> bool {
+        self.is_bot || self.is_webhook
+    }
+
+    /// A pin, join, boost or other system message. Messages without a type
+    /// are treated as ordinary user messages.
+    pub fn is_system(&self) -> bool {
+        self.message_type.as_ref().is_some_and(|kind| {
+            !USER_MESSAGE_TYPES.contains(&kind.trim().to_ascii_lowercase().as_str())
+        })
+    }
+}
+
+/// Message as it appears in an export, before fallbacks are applied.
+///
+/// Only `message_id` and `author_id` are required; everything else may be
+/// missing or `null`.
+#[derive(Deserialize, Debug)]
+pub struct RawMessage {
+    message_id: String,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    timestamp: Option<String>,
+    #[serde(default)]
+    author_name: Option<String>,
+    #[serde(default)]
+    author_nickname: Option<String>,
+    author_id: String,
+    #[serde(default)]
+    mentioned_user_name: Option<String>,
+    #[serde(default)]
+    mentioned_user_nickname: Option<String>,
+    #[serde(default)]
+    channel_id: Option<String>,
+    #[serde(default)]
+    channel_name: Option<String>,
+    #[serde(default)]
+    referenced_message: Option<ReferencedMessage>,
+    #[serde(default)]
+    is_bot: Option<bool>,
+    #[serde(default)]
+    is_webhook: Option<bool>,
+    #[serde(default, rename = "type")]
+    message_type: Option<MessageType>,
+    #[serde(default)]
+    author_roles: Option<Vec<String>>,
+    /// Only counted, so any entry shape (URL string or exporter object) is accepted.
+    #[serde(default)]
+    attachments: Option<Vec<serde::de::IgnoredAny>>,
+}
+
+/// The API exports numeric types, chat exporters their names.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum MessageType {
+    Code(u64),
+    Name(String),
+}
+
+/// Counts of records that needed a fallback value while loading.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy)]
+pub struct FallbackStats {
+    pub records: usize,
+    pub author_name: usize,
+    pub author_nickname: usize,
+    pub timestamp: usize,
+    pub content: usize,
+}
+
+impl FallbackStats {
+    /// Per-field counts of records that lacked the field, skipping zeros.
+    pub fn missing_fields(&self) -> Vec<(&'static str, usize)> {
+        [
+            ("author_name", self.author_name),
+            ("author_nickname", self.author_nickname),
+            ("timestamp", self.timestamp),
+            ("content", self.content),
+        ]
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .collect()
+    }
+}
+
+impl RawMessage {
+    /// Every key accepted in an input record; anything else is rejected by `--strict`.
+    pub const FIELDS: &'static [&'static str] = &[
+        "message_id",
+        "content",
+        "timestamp",
+        "author_name",
+        "author_nickname",
+        "author_id",
+        "mentioned_user_name",
+        "mentioned_user_nickname",
+        "channel_id",
+        "channel_name",
+        "referenced_message",
+        "is_bot",
+        "is_webhook",
+        "type",
+        "author_roles",
+        "attachments",
+    ];
+
+    pub fn into_message(self, stats: &mut FallbackStats) -> Message {
+        let mut used_fallback = false;
+
+        let author_name = match non_empty(self.author_name) {
+            Some(name) => name,
+            None => {
+                stats.author_name += 1;
+                used_fallback = true;
+                self.author_nickname
+                    .clone()
+                    .filter(|n| !n.trim().is_empty())
+                    .unwrap_or_else(|| self.author_id.clone())
+            }
+        };
+
+        let author_nickname = match non_empty(self.author_nickname) {
+            Some(nickname) => nickname,
+            None => {
+                stats.author_nickname += 1;
+                used_fallback = true;
+                author_name.clone()
+            }
+        };
+
+        // An empty timestamp is repaired from the snowflake ID later on.
+        let timestamp = match self.timestamp {
+            Some(ts) => ts,
+            None => {
+                stats.timestamp += 1;
+                used_fallback = true;
+                String::new()
+            }
+        };
+
+        // Missing content is analyzed as an empty (blank) message.
+        let content = match self.content {
+            Some(content) => content,
+            None => {
+                stats.content += 1;
+                used_fallback = true;
+                String::new()
+            }
+        };
+
+        if used_fallback {
+            stats.records += 1;
+        }
+
+        Message {
+            message_id: self.message_id,
+            content,
+            timestamp,
+            author_name,
+            author_nickname,
+            author_id: self.author_id,
+            mentioned_user_name: self.mentioned_user_name,
+            mentioned_user_nickname: self.mentioned_user_nickname,
+            channel_id: non_empty(self.channel_id),
+            channel_name: non_empty(self.channel_name),
+            referenced_message: self.referenced_message,
+            is_bot: self.is_bot.unwrap_or(false),
+            is_webhook: self.is_webhook.unwrap_or(false),
+            message_type: self.message_type.map(|kind| match kind {
+                MessageType::Code(code) => code.to_string(),
+                MessageType::Name(name) => name,
+            }),
+            author_roles: self.author_roles.unwrap_or_default(),
+            attachment_count: self.attachments.map_or(0, |list| list.len()),
+        }
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_author_fields_fall_back() {
+        let raw: RawMessage = serde_json::from_str(
+            r#"{"message_id": "1", "content": "hi", "author_id": "42", "author_nickname": null}"#,
+        )
+        .unwrap();
+        let mut stats = FallbackStats::default();
+        let msg = raw.into_message(&mut stats);
+
+        assert_eq!(msg.author_name, "42");
+        assert_eq!(msg.author_nickname, "42");
+        assert_eq!(msg.timestamp, "");
+        assert_eq!(stats.records, 1);
+        assert_eq!(stats.author_name, 1);
+        assert_eq!(stats.author_nickname, 1);
+        assert_eq!(stats.timestamp, 1);
+        assert_eq!(stats.content, 0);
+    }
+
+    #[test]
+    fn test_null_content_becomes_empty() {
+        let raw: RawMessage = serde_json::from_str(
+            r#"{"message_id": "1", "content": null, "timestamp": "t", "author_id": "42", "author_name": "alice", "author_nickname": "A"}"#,
+        )
+        .unwrap();
+        let mut stats = FallbackStats::default();
+        let msg = raw.into_message(&mut stats);
+
+        assert_eq!(msg.content, "");
+        assert_eq!(stats.records, 1);
+        assert_eq!(stats.missing_fields(), vec![("content", 1)]);
+    }
+
+    #[test]
+    fn test_bot_flags_default_to_false() {
+        let raw: RawMessage = serde_json::from_str(
+            r#"{"message_id": "1", "author_id": "42", "is_bot": true, "is_webhook": null}"#,
+        )
+        .unwrap();
+        let msg = raw.into_message(&mut FallbackStats::default());
+        assert!(msg.is_bot);
+        assert!(!msg.is_webhook);
+        assert!(msg.is_automated());
+    }
+
+    #[test]
+    fn test_system_message_types() {
+        let kind = |json: &str| {
+            let raw: RawMessage = serde_json::from_str(&format!(
+                r#"{{"message_id": "1", "author_id": "42"{}}}"#,
+                json
+            ))
+            .unwrap();
+            raw.into_message(&mut FallbackStats::default()).is_system()
+        };
+        assert!(!kind(""));
+        assert!(!kind(r#", "type": 0"#));
+        assert!(!kind(r#", "type": "Reply""#));
+        assert!(kind(r#", "type": 7"#));
+        assert!(kind(r#", "type": "ChannelPinnedMessage""#));
+    }
+
+    #[test]
+    fn test_nickname_falls_back_to_name() {
+        let raw: RawMessage = serde_json::from_str(
+            r#"{"message_id": "1", "content": "hi", "timestamp": "t", "author_id": "42", "author_name": "alice"}"#,
+        )
+        .unwrap();
+        let mut stats = FallbackStats::default();
+        let msg = raw.into_message(&mut stats);
+
+        assert_eq!(msg.author_nickname, "alice");
+        assert_eq!(stats.records, 1);
+        assert_eq!(stats.author_name, 0);
+    }
+}
@@ -0,0 +1,63 @@
+//! `--ngrams`: counts of consecutive word sequences ("shut up", "deleted
+//! user"), which single-word counts lose.
+//!
+//! N-grams are taken from the words of one message as written, before
+//! stopwords are removed and before `--stem` / `--lemmatize`, so phrases
+//! read naturally and keep their function words. N-grams made only of
+//! stopwords ("of the") are skipped.
+
+use crate::stopwords::Stopwords;
+
+/// Longest n-gram `--ngrams` accepts; longer ones are almost all unique.
+pub const MAX_N: usize = 5;
+
+/// Parses one `--ngrams` size.
+pub fn parse_size(value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(n) if (2..=MAX_N).contains(&n) => Ok(n),
+        _ => Err(format!(
+            "`{}` is not an n-gram size from 2 to {}",
+            value, MAX_N
+        )),
+    }
+}
+
+/// The space-joined n-grams of every size in `sizes`.
+pub fn ngrams(words: &[String], sizes: &[usize], stopwords: &Stopwords) -> Vec<String> {
+    let mut grams = Vec::new();
+    for &n in sizes {
+        for window in words.windows(n) {
+            if window.iter().all(|word| stopwords.contains(word)) {
+                continue;
+            }
+            grams.push(window.join(" "));
+        }
+    }
+    grams
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use whatlang::Lang;
+
+    #[test]
+    fn test_ngrams_skip_stopword_only_phrases() {
+        let words: Vec<String> = ["just", "shut", "up", "of", "the", "user"]
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let stopwords = Stopwords::load(&[Lang::Eng], None).unwrap();
+        assert_eq!(
+            ngrams(&words, &[2], &stopwords),
+            ["just shut", "shut up", "the user"]
+        );
+        assert_eq!(
+            ngrams(&words[..3], &[2, 3], &Stopwords::default()),
+            ["just shut", "shut up", "just shut up"]
+        );
+        assert!(ngrams(&words[..1], &[2], &stopwords).is_empty());
+        assert!(parse_size("1").is_err());
+        assert_eq!(parse_size("3"), Ok(3));
+    }
+}
@@ -0,0 +1,130 @@
+//! `--nickname-differs` / `--multiple-nicknames`: narrow the analysis to
+//! authors who post under a server nickname, or who changed it, which often
+//! marks accounts hiding who they are.
+//!
+//! Whether an author changed nicknames is only known once every input is
+//! loaded, so the filter runs on the matched messages in `summarize_state`
+//! rather than as a per-message stage.
+
+use crate::message::Message;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Which authors the nickname filter keeps. With both flags an author needs
+/// to meet either one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NicknameFilter {
+    /// Posted at least once under a nickname that is not their user name.
+    pub differs: bool,
+    /// Seen under more than one nickname across the inputs.
+    pub multiple: bool,
+}
+
+impl NicknameFilter {
+    pub fn is_active(&self) -> bool {
+        self.differs || self.multiple
+    }
+
+    pub fn keeps(&self, tracker: &NicknameTracker, msg: &Message) -> bool {
+        let Some(author) = tracker.0.get(&msg.author_id) else {
+            return false;
+        };
+        (self.differs && author.differs) || (self.multiple && author.nicknames.len() > 1)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+struct AuthorNicknames {
+    differs: bool,
+    /// Normalized nicknames.
+    nicknames: BTreeSet<String>,
+    /// Each nickname as first written.
+    spellings: Vec<String>,
+}
+
+/// Every nickname seen per author ID over all loaded messages, not only the
+/// matched ones.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct NicknameTracker(HashMap<String, AuthorNicknames>);
+
+impl NicknameTracker {
+    pub fn record(&mut self, msgs: &[Message]) {
+        for msg in msgs {
+            let author = self.0.entry(msg.author_id.clone()).or_default();
+            let nickname = normalize(&msg.author_nickname);
+            if nickname != normalize(&msg.author_name) {
+                author.differs = true;
+            }
+            if author.nicknames.insert(nickname) {
+                author
+                    .spellings
+                    .push(msg.author_nickname.trim().to_string());
+            }
+        }
+    }
+
+    /// The author's nicknames in the order they were first seen.
+    pub fn nicknames(&self, author_id: &str) -> Vec<String> {
+        self.0
+            .get(author_id)
+            .map(|author| author.spellings.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Case and surrounding whitespace are not a different nickname.
+fn normalize(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(author_id: &str, name: &str, nickname: &str) -> Message {
+        Message {
+            author_id: author_id.to_string(),
+            author_name: name.to_string(),
+            author_nickname: nickname.to_string(),
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_differing_and_multiple_nicknames() {
+        let mut tracker = NicknameTracker::default();
+        tracker.record(&[
+            msg("1", "alice", "Alice"),
+            msg("2", "bob", "bobby"),
+            msg("3", "carol", "carol"),
+            msg("3", "carol", "not carol"),
+            msg("1", "alice", " ALICE "),
+        ]);
+        assert_eq!(tracker.nicknames("3"), ["carol", "not carol"]);
+        assert_eq!(tracker.nicknames("1"), ["Alice"]);
+
+        let differs = NicknameFilter {
+            differs: true,
+            multiple: false,
+        };
+        let multiple = NicknameFilter {
+            differs: false,
+            multiple: true,
+        };
+        let kept = |filter: NicknameFilter| -> Vec<&str> {
+            ["1", "2", "3", "4"]
+                .into_iter()
+                .filter(|id| filter.keeps(&tracker, &msg(id, "", "")))
+                .collect()
+        };
+        assert_eq!(kept(differs), ["2", "3"]);
+        assert_eq!(kept(multiple), ["3"]);
+        assert_eq!(
+            kept(NicknameFilter {
+                differs: true,
+                multiple: true
+            }),
+            ["2", "3"]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Privacy opt-out list of author IDs.
+//!
+//! Messages from listed authors are dropped as soon as they are loaded, so
+//! their names, content and message IDs never reach the analysis, the
+//! warnings, a checkpoint or any output. Only anonymous totals are kept.
+
+use crate::filter;
+use crate::paths;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+
+/// Author IDs read from an opt-out file: one per line, with blank lines and
+/// `#` comments ignored.
+#[derive(Debug, Default)]
+pub struct OptOutList(HashSet<String>);
+
+impl OptOutList {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        Ok(OptOutList(filter::read_id_list(paths::extended(path))?))
+    }
+
+    pub fn contains(&self, author_id: &str) -> bool {
+        self.0.contains(author_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Opted-out messages seen so far. The author IDs are only kept to count
+/// distinct authors and are never written to the result.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct OptOutTally {
+    authors: BTreeSet<String>,
+    messages: usize,
+}
+
+impl OptOutTally {
+    pub fn record(&mut self, author_id: &str, mentions_target: bool) {
+        if !self.authors.contains(author_id) {
+            self.authors.insert(author_id.to_string());
+        }
+        if mentions_target {
+            self.messages += 1;
+        }
+    }
+
+    pub fn summary(&self) -> Option<OptOutSummary> {
+        if self.authors.is_empty() {
+            return None;
+        }
+        Some(OptOutSummary {
+            authors: self.authors.len(),
+            messages_to_deleted_users: self.messages,
+        })
+    }
+}
+
+/// Anonymous aggregate reported in place of opted-out authors.
+#[derive(Serialize, JsonSchema, Debug, Clone, Copy)]
+pub struct OptOutSummary {
+    pub authors: usize,
+    pub messages_to_deleted_users: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tally_counts_distinct_authors() {
+        let mut tally = OptOutTally::default();
+        assert!(tally.summary().is_none());
+        tally.record("1", true);
+        tally.record("1", false);
+        tally.record("2", true);
+        let summary = tally.summary().unwrap();
+        assert_eq!(summary.authors, 2);
+        assert_eq!(summary.messages_to_deleted_users, 2);
+    }
+}
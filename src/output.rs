@@ -0,0 +1,162 @@
+//! Guardrails for the `--output` JSON: per-word maps can make it far larger
+//! than the input, so its size is checked before anything is written.
+
+use crate::{AnalysisResult, Args, AuthorAnalysis};
+use std::collections::BTreeMap;
+use std::io;
+
+/// Default for `--max-output-mb`.
+pub const DEFAULT_MAX_OUTPUT_MB: u64 = 1024;
+
+pub struct OutputGuard {
+    /// `None` with `--force`.
+    pub max_bytes: Option<u64>,
+    /// Cleared by `--no-word-maps`, which keeps only the top words and
+    /// n-grams.
+    pub keep_word_maps: bool,
+    /// Drop map entries seen fewer times than this.
+    pub min_word_count: usize,
+    /// `--readonly`: write the file directly rather than through a
+    /// temporary file next to it.
+    pub in_place: bool,
+}
+
+impl OutputGuard {
+    pub fn from_args(args: &Args) -> Self {
+        OutputGuard {
+            max_bytes: (!args.force).then(|| args.max_output_mb.saturating_mul(1024 * 1024)),
+            keep_word_maps: !args.no_word_maps,
+            min_word_count: args.min_word_count,
+            in_place: args.readonly,
+        }
+    }
+
+    /// Trims the word maps as requested. Only the written JSON is affected;
+    /// call this after the console report.
+    pub fn prune(&self, result: &mut AnalysisResult) {
+        if self.keep_word_maps && self.min_word_count <= 1 {
+            return;
+        }
+        let prune_map = |map: &mut BTreeMap<String, usize>| {
+            if self.keep_word_maps {
+                map.retain(|_, count| *count >= self.min_word_count);
+            } else {
+                map.clear();
+            }
+        };
+        let prune_authors = |authors: &mut [AuthorAnalysis]| {
+            for author in authors {
+                prune_map(&mut author.word_frequency);
+                prune_map(&mut author.ngram_frequency);
+            }
+        };
+        prune_map(&mut result.global_word_frequency);
+        prune_map(&mut result.global_ngram_frequency);
+        prune_authors(&mut result.authors_analysis);
+        for section in &mut result.target_sections {
+            prune_authors(&mut section.authors_analysis);
+        }
+    }
+
+    /// Fails when the pretty-printed result would exceed the limit. The
+    /// result is serialized into a byte counter that stops at the limit, so
+    /// an oversized result is never held in memory.
+    pub fn check(&self, result: &AnalysisResult) -> anyhow::Result<()> {
+        let Some(max_bytes) = self.max_bytes else {
+            return Ok(());
+        };
+        let mut counter = ByteCounter {
+            bytes: 0,
+            limit: max_bytes,
+        };
+        match serde_json::to_writer_pretty(&mut counter, result) {
+            Ok(()) => Ok(()),
+            Err(_) if counter.bytes > max_bytes => anyhow::bail!(
+                "output would exceed {} MB; pass --no-word-maps or --min-word-count <N> to \
+                 shrink it, raise --max-output-mb, or use --force to write it anyway",
+                max_bytes / (1024 * 1024)
+            ),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+struct ByteCounter {
+    bytes: u64,
+    limit: u64,
+}
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes += buf.len() as u64;
+        if self.bytes > self.limit {
+            return Err(io::Error::other("size limit exceeded"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::BlankCounts;
+    use crate::warnings::Warnings;
+
+    fn result_with_words(words: &[(&str, usize)]) -> AnalysisResult {
+        let mut result = crate::build_result(
+            Vec::new(),
+            BlankCounts::default(),
+            Vec::new(),
+            0,
+            Warnings::default(),
+        );
+        result.global_word_frequency = words
+            .iter()
+            .map(|(word, count)| (word.to_string(), *count))
+            .collect();
+        result
+    }
+
+    fn guard(max_bytes: Option<u64>) -> OutputGuard {
+        OutputGuard {
+            max_bytes,
+            keep_word_maps: true,
+            min_word_count: 1,
+            in_place: false,
+        }
+    }
+
+    #[test]
+    fn test_prune_word_maps() {
+        let mut result = result_with_words(&[("rare", 1), ("common", 5)]);
+        OutputGuard {
+            min_word_count: 2,
+            ..guard(None)
+        }
+        .prune(&mut result);
+        assert_eq!(result.global_word_frequency.len(), 1);
+        assert!(result.global_word_frequency.contains_key("common"));
+
+        OutputGuard {
+            keep_word_maps: false,
+            ..guard(None)
+        }
+        .prune(&mut result);
+        assert!(result.global_word_frequency.is_empty());
+    }
+
+    #[test]
+    fn test_check_size_limit() {
+        let words: Vec<(String, usize)> = (0..1000).map(|i| (format!("word{}", i), i)).collect();
+        let words: Vec<(&str, usize)> = words.iter().map(|(w, c)| (w.as_str(), *c)).collect();
+        let result = result_with_words(&words);
+        let err = guard(Some(1024)).check(&result).unwrap_err();
+        assert!(err.to_string().contains("--no-word-maps"));
+        assert!(guard(Some(1024 * 1024)).check(&result).is_ok());
+        assert!(guard(None).check(&result).is_ok());
+    }
+}
@@ -0,0 +1,124 @@
+//! Paths as moderation archives come: on network shares, nested deep enough
+//! to pass the Windows `MAX_PATH` limit, or named in a non-UTF-8 encoding.
+//!
+//! Inputs and outputs are kept as [`Path`]s end to end and only rendered
+//! (lossily) for messages, so an odd file name never stops a file from being
+//! opened.
+
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Windows paths at least this long need the `\\?\` prefix. Lower than
+/// `MAX_PATH` (260) because a directory path must leave room for an 8.3
+/// file name inside it.
+#[cfg(windows)]
+const WINDOWS_PATH_LIMIT: usize = 248;
+
+/// `path` in a form the OS opens whatever its length. On Windows, long paths
+/// are made absolute and given the `\\?\` prefix (`\\?\UNC\` for network
+/// shares) that lifts the `MAX_PATH` limit; elsewhere, and for short paths,
+/// the path is returned as is.
+pub fn extended(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    if path.as_os_str().len() >= WINDOWS_PATH_LIMIT {
+        if let Some(long) = std::path::absolute(path)
+            .ok()
+            .and_then(|abs| abs.to_str().and_then(verbatim))
+        {
+            return Cow::Owned(PathBuf::from(long));
+        }
+    }
+    Cow::Borrowed(path)
+}
+
+/// The `\\?\` form of an absolute Windows path, or `None` when it already
+/// has one or is not absolute.
+#[cfg(any(windows, test))]
+fn verbatim(path: &str) -> Option<String> {
+    if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return None;
+    }
+    if let Some(share) = path.strip_prefix(r"\\") {
+        return Some(format!(r"\\?\UNC\{}", share));
+    }
+    let bytes = path.as_bytes();
+    let drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+    drive.then(|| format!(r"\\?\{}", path.replace('/', r"\")))
+}
+
+/// `path` with `suffix` appended to its file name (`out.json` →
+/// `out.json.tmp`), without going through a string.
+pub fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Whether a side file that can be CSV or JSON is CSV: only for a `.csv`
+/// path, so every such file defaults to JSON.
+pub fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verbatim() {
+        assert_eq!(
+            verbatim(r"C:\archive\export.json").as_deref(),
+            Some(r"\\?\C:\archive\export.json")
+        );
+        assert_eq!(
+            verbatim(r"\\mod-share\archives\2024").as_deref(),
+            Some(r"\\?\UNC\mod-share\archives\2024")
+        );
+        assert_eq!(
+            verbatim("d:/exports/a.json").as_deref(),
+            Some(r"\\?\d:\exports\a.json")
+        );
+        assert_eq!(verbatim(r"\\?\C:\already"), None);
+        assert_eq!(verbatim(r"relative\path"), None);
+    }
+
+    #[test]
+    fn test_short_paths_are_unchanged() {
+        let path = Path::new("exports/a.json");
+        assert_eq!(extended(path), Cow::Borrowed(path));
+    }
+
+    #[test]
+    fn test_side_files_default_to_json() {
+        assert!(is_csv(Path::new("out/co.CSV")));
+        assert!(!is_csv(Path::new("out/co.json")));
+        assert!(!is_csv(Path::new("out/co.txt")));
+        assert!(!is_csv(Path::new("out/co")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_names_survive() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = std::env::temp_dir().join(format!("dua-paths-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join(std::ffi::OsStr::from_bytes(b"caf\xe9.json"));
+        std::fs::write(&file, "[]").unwrap();
+
+        let tmp = with_suffix(&file, ".tmp");
+        assert_eq!(
+            tmp.as_os_str().as_bytes(),
+            [file.as_os_str().as_bytes(), b".tmp"].concat()
+        );
+        let files = crate::input::expand_inputs(std::slice::from_ref(&dir)).unwrap();
+        assert_eq!(files, vec![file]);
+        assert_eq!(std::fs::read(&files[0]).unwrap(), b"[]");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
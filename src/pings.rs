@@ -0,0 +1,73 @@
+//! Direct pings versus talk: whether a message tags the deleted user
+//! (`<@id>`, `<@!id>` or a rendered `@Deleted User`) or only refers to them,
+//! by replying to them or with a tag the export resolved elsewhere.
+//!
+//! Exports name the mentioned user but not their ID, so the target's IDs
+//! are learned from messages that mention a target and tag exactly one
+//! user: that tag must be the target.
+
+use crate::content;
+use crate::message::Message;
+use crate::target::Target;
+use std::collections::HashSet;
+
+/// User IDs resolved to a target.
+#[derive(Debug, Clone, Default)]
+pub struct TargetIds(HashSet<String>);
+
+impl TargetIds {
+    /// Learns the target's ID from `msg` when it mentions a target and tags
+    /// a single user.
+    pub fn learn(&mut self, msg: &Message, target: &Target) {
+        if !target.mentioned(msg) {
+            return;
+        }
+        if let [id] = content::user_mention_ids(&msg.content)[..] {
+            if !self.0.contains(id) {
+                self.0.insert(id.to_string());
+            }
+        }
+    }
+
+    /// Whether `msg` pings a target directly.
+    pub fn pings(&self, msg: &Message) -> bool {
+        content::user_mention_ids(&msg.content)
+            .iter()
+            .any(|id| self.0.contains(*id))
+            || content::mentioned_names(msg)
+                .iter()
+                .filter(|name| !name.trim().is_empty())
+                .any(|name| msg.content.contains(&format!("@{}", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(content: &str, mentioned: Option<&str>) -> Message {
+        Message {
+            content: content.to_string(),
+            mentioned_user_name: mentioned.map(str::to_string),
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_single_tag_resolves_the_target() {
+        let target = Target::default();
+        let mut ids = TargetIds::default();
+        // Two tags: either could be the target.
+        ids.learn(&message("<@1> <@2> hi", Some("Deleted User")), &target);
+        ids.learn(&message("<@3> gg", Some("someone")), &target);
+        assert!(!ids.pings(&message("<@1>", Some("Deleted User"))));
+
+        ids.learn(
+            &message("<@1> where are you", Some("Deleted User")),
+            &target,
+        );
+        assert!(ids.pings(&message("<@!1> <@2>", Some("Deleted User"))));
+        assert!(ids.pings(&message("hey @Deleted User", Some("Deleted User"))));
+        assert!(!ids.pings(&message("<@3> did they leave?", Some("Deleted User"))));
+    }
+}
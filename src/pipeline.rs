@@ -0,0 +1,382 @@
+//! Per-run settings derived from the command line.
+//!
+//! Anything that must be prepared once (files read, samplers built) lives
+//! here, so the in-memory, bounded and watch paths apply exactly the same
+//! rules.
+
+use crate::chain::{FilterChain, MessageFilter};
+use crate::checkpoint::RunState;
+use crate::concordance::Concordance;
+use crate::events::Events;
+use crate::filter::{
+    self, AttachmentFilter, AuthorFilter, ChannelFilter, ContentFilter, DateWindow, IdRange,
+    LanguageFilter, RoleFilter,
+};
+use crate::lemma::Lemmatizer;
+use crate::message::{Message, RawMessage};
+use crate::nicknames::NicknameFilter;
+use crate::optout::{OptOutList, OptOutTally};
+use crate::output::OutputGuard;
+use crate::sampling::{BottomK, SampleSpec, Sampler};
+use crate::sanitize::Sanitizer;
+use crate::stem::Stem;
+use crate::stopwords::Stopwords;
+use crate::target::{NameMatching, Target};
+use crate::tokenize::Tokenizer;
+use crate::toxicity::Flagger;
+use crate::vocab::VocabCap;
+use crate::{repair_timestamps, AnalysisResult, Args};
+use chrono_tz::Tz;
+use rayon::prelude::*;
+
+pub struct Pipeline {
+    pub target: Target,
+    pub sampler: Option<Sampler>,
+    pub opt_out: OptOutList,
+    /// The message filters, target check first, in the order they run;
+    /// `filter_stats` charges a dropped message to the first filter that
+    /// rejects it. Inactive filters are left out, since they keep every
+    /// message.
+    pub filters: FilterChain,
+    /// `--timezone`: where calendar days start, for date filters and
+    /// day-based statistics.
+    pub timezone: Tz,
+    /// How message content is split into counted words.
+    pub tokenizer: Tokenizer,
+    /// `--stem`: words are counted by their stem.
+    pub stem: Option<Stem>,
+    /// `--lemmatize` / `--lemma-file`: words are counted by their lemma.
+    pub lemmatizer: Option<Lemmatizer>,
+    /// `--concordance`: words listed with their context.
+    pub concordance: Option<Concordance>,
+    /// `--flag-toxicity`: terms that flag a message.
+    pub flagger: Option<Flagger>,
+    /// `--max-author-vocab`: distinct words counted per author.
+    pub vocab_cap: Option<VocabCap>,
+    /// `--events` windows, bucketed in the result or excluded.
+    pub events: Events,
+    /// `--deletion-gaps`: record every message ID per channel.
+    pub deletion_gaps: bool,
+    /// `--nickname-differs` / `--multiple-nicknames`, applied once every
+    /// input is loaded.
+    pub nicknames: NicknameFilter,
+    /// Authors with fewer matching messages are left out of the per-author
+    /// analysis, though still counted in the totals.
+    pub min_author_messages: usize,
+    /// Applied to every result before it is displayed or written.
+    pub sanitizer: Sanitizer,
+    /// Applied to the JSON output only, after the console report.
+    pub output: OutputGuard,
+}
+
+impl Pipeline {
+    pub fn from_args(args: &Args) -> anyhow::Result<Self> {
+        let opt_out = match &args.opt_out {
+            Some(path) => OptOutList::load(path)?,
+            None => OptOutList::default(),
+        };
+        let timezone = args.timezone.unwrap_or(Tz::UTC);
+        if args.max_author_vocab == Some(0) {
+            anyhow::bail!("--max-author-vocab must be at least 1");
+        }
+        let events = match &args.events {
+            Some(path) => Events::load(path, args.event_window, args.exclude_events, timezone)?,
+            None => Events::default(),
+        };
+        let target = if args.no_target_filter {
+            Target::everyone()
+        } else if let Some(days) = args.inactive_targets {
+            Target::inactive(days)
+        } else {
+            Target::load(
+                &args.target,
+                &args.target_regex,
+                args.targets_file.as_deref(),
+                NameMatching {
+                    match_case: args.match_case,
+                    normalize_unicode: args.normalize_unicode,
+                },
+            )?
+        };
+        Ok(Pipeline {
+            filters: filters(args, &target, &events, timezone)?,
+            target,
+            sampler: args.sampler(),
+            opt_out,
+            timezone,
+            tokenizer: Tokenizer {
+                mode: args.tokenizer,
+                cjk: args.cjk,
+                strip_quotes: args.strip_quotes,
+                strip_urls: args.strip_urls,
+                strip_mentions: args.strip_mentions,
+                strip_code_blocks: !args.include_code,
+                strip_emoji: args.strip_emoji,
+                strip_spoilers: args.strip_spoilers,
+                normalize_leet: args.normalize_leet,
+                stopwords: Stopwords::load(&args.stopwords, args.stopwords_file.as_deref())?,
+                max_len: args.max_word_length,
+                drop_numbers: args.drop_numbers,
+                drop_pattern: args.drop_tokens_matching.clone(),
+                ..Tokenizer::new(args.min_word_length, &args.token_chars)
+            },
+            stem: args.stem.map(Stem::for_language).transpose()?,
+            lemmatizer: if args.lemmatize.is_some() || args.lemma_file.is_some() {
+                Some(Lemmatizer::load(
+                    args.lemmatize,
+                    args.lemma_file.as_deref(),
+                )?)
+            } else {
+                None
+            },
+            concordance: Concordance::new(&args.concordance, args.concordance_context),
+            flagger: if args.flag_toxicity {
+                Some(Flagger::new(
+                    args.flag_terms.as_deref(),
+                    args.flag_threshold,
+                )?)
+            } else {
+                None
+            },
+            vocab_cap: args.max_author_vocab.map(VocabCap),
+            events,
+            deletion_gaps: args.deletion_gaps,
+            nicknames: NicknameFilter {
+                differs: args.nickname_differs,
+                multiple: args.multiple_nicknames,
+            },
+            min_author_messages: args.min_author_messages,
+            sanitizer: Sanitizer::new(args.mask_profanity, args.mask_words.as_deref())?,
+            output: OutputGuard::from_args(args),
+        })
+    }
+
+    /// Applies fallbacks, samples, drops opted-out authors, repairs
+    /// timestamps and keeps the records that pass [`Pipeline::matches`].
+    pub fn ingest(&self, state: &mut RunState, path: &str, raw_msgs: Vec<RawMessage>) {
+        let mut file_msgs: Vec<Message> = raw_msgs
+            .into_iter()
+            .map(|raw| raw.into_message(&mut state.fallbacks))
+            .collect();
+        state.loaded += file_msgs.len();
+        // Gaps are counted over the whole export, before anything is dropped.
+        if self.deletion_gaps {
+            let gaps = state.gaps.get_or_insert_with(Default::default);
+            for msg in &file_msgs {
+                gaps.record(msg);
+            }
+        }
+        // So is activity, or sampled-out messages would make their authors
+        // look inactive; opted-out authors are never looked at.
+        if self.target.inactive_days().is_some() {
+            let activity = state.activity.get_or_insert_with(Default::default);
+            for msg in &file_msgs {
+                if !self.opt_out.contains(&msg.author_id) {
+                    activity.record(msg);
+                }
+            }
+        }
+        if let Some(sampler) = self.sampler {
+            match sampler.spec {
+                SampleSpec::Fraction(_) => file_msgs.retain(|msg| sampler.keep(msg)),
+                // The sample is only known once every key has been seen, so
+                // matched messages are trimmed to it in `summarize_state`.
+                SampleSpec::Count(count) => {
+                    let keys = state.sample_keys.get_or_insert_with(|| BottomK::new(count));
+                    for msg in &file_msgs {
+                        keys.push(sampler.key(msg));
+                    }
+                }
+            }
+        }
+        state.sampled += file_msgs.len();
+        if !self.opt_out.is_empty() {
+            file_msgs.retain(|msg| !self.opted_out(msg, &mut state.opted_out));
+        }
+        state.conflicts.record(path, &file_msgs);
+        if self.nicknames.is_active() {
+            let tracker = state.nicknames.get_or_insert_with(Default::default);
+            tracker.record(&file_msgs);
+        }
+        repair_timestamps(&mut file_msgs, &mut state.warnings);
+        let verdicts: Vec<(Message, Option<&str>)> = file_msgs
+            .into_par_iter()
+            .map(|msg| {
+                let rejected_by = self.filters.rejected_by(&msg);
+                (msg, rejected_by)
+            })
+            .collect();
+        for (msg, rejected_by) in verdicts {
+            state.filter_stats.record(rejected_by);
+            if rejected_by.is_none() {
+                state.matched.push(msg);
+            }
+        }
+    }
+
+    /// Whether a loaded message is one the analysis is about.
+    pub fn matches(&self, msg: &Message) -> bool {
+        self.filters.matches(msg)
+    }
+
+    /// Names of the filters this run applies, in the order they run.
+    pub fn active_filters(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.filters.names().collect();
+        if self.nicknames.is_active() {
+            names.push("nickname");
+        }
+        names
+    }
+
+    /// Records the run-level settings that shape how a result reads.
+    pub fn annotate(&self, result: &mut AnalysisResult) {
+        if !self.target.is_default() {
+            result.targets = self.target.names();
+        }
+        if self.timezone != Tz::UTC {
+            result.timezone = Some(self.timezone);
+        }
+
+        let min = self.min_author_messages;
+        let before = result.authors_analysis.len();
+        result
+            .authors_analysis
+            .retain(|a| a.total_messages_to_deleted_user >= min);
+        result.omitted_authors = before - result.authors_analysis.len();
+        for section in &mut result.target_sections {
+            section
+                .authors_analysis
+                .retain(|a| a.total_messages_to_deleted_user >= min);
+        }
+    }
+
+    /// Whether the message belongs to an opted-out author, tallying it if so.
+    pub fn opted_out(&self, msg: &Message, tally: &mut OptOutTally) -> bool {
+        if !self.opt_out.contains(&msg.author_id) {
+            return false;
+        }
+        tally.record(&msg.author_id, self.matches(msg));
+        true
+    }
+}
+
+/// The active message filters, in the order they run.
+fn filters(
+    args: &Args,
+    target: &Target,
+    events: &Events,
+    timezone: Tz,
+) -> anyhow::Result<FilterChain> {
+    let mut chain = FilterChain::new();
+    if !target.is_everyone() {
+        chain.push("target", target.clone());
+    }
+    let window = DateWindow {
+        since: args.since.map(|bound| bound.start(timezone)),
+        until: args.until.map(|bound| bound.end(timezone)),
+    };
+    if !window.is_unbounded() {
+        chain.push("since/until", window);
+    }
+    let ids = IdRange {
+        after: args.after_id,
+        before: args.before_id,
+    };
+    if ids.is_active() {
+        chain.push("after/before-id", ids);
+    }
+    let authors = AuthorFilter::new(
+        &args.authors,
+        args.authors_file.as_deref(),
+        &args.exclude_authors,
+    )?;
+    if authors.is_active() {
+        chain.push("authors", authors);
+    }
+    let channels = ChannelFilter::new(&args.channel);
+    if channels.is_active() {
+        chain.push("channel", channels);
+    }
+    let roles = RoleFilter::new(&args.role, &args.exclude_role);
+    if roles.is_active() {
+        chain.push("role", roles);
+    }
+    if args.only_with_attachments {
+        chain.push("attachments", AttachmentFilter::With);
+    } else if args.text_only {
+        chain.push("attachments", AttachmentFilter::Without);
+    }
+    let content = ContentFilter {
+        include: args.content_match.clone(),
+        exclude: args
+            .content_exclude
+            .iter()
+            .cloned()
+            .chain(match &args.exclude_keyword_file {
+                Some(path) => filter::read_keyword_file(path)?,
+                None => None,
+            })
+            .collect(),
+        min_chars: args.min_content_chars,
+        max_chars: args.max_content_chars,
+    };
+    if content.is_active() {
+        chain.push("content", content);
+    }
+    if args.language.is_some() {
+        chain.push("language", LanguageFilter(args.language));
+    }
+    if let Some(expr) = &args.filter {
+        chain.push("filter", expr.clone().in_timezone(timezone));
+    }
+    if args.exclude_bots {
+        chain.push("exclude-bots", |msg: &Message| !msg.is_automated());
+    }
+    if !args.include_system {
+        chain.push("system", |msg: &Message| !msg.is_system());
+    }
+    if events.is_excluding() {
+        let events = events.clone();
+        chain.push("exclude-events", move |msg: &Message| !events.excludes(msg));
+    }
+    Ok(chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_filters_follow_the_chain() {
+        let args = Args::try_parse_from([
+            "deleted-user-analyzer",
+            "-i",
+            "a.json",
+            "--exclude-bots",
+            "--min-content-chars",
+            "3",
+        ])
+        .unwrap();
+        let pipeline = Pipeline::from_args(&args).unwrap();
+        assert_eq!(
+            pipeline.active_filters(),
+            ["target", "content", "exclude-bots", "system"]
+        );
+        let msg = |content: &str, is_bot: bool| Message {
+            content: content.to_string(),
+            mentioned_user_name: Some("Deleted User".to_string()),
+            is_bot,
+            ..Message::default()
+        };
+        assert!(pipeline.matches(&msg("hello", false)));
+        assert_eq!(
+            pipeline.filters.rejected_by(&msg("hi", true)),
+            Some("content")
+        );
+        assert_eq!(
+            pipeline.filters.rejected_by(&msg("hello", true)),
+            Some("exclude-bots")
+        );
+    }
+}
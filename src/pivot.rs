@@ -0,0 +1,176 @@
+//! Authors × channels matrix of analyzed message counts, showing where each
+//! author engaged the target.
+
+use crate::message::Message;
+use crate::paths;
+use crate::timeline::csv_field;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Column for messages whose export has no channel information.
+const NO_CHANNEL: &str = "(no channel)";
+
+#[derive(Debug, Clone, Default)]
+pub struct Pivot {
+    /// Column headers, busiest channel first.
+    pub channels: Vec<String>,
+    /// One row per author, most active first.
+    pub rows: Vec<PivotRow>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PivotRow {
+    pub author_id: String,
+    pub author_name: String,
+    /// Message counts, in the order of [`Pivot::channels`].
+    pub counts: Vec<usize>,
+    pub total: usize,
+}
+
+impl Pivot {
+    pub fn build(msgs: &[Message]) -> Self {
+        let mut cells: HashMap<(&str, &str), usize> = HashMap::new();
+        let mut channel_totals: HashMap<&str, usize> = HashMap::new();
+        let mut authors: HashMap<&str, (&str, usize)> = HashMap::new();
+        for msg in msgs {
+            let channel = msg
+                .channel_name
+                .as_deref()
+                .or(msg.channel_id.as_deref())
+                .unwrap_or(NO_CHANNEL);
+            *cells.entry((&msg.author_id, channel)).or_insert(0) += 1;
+            *channel_totals.entry(channel).or_insert(0) += 1;
+            authors
+                .entry(&msg.author_id)
+                .or_insert((&msg.author_name, 0))
+                .1 += 1;
+        }
+
+        let mut channels: Vec<(&str, usize)> = channel_totals.into_iter().collect();
+        channels.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let mut rows: Vec<PivotRow> = authors
+            .into_iter()
+            .map(|(author_id, (author_name, total))| PivotRow {
+                author_id: author_id.to_string(),
+                author_name: author_name.to_string(),
+                counts: channels
+                    .iter()
+                    .map(|(channel, _)| cells.get(&(author_id, *channel)).copied().unwrap_or(0))
+                    .collect(),
+                total,
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            b.total
+                .cmp(&a.total)
+                .then_with(|| a.author_id.cmp(&b.author_id))
+        });
+
+        Pivot {
+            channels: channels
+                .into_iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            rows,
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let mut header = vec!["author_id".to_string(), "author_name".to_string()];
+        header.extend(self.channels.iter().map(|channel| csv_field(channel)));
+        header.push("total".to_string());
+        let mut out = header.join(",");
+        out.push('\n');
+        for row in &self.rows {
+            let mut fields = vec![csv_field(&row.author_id), csv_field(&row.author_name)];
+            fields.extend(row.counts.iter().map(usize::to_string));
+            fields.push(row.total.to_string());
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    fn to_html(&self) -> String {
+        let mut out = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Messages by author and channel</title>\n\
+             <style>table{border-collapse:collapse}th,td{border:1px solid #ccc;padding:2px 6px}\
+             td.n{text-align:right}td.z{color:#bbb}</style>\n</head>\n<body>\n<table>\n<tr><th>Author</th>",
+        );
+        for channel in &self.channels {
+            out.push_str(&format!("<th>{}</th>", html_escape(channel)));
+        }
+        out.push_str("<th>Total</th></tr>\n");
+        for row in &self.rows {
+            out.push_str(&format!(
+                "<tr><td title=\"{}\">{}</td>",
+                html_escape(&row.author_id),
+                html_escape(&row.author_name)
+            ));
+            for count in &row.counts {
+                let class = if *count == 0 { "n z" } else { "n" };
+                out.push_str(&format!("<td class=\"{}\">{}</td>", class, count));
+            }
+            out.push_str(&format!("<td class=\"n\"><b>{}</b></td></tr>\n", row.total));
+        }
+        out.push_str("</table>\n</body>\n</html>\n");
+        out
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes HTML for an `.html`/`.htm` path and CSV otherwise.
+pub fn write(pivot: &Pivot, path: &Path) -> anyhow::Result<()> {
+    let is_html = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"));
+    let data = if is_html {
+        pivot.to_html()
+    } else {
+        pivot.to_csv()
+    };
+    fs::write(paths::extended(path), data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(author: &str, channel: Option<&str>) -> Message {
+        Message {
+            author_id: author.to_string(),
+            author_name: format!("name-{}", author),
+            channel_name: channel.map(str::to_string),
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_build_pivot() {
+        let pivot = Pivot::build(&[
+            msg("1", Some("general")),
+            msg("1", Some("general")),
+            msg("2", Some("memes")),
+            msg("2", None),
+            msg("2", Some("general")),
+        ]);
+        assert_eq!(pivot.channels, ["general", "(no channel)", "memes"]);
+        assert_eq!(pivot.rows[0].author_id, "2");
+        assert_eq!(pivot.rows[0].counts, [1, 1, 1]);
+        assert_eq!(pivot.rows[1].counts, [2, 0, 0]);
+        assert_eq!(
+            pivot.to_csv(),
+            "author_id,author_name,general,(no channel),memes,total\n\
+             2,name-2,1,1,1,3\n1,name-1,2,0,0,2\n"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Background file reading so IO overlaps with parsing.
+//!
+//! One reader thread reads the input files in order, in chunks of
+//! [`CHUNK_BYTES`], and hands them over a bounded channel, so the disk keeps
+//! reading while earlier chunks are parsed, within one file as well as
+//! across files. The bound keeps at most [`PREFETCH_CHUNKS`] chunks waiting
+//! in memory. Each file is handed out as a [`Chunks`] reader that the
+//! loader parses incrementally.
+
+use crate::paths;
+use std::fs::File;
+use std::io::{self, BufRead, Read};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// Bytes read per chunk.
+pub const CHUNK_BYTES: usize = 1024 * 1024;
+
+/// Chunks read ahead of the one being parsed.
+pub const PREFETCH_CHUNKS: usize = 8;
+
+enum Piece {
+    Start(PathBuf),
+    Bytes(Vec<u8>),
+    Failed(io::Error),
+    End,
+}
+
+pub struct Prefetch {
+    rx: Receiver<Piece>,
+}
+
+/// Starts reading `inputs` in the background.
+pub fn files(inputs: Vec<PathBuf>) -> Prefetch {
+    files_in_chunks(inputs, CHUNK_BYTES)
+}
+
+fn files_in_chunks(inputs: Vec<PathBuf>, chunk_bytes: usize) -> Prefetch {
+    let (tx, rx) = mpsc::sync_channel(PREFETCH_CHUNKS);
+    thread::spawn(move || {
+        for path in inputs {
+            let file = File::open(paths::extended(&path));
+            // The receiver is gone once the consumer has stopped early.
+            if tx.send(Piece::Start(path)).is_err() {
+                return;
+            }
+            let mut file = match file {
+                Ok(file) => file,
+                Err(err) => {
+                    if tx.send(Piece::Failed(err)).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+            };
+            loop {
+                let mut chunk = Vec::with_capacity(chunk_bytes);
+                let piece = match (&mut file).take(chunk_bytes as u64).read_to_end(&mut chunk) {
+                    Ok(0) => Piece::End,
+                    Ok(_) => Piece::Bytes(chunk),
+                    Err(err) => Piece::Failed(err),
+                };
+                let last = !matches!(piece, Piece::Bytes(_));
+                if tx.send(piece).is_err() {
+                    return;
+                }
+                if last {
+                    break;
+                }
+            }
+        }
+    });
+    Prefetch { rx }
+}
+
+impl Prefetch {
+    /// The next file and a reader over its chunks as they arrive. Chunks of
+    /// the previous file its reader left unread are skipped.
+    pub fn next_file(&mut self) -> Option<(PathBuf, Chunks<'_>)> {
+        loop {
+            if let Piece::Start(path) = self.rx.recv().ok()? {
+                let chunks = Chunks {
+                    rx: &self.rx,
+                    chunk: Vec::new(),
+                    pos: 0,
+                    done: false,
+                };
+                return Some((path, chunks));
+            }
+        }
+    }
+}
+
+/// One file's bytes, read from the channel chunk by chunk. A read error in
+/// the reader thread is returned once, after the bytes read before it.
+pub struct Chunks<'a> {
+    rx: &'a Receiver<Piece>,
+    chunk: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl BufRead for Chunks<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.pos == self.chunk.len() && !self.done {
+            match self.rx.recv() {
+                Ok(Piece::Bytes(bytes)) => {
+                    self.chunk = bytes;
+                    self.pos = 0;
+                }
+                Ok(Piece::Failed(err)) => {
+                    self.done = true;
+                    return Err(err);
+                }
+                // Every file ends in `End` or `Failed` before the next
+                // `Start`, so this is the end of the file.
+                Ok(_) | Err(_) => self.done = true,
+            }
+        }
+        Ok(&self.chunk[self.pos..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.pos = (self.pos + amount).min(self.chunk.len());
+    }
+}
+
+impl Read for Chunks<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_files_arrive_in_order_and_in_chunks() {
+        let dir = std::env::temp_dir().join(format!("dua-prefetch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let paths: Vec<PathBuf> = (0..5)
+            .map(|i| {
+                let path = dir.join(format!("{}.json", i));
+                fs::write(&path, format!("file {} of several chunks", i)).unwrap();
+                path
+            })
+            .chain(std::iter::once(dir.join("missing.json")))
+            .collect();
+
+        let mut prefetch = files_in_chunks(paths.clone(), 4);
+        for (i, expected) in paths.iter().take(5).enumerate() {
+            let (path, mut chunks) = prefetch.next_file().unwrap();
+            assert_eq!(&path, expected);
+            if i == 2 {
+                // Only partly read: the rest is skipped.
+                let mut start = [0; 6];
+                chunks.read_exact(&mut start).unwrap();
+                continue;
+            }
+            let mut text = String::new();
+            chunks.read_to_string(&mut text).unwrap();
+            assert_eq!(text, format!("file {} of several chunks", i));
+        }
+        let (path, mut chunks) = prefetch.next_file().unwrap();
+        assert_eq!(path, paths[5]);
+        assert!(chunks.read_to_end(&mut Vec::new()).is_err());
+        assert!(prefetch.next_file().is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+//! Normalized per-author behavioral profiles for radar-chart rendering.
+
+use crate::message::Message;
+use crate::tokenize::tokenize_content;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Small built-in list of hostile terms used for the negativity axis.
+const NEGATIVE_TERMS: &[&str] = &[
+    "annoying",
+    "awful",
+    "cringe",
+    "damn",
+    "die",
+    "disgusting",
+    "dumb",
+    "fake",
+    "hate",
+    "hell",
+    "idiot",
+    "liar",
+    "loser",
+    "moron",
+    "pathetic",
+    "shut",
+    "stupid",
+    "sucks",
+    "terrible",
+    "trash",
+    "ugly",
+    "useless",
+    "worst",
+];
+
+/// Second-person forms that mark a message as addressed at the target.
+const DIRECT_TERMS: &[&str] = &["you", "your", "youre", "yours", "yourself", "u", "ur"];
+
+/// Raw, un-normalized features for one author.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RadarInputs {
+    pub message_count: usize,
+    /// Burstiness of inter-message gaps, already mapped onto 0-1.
+    pub burstiness: f64,
+    pub negativity: f64,
+    pub directness: f64,
+    /// Unix milliseconds of the author's latest message.
+    pub last_seen: Option<i64>,
+}
+
+/// Per-author feature vector where every axis lies in 0-1.
+#[derive(Serialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+pub struct RadarProfile {
+    pub volume: f64,
+    pub burstiness: f64,
+    pub negativity: f64,
+    pub directness: f64,
+    pub recency: f64,
+}
+
+/// Running counts behind an author's `RadarInputs`, for callers that see
+/// the messages one at a time.
+#[derive(Debug, Clone, Default)]
+pub struct RadarTally {
+    messages: usize,
+    negative: usize,
+    direct: usize,
+}
+
+impl RadarTally {
+    pub fn record(&mut self, msg: &Message) {
+        self.messages += 1;
+        let words = tokenize_content(&msg.content, 1);
+        if words.iter().any(|w| NEGATIVE_TERMS.contains(&w.as_str())) {
+            self.negative += 1;
+        }
+        if words.iter().any(|w| DIRECT_TERMS.contains(&w.as_str())) {
+            self.direct += 1;
+        }
+    }
+
+    /// The inputs for the recorded messages, whose sorted Unix-millisecond
+    /// times are `sorted_times`.
+    pub fn inputs(&self, sorted_times: &[i64]) -> RadarInputs {
+        let share = |n: usize| {
+            if self.messages == 0 {
+                0.0
+            } else {
+                n as f64 / self.messages as f64
+            }
+        };
+
+        RadarInputs {
+            message_count: self.messages,
+            burstiness: burstiness(sorted_times),
+            negativity: share(self.negative),
+            directness: share(self.direct),
+            last_seen: sorted_times.last().copied(),
+        }
+    }
+}
+
+impl RadarInputs {
+    pub fn from_messages(msgs: &[Message]) -> Self {
+        let mut times: Vec<i64> = msgs
+            .iter()
+            .filter_map(|msg| msg.parsed_timestamp())
+            .map(|ts| ts.timestamp_millis())
+            .collect();
+        times.sort_unstable();
+
+        let mut tally = RadarTally::default();
+        for msg in msgs {
+            tally.record(msg);
+        }
+        tally.inputs(&times)
+    }
+}
+
+/// Goh-Barabási burstiness `(σ - μ) / (σ + μ)` of the gaps between sorted
+/// timestamps, rescaled from -1..1 to 0..1. Fewer than two gaps yield 0.
+fn burstiness(sorted_times: &[i64]) -> f64 {
+    if sorted_times.len() < 3 {
+        return 0.0;
+    }
+    let gaps: Vec<f64> = sorted_times
+        .windows(2)
+        .map(|w| (w[1] - w[0]) as f64)
+        .collect();
+    let mean = gaps.iter().sum::<f64>() / gaps.len() as f64;
+    let variance = gaps.iter().map(|g| (g - mean).powi(2)).sum::<f64>() / gaps.len() as f64;
+    let sd = variance.sqrt();
+    if sd + mean == 0.0 {
+        return 0.0;
+    }
+    ((sd - mean) / (sd + mean) + 1.0) / 2.0
+}
+
+/// Scales volume and recency against the other authors; the share-based axes
+/// are already 0-1 and pass through unchanged.
+pub fn normalize(inputs: &[RadarInputs]) -> Vec<RadarProfile> {
+    let max_count = inputs.iter().map(|i| i.message_count).max().unwrap_or(0);
+    let first = inputs.iter().filter_map(|i| i.last_seen).min();
+    let last = inputs.iter().filter_map(|i| i.last_seen).max();
+
+    inputs
+        .iter()
+        .map(|i| {
+            let volume = if max_count == 0 {
+                0.0
+            } else {
+                i.message_count as f64 / max_count as f64
+            };
+            let recency = match (i.last_seen, first, last) {
+                (Some(seen), Some(first), Some(last)) if last > first => {
+                    (seen - first) as f64 / (last - first) as f64
+                }
+                (Some(_), _, _) => 1.0,
+                _ => 0.0,
+            };
+            RadarProfile {
+                volume,
+                burstiness: i.burstiness,
+                negativity: i.negativity,
+                directness: i.directness,
+                recency,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burstiness_regular_vs_bursty() {
+        let regular = burstiness(&[0, 10, 20, 30, 40]);
+        let bursty = burstiness(&[0, 1, 2, 3, 1000]);
+        assert!(regular < 0.01);
+        assert!(bursty > 0.5);
+        assert_eq!(burstiness(&[0, 10]), 0.0);
+    }
+
+    #[test]
+    fn test_normalize_scales_volume_and_recency() {
+        let inputs = [
+            RadarInputs {
+                message_count: 10,
+                last_seen: Some(2_000),
+                ..Default::default()
+            },
+            RadarInputs {
+                message_count: 5,
+                last_seen: Some(1_000),
+                ..Default::default()
+            },
+        ];
+        let profiles = normalize(&inputs);
+        assert_eq!(profiles[0].volume, 1.0);
+        assert_eq!(profiles[1].volume, 0.5);
+        assert_eq!(profiles[0].recency, 1.0);
+        assert_eq!(profiles[1].recency, 0.0);
+    }
+}
@@ -0,0 +1,116 @@
+//! `--readonly`: for analyses over mounted evidence, where nothing but the
+//! requested report may be written.
+//!
+//! Inputs are only ever opened for reading. Options that write anything
+//! else (state files, spill directories, side outputs, usage logs) are
+//! rejected by the argument parser, the JSON output is written in place
+//! instead of through a temporary file next to it, and it may not land
+//! inside an input.
+
+use crate::Args;
+use std::path::{Path, PathBuf};
+
+/// Options that write somewhere other than `--output`; `--readonly`
+/// conflicts with each of them.
+pub const WRITING_OPTIONS: &[&str] = &[
+    "checkpoint",
+    "resume",
+    "max_memory",
+    "usage_log",
+    "timeline",
+    "pivot",
+    "explain_matches",
+    "cooccurrence",
+    "similarity",
+    "output_dir",
+];
+
+/// Refuses an `--output` that is one of the inputs or inside an input
+/// directory.
+pub fn check(args: &Args) -> anyhow::Result<()> {
+    let Some(output) = &args.output else {
+        return Ok(());
+    };
+    let target = resolve(output);
+    for input in &args.input {
+        let Ok(input) = input.canonicalize() else {
+            continue;
+        };
+        if target.starts_with(&input) {
+            anyhow::bail!(
+                "--readonly: --output {} is inside the input {}",
+                output.display(),
+                input.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `path` with its directory resolved, whether or not the file exists yet.
+fn resolve(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match (parent.canonicalize(), path.file_name()) {
+        (Ok(parent), Some(name)) => parent.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::fs;
+
+    #[test]
+    fn test_output_must_stay_outside_inputs() {
+        let dir = std::env::temp_dir().join(format!("dua-readonly-{}", std::process::id()));
+        let evidence = dir.join("evidence");
+        fs::create_dir_all(&evidence).unwrap();
+        fs::write(evidence.join("export.json"), "[]").unwrap();
+
+        let args = |output: &Path| {
+            Args::try_parse_from([
+                "deleted-user-analyzer".as_ref(),
+                "--readonly".as_ref(),
+                "-i".as_ref(),
+                evidence.as_os_str(),
+                "-o".as_ref(),
+                output.as_os_str(),
+            ])
+            .unwrap()
+        };
+        assert!(check(&args(&evidence.join("report.json"))).is_err());
+        assert!(check(&args(&evidence.join("export.json"))).is_err());
+        assert!(check(&args(&dir.join("report.json"))).is_ok());
+        // Nor may the output schema.
+        let schema = Args::try_parse_from([
+            "deleted-user-analyzer".as_ref(),
+            "--readonly".as_ref(),
+            "--emit-output-schema".as_ref(),
+            "-i".as_ref(),
+            evidence.as_os_str(),
+            "-o".as_ref(),
+            evidence.join("schema.json").as_os_str(),
+        ])
+        .unwrap();
+        assert!(check(&schema).is_err());
+
+        let writes = Args::try_parse_from([
+            "deleted-user-analyzer",
+            "--readonly",
+            "-i",
+            "a.json",
+            "--checkpoint",
+            "state.json",
+        ]);
+        assert!(writes.is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+//! `references`: the channels each author pointed the target to, from raw
+//! `<#id>` references and `#channel-name` words.
+//!
+//! Raw references carry only an ID; its name is filled in from the
+//! channels the analyzed messages were sent in, when one of them matches.
+
+use crate::content;
+use crate::{AnalysisResult, AuthorAnalysis};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// What an author's messages refer to.
+#[derive(Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+pub struct References {
+    /// Channels referenced, most often first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub channels: Vec<ChannelReference>,
+}
+
+impl References {
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct ChannelReference {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_name: Option<String>,
+    pub count: usize,
+}
+
+/// References keyed by channel ID, or by `#name` for hashtags.
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceTally(BTreeMap<String, ChannelReference>);
+
+impl ReferenceTally {
+    pub fn record(&mut self, text: &str) {
+        for id in content::channel_ref_ids(text) {
+            self.0
+                .entry(id.to_string())
+                .or_insert_with(|| ChannelReference {
+                    channel_id: Some(id.to_string()),
+                    channel_name: None,
+                    count: 0,
+                })
+                .count += 1;
+        }
+        for name in content::hashtags(text) {
+            self.0
+                .entry(format!("#{}", name))
+                .or_insert_with(|| ChannelReference {
+                    channel_id: None,
+                    channel_name: Some(name),
+                    count: 0,
+                })
+                .count += 1;
+        }
+    }
+
+    pub fn into_references(self) -> References {
+        let mut channels: Vec<ChannelReference> = self.0.into_values().collect();
+        channels.sort_by_key(|c| std::cmp::Reverse(c.count));
+        References { channels }
+    }
+}
+
+/// Names the raw references whose channel is among `result.channels`.
+pub fn resolve_names(result: &mut AnalysisResult) {
+    let names: HashMap<&str, &str> = result
+        .channels
+        .iter()
+        .filter_map(|c| Some((c.channel_id.as_deref()?, c.channel_name.as_deref()?)))
+        .collect();
+    if names.is_empty() {
+        return;
+    }
+    let resolve = |authors: &mut [AuthorAnalysis]| {
+        for author in authors {
+            for reference in &mut author.references.channels {
+                if reference.channel_name.is_none() {
+                    let id = reference.channel_id.as_deref().unwrap_or_default();
+                    reference.channel_name = names.get(id).map(|name| name.to_string());
+                }
+            }
+        }
+    };
+    resolve(&mut result.authors_analysis);
+    for section in &mut result.target_sections {
+        resolve(&mut section.authors_analysis);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_references_counted_by_channel() {
+        let mut tally = ReferenceTally::default();
+        tally.record("take it to <#42> or #memes");
+        tally.record("<#42> please");
+        tally.record("#MEMES");
+        let references = tally.into_references();
+        let counts: Vec<(Option<&str>, Option<&str>, usize)> = references
+            .channels
+            .iter()
+            .map(|c| (c.channel_id.as_deref(), c.channel_name.as_deref(), c.count))
+            .collect();
+        assert_eq!(counts, [(None, Some("memes"), 2), (Some("42"), None, 2)]);
+        assert!(ReferenceTally::default().into_references().is_empty());
+    }
+}
@@ -0,0 +1,742 @@
+//! Console rendering of an [`AnalysisResult`].
+
+use crate::gaps::{ChannelGaps, DeletionGaps};
+use crate::lifecycle::Lifecycle;
+use crate::locale::Locale;
+use crate::message::FallbackStats;
+use crate::AnalysisResult;
+use crate::{summary, target};
+use chrono_tz::Tz;
+use std::collections::BTreeMap;
+
+pub fn display_results(result: &AnalysisResult, verbose: bool, locale: &Locale) {
+    let tz = result.timezone.unwrap_or(Tz::UTC);
+    let target = target::label(&result.targets);
+    println!("\nANALYSIS RESULTS");
+    println!("==================");
+    println!("{}\n", summary::paragraph(result, locale));
+    if !result.targets.is_empty() {
+        println!("Target: {}", result.targets.join(", "));
+    }
+    println!(
+        "Total messages to {}: {}",
+        target,
+        locale.count(result.total_messages)
+    );
+    println!(
+        "Unique messages (after deduplication): {}",
+        locale.count(result.messages_to_deleted_users)
+    );
+    println!("Unique authors: {}", locale.count(result.unique_authors));
+
+    if let Some(sampling) = &result.sampling {
+        println!(
+            "Sample: {} of {} messages ({}, seed {})",
+            locale.count(sampling.sampled_messages),
+            locale.count(sampling.population_messages),
+            locale.percent(sampling.effective_fraction),
+            sampling.seed
+        );
+        println!(
+            "Estimated messages to {} in the full input: {} ({} CI {}–{})",
+            target,
+            locale.count(sampling.estimated_total_messages),
+            locale.percent(sampling.confidence_level),
+            locale.count(sampling.estimated_total_messages_low),
+            locale.count(sampling.estimated_total_messages_high)
+        );
+    }
+
+    let first = result
+        .authors_analysis
+        .iter()
+        .filter_map(|a| a.first_message_at)
+        .min();
+    let last = result
+        .authors_analysis
+        .iter()
+        .filter_map(|a| a.last_message_at)
+        .max();
+    if let (Some(first), Some(last)) = (first, last) {
+        println!(
+            "Period: {} – {}",
+            locale.date(&first, tz),
+            locale.date(&last, tz)
+        );
+    }
+
+    if result.reply_only_matches > 0 {
+        println!(
+            "Replies to {} without a mention: {}",
+            target,
+            locale.count(result.reply_only_matches)
+        );
+    }
+
+    if result.omitted_authors > 0 {
+        println!(
+            "Authors below --min-author-messages (not listed): {}",
+            locale.count(result.omitted_authors)
+        );
+    }
+
+    if let Some(events) = &result.events {
+        for event in &events.events {
+            println!(
+                "Event {}: {} messages from {} authors",
+                event.label,
+                locale.count(event.messages),
+                locale.count(event.authors)
+            );
+        }
+        println!(
+            "Outside event windows: {} messages from {} authors",
+            locale.count(events.baseline_messages),
+            locale.count(events.baseline_authors)
+        );
+    }
+
+    if let Some(gaps) = &result.deletion_gaps {
+        print_gaps(gaps, locale);
+    }
+
+    if !result.inactive_users.is_empty() {
+        println!(
+            "Inactive users mentioned: {}",
+            locale.count(result.inactive_users.len())
+        );
+    }
+
+    for section in &result.target_sections {
+        println!(
+            "Target {}: {} messages from {} authors",
+            section.label,
+            locale.count(section.total_messages),
+            locale.count(section.unique_authors)
+        );
+    }
+
+    let lifecycles: Vec<String> = Lifecycle::ALL
+        .iter()
+        .filter_map(|kind| {
+            let n = result
+                .authors_analysis
+                .iter()
+                .filter(|a| a.lifecycle == Some(*kind))
+                .count();
+            (n > 0).then(|| format!("{} {}", locale.count(n), kind.name()))
+        })
+        .collect();
+    if !lifecycles.is_empty() {
+        println!("Author patterns: {}", lifecycles.join(", "));
+    }
+
+    let blank = result.blank_messages;
+    if blank.total() > 0 {
+        println!(
+            "Blank messages excluded from word stats: {} (empty: {}, whitespace: {}, attachment-only: {})",
+            locale.count(blank.total()),
+            locale.count(blank.empty),
+            locale.count(blank.whitespace_only),
+            locale.count(blank.attachment_only)
+        );
+    }
+
+    if let Some(opted_out) = &result.opted_out {
+        println!(
+            "Opted-out authors excluded: {} ({} messages to {})",
+            locale.count(opted_out.authors),
+            locale.count(opted_out.messages_to_deleted_users),
+            target
+        );
+    }
+
+    if !result.warnings.is_empty() {
+        let total: usize = result.warnings.iter().map(|w| w.count).sum();
+        println!(
+            "Data-quality warnings: {} across {} kinds",
+            locale.count(total),
+            result.warnings.len()
+        );
+    }
+
+    if !result.channels.is_empty() {
+        println!("Channels: {}", locale.count(result.channels.len()));
+    }
+
+    if !result.author_conflicts.is_empty() {
+        println!(
+            "Author ID conflicts: {} (IDs seen under different names)",
+            locale.count(result.author_conflicts.len())
+        );
+    }
+
+    if verbose {
+        if let Some(stats) = &result.filter_stats {
+            println!("\nFILTERS");
+            println!("=======");
+            println!("   Messages considered: {}", locale.count(stats.considered));
+            for filter in &stats.filters {
+                println!(
+                    "   Removed by {}: {}",
+                    filter.filter,
+                    locale.count(filter.dropped)
+                );
+            }
+            println!("   Kept: {}", locale.count(stats.kept));
+        }
+
+        println!("\nAUTHORS ANALYSIS");
+        println!("===================");
+
+        let mut sorted_auth = result.authors_analysis.clone();
+        sorted_auth.sort_by(|a, b| {
+            b.total_messages_to_deleted_user
+                .cmp(&a.total_messages_to_deleted_user)
+        });
+
+        for (i, auth) in sorted_auth.iter().enumerate().take(10) {
+            println!(
+                "\n{}. {} ({})",
+                i + 1,
+                auth.author_name,
+                auth.author_nickname
+            );
+            println!("   Author ID: {}", auth.author_id);
+            if !auth.nicknames.is_empty() {
+                println!("   Nicknames: {}", auth.nicknames.join(", "));
+            }
+            println!(
+                "   Messages to {}: {}",
+                target,
+                locale.count(auth.total_messages_to_deleted_user)
+            );
+            println!(
+                "   Unique messages: {}",
+                locale.count(auth.unique_message_count)
+            );
+            if auth.blank_message_count > 0 {
+                println!(
+                    "   Blank messages: {} ({})",
+                    locale.count(auth.blank_message_count),
+                    locale.percent(auth.blank_message_share)
+                );
+            }
+            println!(
+                "   Mentions per message: {} (mention-only: {}, {})",
+                locale.decimal(auth.mentions_per_message, 2),
+                locale.count(auth.mention_only_count),
+                locale.percent(auth.mention_only_share)
+            );
+            if !auth.references.is_empty() {
+                let channels: Vec<String> = auth
+                    .references
+                    .channels
+                    .iter()
+                    .take(5)
+                    .map(|c| {
+                        let channel = match (&c.channel_name, &c.channel_id) {
+                            (Some(name), _) => format!("#{}", name),
+                            (None, Some(id)) => format!("<#{}>", id),
+                            (None, None) => "?".to_string(),
+                        };
+                        format!("{} ({})", channel, locale.count(c.count))
+                    })
+                    .collect();
+                println!("   Pointed to: {}", channels.join(", "));
+            }
+            println!(
+                "   Vocabulary: {} words (type-token ratio {}, average length {})",
+                locale.count(auth.vocabulary_size),
+                locale.decimal(auth.type_token_ratio, 2),
+                locale.decimal(auth.average_word_length, 1)
+            );
+            println!(
+                "   Readability: {} words per sentence, Flesch {}, {} capitals ({} shouted)",
+                locale.decimal(auth.average_sentence_length, 1),
+                locale.decimal(auth.flesch_reading_ease, 1),
+                locale.percent(auth.caps_ratio),
+                locale.count(auth.shouted_message_count)
+            );
+            println!(
+                "   Direct pings: {}, talked about: {}",
+                locale.count(auth.direct_ping_count),
+                locale.count(auth.talked_about_count)
+            );
+            if let (Some(first), Some(last)) = (auth.first_message_at, auth.last_message_at) {
+                println!(
+                    "   Active: {} – {}",
+                    locale.date(&first, tz),
+                    locale.date(&last, tz)
+                );
+            }
+            if let Some(lifecycle) = auth.lifecycle {
+                println!("   Pattern: {}", lifecycle.name());
+            }
+
+            if let Some(radar) = &auth.radar {
+                println!(
+                    "   Profile: volume {}, burstiness {}, negativity {}, directness {}, recency {}",
+                    locale.decimal(radar.volume, 2),
+                    locale.decimal(radar.burstiness, 2),
+                    locale.decimal(radar.negativity, 2),
+                    locale.decimal(radar.directness, 2),
+                    locale.decimal(radar.recency, 2)
+                );
+            }
+
+            if !auth.most_common_words.is_empty() {
+                println!("   Most common words:");
+                for (word, count) in auth.most_common_words.iter().take(5) {
+                    println!("     - {}: {}", word, locale.count(*count));
+                }
+            }
+            if !auth.top_tfidf_words.is_empty() {
+                let words: Vec<&str> = auth
+                    .top_tfidf_words
+                    .iter()
+                    .take(5)
+                    .map(|(word, _)| word.as_str())
+                    .collect();
+                println!("   Distinctive words: {}", words.join(", "));
+            }
+            if !auth.keywords.is_empty() {
+                println!("   Keywords:");
+                for keyword in auth.keywords.iter().take(5) {
+                    println!(
+                        "     - {}: {} ({} vs {} elsewhere)",
+                        keyword.word,
+                        locale.decimal(keyword.score, 2),
+                        locale.count(keyword.count),
+                        locale.count(keyword.reference_count)
+                    );
+                }
+            }
+            if !auth.hapax_legomena.is_empty() {
+                let shown: Vec<&str> = auth
+                    .hapax_legomena
+                    .iter()
+                    .take(10)
+                    .map(String::as_str)
+                    .collect();
+                let more = auth.hapax_legomena.len() - shown.len();
+                let more = if more > 0 {
+                    format!(" (+{} more)", locale.count(more))
+                } else {
+                    String::new()
+                };
+                println!("   Used once: {}{}", shown.join(", "), more);
+            }
+            if !auth.concordance.is_empty() {
+                println!("   In context:");
+                for line in auth.concordance.iter().take(5) {
+                    println!("     … {} [{}] {} …", line.left, line.keyword, line.right);
+                }
+                if auth.concordance.len() > 5 {
+                    println!("     (+{} more)", locale.count(auth.concordance.len() - 5));
+                }
+            }
+            if !auth.language_frequency.is_empty() {
+                println!(
+                    "   Languages: {}",
+                    languages(&auth.language_frequency, locale)
+                );
+            }
+            if let Some((topic, share)) = auth
+                .topic_proportions
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1).then_with(|| b.0.cmp(&a.0)))
+            {
+                println!("   Main topic: {} ({})", topic + 1, locale.percent(*share));
+            }
+            if let Some(sentiment) = &auth.sentiment {
+                println!(
+                    "   Sentiment: {} ({} positive, {} neutral, {} negative)",
+                    locale.decimal(sentiment.mean_compound, 2),
+                    locale.count(sentiment.positive),
+                    locale.count(sentiment.neutral),
+                    locale.count(sentiment.negative)
+                );
+            }
+            if auth.flagged_message_count > 0 {
+                println!(
+                    "   Flagged messages: {}{}",
+                    locale.count(auth.flagged_message_count),
+                    if auth.flagged {
+                        " (author flagged)"
+                    } else {
+                        ""
+                    }
+                );
+                for flagged in &auth.flagged_excerpts {
+                    println!("     - {} [{}]", flagged.excerpt, flagged.terms.join(", "));
+                }
+            }
+            if !auth.most_common_ngrams.is_empty() {
+                println!("   Most common phrases:");
+                for (phrase, count) in auth.most_common_ngrams.iter().take(5) {
+                    println!("     - {}: {}", phrase, locale.count(*count));
+                }
+            }
+            if !auth.collocations.is_empty() {
+                println!("   Characteristic pairs:");
+                for collocation in auth.collocations.iter().take(5) {
+                    println!(
+                        "     - {}: {} ({})",
+                        collocation.words,
+                        locale.decimal(collocation.score, 2),
+                        locale.count(collocation.count)
+                    );
+                }
+            }
+        }
+
+        for section in &result.target_sections {
+            println!("\nTARGET {} ({})", section.label, section.names.join(", "));
+            println!("==================");
+            let mut authors: Vec<_> = section.authors_analysis.iter().collect();
+            authors.sort_by_key(|a| std::cmp::Reverse(a.total_messages_to_deleted_user));
+            for auth in authors.iter().take(5) {
+                println!(
+                    "   {} ({}): {} messages",
+                    auth.author_name,
+                    auth.author_id,
+                    locale.count(auth.total_messages_to_deleted_user)
+                );
+            }
+        }
+
+        if !result.channels.is_empty() {
+            println!("\nCHANNELS");
+            println!("========");
+            for channel in result.channels.iter().take(10) {
+                let label = match (&channel.channel_name, &channel.channel_id) {
+                    (Some(name), Some(id)) => format!("#{} ({})", name, id),
+                    (Some(name), None) => format!("#{}", name),
+                    (None, Some(id)) => id.clone(),
+                    (None, None) => continue,
+                };
+                println!("   {}: {}", label, locale.count(channel.messages));
+            }
+        }
+
+        if let Some(gaps) = result
+            .deletion_gaps
+            .as_ref()
+            .filter(|g| !g.channels.is_empty())
+        {
+            println!("\nDELETION GAPS");
+            println!("=============");
+            for channel in gaps.channels.iter().take(10) {
+                let label = match (&channel.channel_name, &channel.channel_id) {
+                    (Some(name), _) => format!("#{}", name),
+                    (None, Some(id)) => id.clone(),
+                    (None, None) => continue,
+                };
+                println!(
+                    "   {}: {} of {} IDs missing near analyzed messages ({}), {} of {} overall ({}){}",
+                    label,
+                    locale.count(channel.window_missing as usize),
+                    locale.count(channel.window_ids as usize),
+                    locale.percent(channel.window_density),
+                    locale.count(channel.missing as usize),
+                    locale.count((channel.last_id - channel.first_id + 1) as usize),
+                    locale.percent(channel.density),
+                    if channel.elevated { " – elevated" } else { "" }
+                );
+            }
+        }
+
+        if !result.inactive_users.is_empty() {
+            println!("\nINACTIVE USERS");
+            println!("==============");
+            for user in result.inactive_users.iter().take(10) {
+                println!(
+                    "   {} ({}): {} messages, last wrote {}",
+                    user.author_name,
+                    user.author_id,
+                    locale.count(user.messages),
+                    locale.date(&user.last_message_at, tz)
+                );
+            }
+        }
+
+        if !result.word_buckets.is_empty() {
+            println!("\nWORDS OVER TIME");
+            println!("===============");
+            for bucket in &result.word_buckets {
+                let words: Vec<&str> = bucket
+                    .top_words
+                    .iter()
+                    .take(5)
+                    .map(|(word, _)| word.as_str())
+                    .collect();
+                println!(
+                    "   {} ({} messages): {}",
+                    bucket.period,
+                    locale.count(bucket.messages),
+                    words.join(", ")
+                );
+            }
+        }
+
+        if !result.clusters.is_empty() {
+            println!("\nAUTHOR CLUSTERS");
+            println!("===============");
+            for cluster in &result.clusters {
+                let words: Vec<&str> = cluster
+                    .top_words
+                    .iter()
+                    .take(5)
+                    .map(|(word, _)| word.as_str())
+                    .collect();
+                println!(
+                    "   {}. {} authors: {}",
+                    cluster.cluster,
+                    locale.count(cluster.authors.len()),
+                    words.join(", ")
+                );
+            }
+        }
+
+        if !result.similar_authors.is_empty() {
+            println!("\nSIMILAR AUTHORS");
+            println!("===============");
+            let name = |id: &str| {
+                result
+                    .authors_analysis
+                    .iter()
+                    .find(|author| author.author_id == id)
+                    .map_or(id.to_string(), |author| author.author_name.clone())
+            };
+            for pair in result.similar_authors.iter().take(10) {
+                println!(
+                    "   {} / {}: {}",
+                    name(&pair.author_a),
+                    name(&pair.author_b),
+                    locale.decimal(pair.similarity, 3)
+                );
+            }
+        }
+
+        if !result.topics.is_empty() {
+            println!("\nTOPICS");
+            println!("======");
+            for topic in &result.topics {
+                let terms: Vec<&str> = topic
+                    .top_terms
+                    .iter()
+                    .take(8)
+                    .map(|(term, _)| term.as_str())
+                    .collect();
+                println!(
+                    "   {}. ({}) {}",
+                    topic.topic,
+                    locale.percent(topic.share),
+                    terms.join(", ")
+                );
+            }
+        }
+
+        if let Some(trends) = &result.word_trends {
+            println!("\nWORD TRENDS");
+            println!("===========");
+            for trend in &trends.words {
+                let total: usize = trend.counts.iter().sum();
+                let peak = trend
+                    .counts
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &count)| count > 0)
+                    .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(&a.0)));
+                match peak {
+                    Some((i, count)) => println!(
+                        "   {}: {} uses, peak {} ({})",
+                        trend.word,
+                        locale.count(total),
+                        trends.periods[i],
+                        locale.count(*count)
+                    ),
+                    None => println!("   {}: not used", trend.word),
+                }
+            }
+        }
+
+        if !result.author_conflicts.is_empty() {
+            println!("\nAUTHOR ID CONFLICTS");
+            println!("===================");
+            for conflict in &result.author_conflicts {
+                println!("\n   Author ID: {}", conflict.author_id);
+                for sighting in &conflict.names {
+                    println!(
+                        "     - {} ({} messages in {})",
+                        sighting.author_name,
+                        locale.count(sighting.message_count),
+                        sighting.files.join(", ")
+                    );
+                }
+            }
+        }
+
+        if !result.warnings.is_empty() {
+            println!("\nWARNINGS");
+            println!("========");
+            for warning in &result.warnings {
+                println!("   - {}: {}", locale.count(warning.count), warning.message);
+                if !warning.examples.is_empty() {
+                    println!("     e.g. {}", warning.examples.join(", "));
+                }
+            }
+        }
+
+        println!("\nGLOBAL WORD FREQUENCY (TOP 20)");
+        println!("==================================");
+        let mut global_w: Vec<(String, usize)> = result
+            .global_word_frequency
+            .iter()
+            .map(|(k, v)| (k.clone(), *v))
+            .collect();
+
+        global_w.sort_by_key(|w| std::cmp::Reverse(w.1));
+
+        for (i, (word, count)) in global_w.iter().enumerate().take(20) {
+            println!("{}. {}: {}", i + 1, word, locale.count(*count));
+        }
+
+        if let Some(table) = &result.rank_frequency {
+            if let (Some(exponent), Some(r_squared)) = (table.zipf_exponent, table.r_squared) {
+                println!(
+                    "\nZipf exponent: {} (R² {})",
+                    locale.decimal(exponent, 2),
+                    locale.decimal(r_squared, 2)
+                );
+            }
+        }
+
+        if !result.emoji_frequency.is_empty() {
+            let mut emoji: Vec<(&String, &usize)> = result.emoji_frequency.iter().collect();
+            emoji.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
+            let top: Vec<String> = emoji
+                .iter()
+                .take(10)
+                .map(|(emoji, count)| format!("{} {}", emoji, locale.count(**count)))
+                .collect();
+            println!("\nTop emoji: {}", top.join(", "));
+        }
+        if !result.custom_emoji.is_empty() {
+            let top: Vec<String> = result
+                .custom_emoji
+                .iter()
+                .take(10)
+                .map(|emoji| format!(":{}: {}", emoji.name, locale.count(emoji.count)))
+                .collect();
+            println!("Top custom emoji: {}", top.join(", "));
+        }
+        if !result.language_frequency.is_empty() {
+            println!(
+                "Languages: {}",
+                languages(&result.language_frequency, locale)
+            );
+        }
+
+        if let Some(links) = &result.link_stats {
+            println!(
+                "\nLINKS ({} in {} messages)",
+                locale.count(links.total_links),
+                locale.count(links.messages_with_links)
+            );
+            println!("================================");
+            for domain in links.domains.iter().take(10) {
+                println!(
+                    "{}: {} links from {} authors",
+                    domain.domain,
+                    locale.count(domain.links),
+                    locale.count(domain.authors)
+                );
+            }
+        }
+
+        if !result.most_common_ngrams.is_empty() {
+            println!("\nGLOBAL PHRASE FREQUENCY (TOP 10)");
+            println!("================================");
+            for (i, (phrase, count)) in result.most_common_ngrams.iter().enumerate() {
+                println!("{}. {}: {}", i + 1, phrase, locale.count(*count));
+            }
+        }
+
+        if !result.collocations.is_empty() {
+            println!("\nCOLLOCATIONS (TOP 10)");
+            println!("=====================");
+            for (i, collocation) in result.collocations.iter().enumerate() {
+                println!(
+                    "{}. {}: {} ({} times)",
+                    i + 1,
+                    collocation.words,
+                    locale.decimal(collocation.score, 2),
+                    locale.count(collocation.count)
+                );
+            }
+        }
+    }
+
+    println!("\nAnalysis complete!");
+    println!("Use --output <filename.json> to save detailed results");
+    println!("Use --verbose for detailed output");
+}
+
+/// `--detect-languages` counts, most messages first.
+fn languages(counts: &BTreeMap<String, usize>, locale: &Locale) -> String {
+    let mut counts: Vec<(&String, &usize)> = counts.iter().collect();
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
+    counts
+        .iter()
+        .map(|(lang, count)| format!("{} {}", lang, locale.count(**count)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// One line on missing IDs across the sequential channels.
+fn print_gaps(gaps: &DeletionGaps, locale: &Locale) {
+    if gaps.channels.is_empty() {
+        println!(
+            "Deletion gaps: no channel with sequential message IDs ({} skipped)",
+            locale.count(gaps.non_sequential_channels)
+        );
+        return;
+    }
+    let sum = |f: fn(&ChannelGaps) -> u64| gaps.channels.iter().map(f).sum::<u64>();
+    let (window_missing, window_ids) = (sum(|c| c.window_missing), sum(|c| c.window_ids));
+    let (missing, span) = (sum(|c| c.missing), sum(|c| c.last_id - c.first_id + 1));
+    let share = |part: u64, whole: u64| {
+        if whole == 0 {
+            0.0
+        } else {
+            part as f64 / whole as f64
+        }
+    };
+    let elevated = gaps.channels.iter().filter(|c| c.elevated).count();
+    println!(
+        "Deletion gaps: {} IDs missing within {} of analyzed messages ({}), {} overall ({}); {} of {} channels elevated",
+        locale.count(window_missing as usize),
+        locale.count(gaps.window as usize),
+        locale.percent(share(window_missing, window_ids)),
+        locale.count(missing as usize),
+        locale.percent(share(missing, span)),
+        locale.count(elevated),
+        locale.count(gaps.channels.len())
+    );
+}
+
+/// Verbose load-time line listing how many records lacked each field.
+pub fn print_missing_fields(fallbacks: &FallbackStats) {
+    let missing = fallbacks.missing_fields();
+    if missing.is_empty() {
+        return;
+    }
+    let fields: Vec<String> = missing
+        .iter()
+        .map(|(field, count)| format!("{} {}", field, count))
+        .collect();
+    println!("Missing fields (defaults applied): {}", fields.join(", "));
+}
@@ -0,0 +1,59 @@
+//! Vocabulary richness per author: how many different words they use and
+//! how long those words are, which separates an author repeating one
+//! phrase from one writing a lot.
+//!
+//! Measured over the counted words, so after stopwords, `--stem` and
+//! `--lemmatize`. The type-token ratio falls as an author writes more, so
+//! compare it between authors with similar word totals.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Richness {
+    /// Distinct words.
+    pub vocabulary_size: usize,
+    /// Distinct words per counted word, from near 0 (one word over and
+    /// over) to 1 (no word repeated).
+    pub type_token_ratio: f64,
+    /// Characters per counted word.
+    pub average_word_length: f64,
+}
+
+pub fn measure(words: &HashMap<String, usize>) -> Richness {
+    let tokens: usize = words.values().sum();
+    if tokens == 0 {
+        return Richness::default();
+    }
+    let characters: usize = words
+        .iter()
+        .map(|(word, count)| word.chars().count() * count)
+        .sum();
+    Richness {
+        vocabulary_size: words.len(),
+        type_token_ratio: words.len() as f64 / tokens as f64,
+        average_word_length: characters as f64 / tokens as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repetition_lowers_type_token_ratio() {
+        let spam = HashMap::from([("join".to_string(), 50), ("now".to_string(), 50)]);
+        let chat = HashMap::from([
+            ("honestly".to_string(), 2),
+            ("miss".to_string(), 1),
+            ("café".to_string(), 1),
+        ]);
+        let spam = measure(&spam);
+        let chat = measure(&chat);
+        assert_eq!(spam.vocabulary_size, 2);
+        assert_eq!(spam.type_token_ratio, 0.02);
+        assert_eq!(spam.average_word_length, 3.5);
+        assert_eq!(chat.type_token_ratio, 0.75);
+        assert_eq!(chat.average_word_length, 6.0);
+        assert_eq!(measure(&HashMap::new()), Richness::default());
+    }
+}
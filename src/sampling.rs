@@ -0,0 +1,258 @@
+//! Reproducible random sampling of input messages.
+//!
+//! Each message is assigned a pseudo-random 64-bit key derived from its ID
+//! and the seed, so the same seed always selects the same messages no matter
+//! the file order or chunking. `--sample` keeps keys below a threshold;
+//! `--sample-n` keeps the N smallest keys.
+
+use crate::message::Message;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BinaryHeap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleSpec {
+    Fraction(f64),
+    Count(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sampler {
+    pub spec: SampleSpec,
+    pub seed: u64,
+}
+
+impl Sampler {
+    pub fn key(&self, msg: &Message) -> u64 {
+        sample_key(self.seed, &msg.message_id)
+    }
+
+    /// Whether a message belongs to a fraction sample. Always true for
+    /// count samples, which are decided once all keys are known.
+    pub fn keep(&self, msg: &Message) -> bool {
+        match self.spec {
+            SampleSpec::Fraction(fraction) => {
+                fraction >= 1.0 || self.key(msg) < (fraction * u64::MAX as f64) as u64
+            }
+            SampleSpec::Count(_) => true,
+        }
+    }
+}
+
+/// Parses a `--sample` fraction, which must lie in (0, 1].
+pub fn parse_fraction(value: &str) -> Result<f64, String> {
+    let fraction: f64 = value
+        .parse()
+        .map_err(|_| format!("`{}` is not a number", value))?;
+    if fraction > 0.0 && fraction <= 1.0 {
+        Ok(fraction)
+    } else {
+        Err(format!("sample fraction must be in (0, 1], got {}", value))
+    }
+}
+
+/// Stable key for a message: FNV-1a over the ID, mixed with the seed
+/// through SplitMix64. Unlike `DefaultHasher` this is fixed across Rust
+/// releases, so samples stay reproducible.
+pub fn sample_key(seed: u64, message_id: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in message_id.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    let mut z = hash ^ seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The `k` smallest keys seen so far.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BottomK {
+    k: usize,
+    keys: BinaryHeap<u64>,
+}
+
+impl BottomK {
+    pub fn new(k: usize) -> Self {
+        BottomK {
+            k,
+            keys: BinaryHeap::with_capacity(k.min(1 << 20)),
+        }
+    }
+
+    pub fn push(&mut self, key: u64) {
+        if self.keys.len() < self.k {
+            self.keys.push(key);
+        } else if self.keys.peek().is_some_and(|&max| key < max) {
+            self.keys.pop();
+            self.keys.push(key);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Largest key that is part of the sample.
+    pub fn threshold(&self) -> Option<u64> {
+        self.keys.peek().copied()
+    }
+}
+
+/// Coverage of the interval around `estimated_total_messages`.
+pub const CONFIDENCE_LEVEL: f64 = 0.95;
+/// Standard normal quantile for [`CONFIDENCE_LEVEL`].
+const Z: f64 = 1.959_964;
+
+/// How the analyzed messages were sampled, with scaled-up estimates.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct SamplingInfo {
+    pub seed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requested_fraction: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requested_count: Option<usize>,
+    pub population_messages: usize,
+    pub sampled_messages: usize,
+    pub effective_fraction: f64,
+    /// Only additive counts are scaled; distinct counts such as unique
+    /// messages or authors do not grow linearly with the sample.
+    pub estimated_total_messages: usize,
+    /// Bounds of the [`CONFIDENCE_LEVEL`] interval around the estimate.
+    pub confidence_level: f64,
+    pub estimated_total_messages_low: usize,
+    pub estimated_total_messages_high: usize,
+}
+
+impl SamplingInfo {
+    pub fn new(sampler: &Sampler, population: usize, sampled: usize) -> Self {
+        let effective_fraction = if population == 0 {
+            0.0
+        } else {
+            sampled as f64 / population as f64
+        };
+        let (requested_fraction, requested_count) = match sampler.spec {
+            SampleSpec::Fraction(f) => (Some(f), None),
+            SampleSpec::Count(n) => (None, Some(n)),
+        };
+        SamplingInfo {
+            seed: sampler.seed,
+            requested_fraction,
+            requested_count,
+            population_messages: population,
+            sampled_messages: sampled,
+            effective_fraction,
+            estimated_total_messages: 0,
+            confidence_level: CONFIDENCE_LEVEL,
+            estimated_total_messages_low: 0,
+            estimated_total_messages_high: 0,
+        }
+    }
+
+    /// Extrapolates an observed count to the full population.
+    pub fn scale(&self, observed: usize) -> usize {
+        if self.effective_fraction == 0.0 {
+            0
+        } else {
+            (observed as f64 / self.effective_fraction).round() as usize
+        }
+    }
+
+    /// Interval for the population count behind `observed` sampled
+    /// matches: a Wilson score interval on the matching share, narrowed by
+    /// the finite population correction and clamped to what the sample
+    /// rules out. A full sample gives the exact count.
+    pub fn interval(&self, observed: usize) -> (usize, usize) {
+        let (n, population) = (self.sampled_messages, self.population_messages);
+        if n == 0 {
+            return (0, population);
+        }
+        if n >= population {
+            return (observed, observed);
+        }
+        let p = observed as f64 / n as f64;
+        let fpc = (population - n) as f64 / (population - 1) as f64;
+        let n_eff = n as f64 / fpc;
+        let z2 = Z * Z;
+        let denom = 1.0 + z2 / n_eff;
+        let center = (p + z2 / (2.0 * n_eff)) / denom;
+        let half = Z * (p * (1.0 - p) / n_eff + z2 / (4.0 * n_eff * n_eff)).sqrt() / denom;
+        let low = ((center - half).max(0.0) * population as f64).floor() as usize;
+        let high = ((center + half).min(1.0) * population as f64).ceil() as usize;
+        (low.max(observed), high.min(population - (n - observed)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_key_is_stable_and_seeded() {
+        assert_eq!(sample_key(7, "123"), sample_key(7, "123"));
+        assert_ne!(sample_key(7, "123"), sample_key(8, "123"));
+        assert_ne!(sample_key(7, "123"), sample_key(7, "124"));
+    }
+
+    #[test]
+    fn test_fraction_sample_size_is_close() {
+        let threshold = (0.1 * u64::MAX as f64) as u64;
+        let kept = (0..10_000)
+            .filter(|i| sample_key(42, &i.to_string()) < threshold)
+            .count();
+        assert!((800..1200).contains(&kept), "kept {}", kept);
+    }
+
+    #[test]
+    fn test_bottom_k_keeps_smallest() {
+        let mut bottom = BottomK::new(3);
+        for key in [9, 1, 8, 2, 7, 3] {
+            bottom.push(key);
+        }
+        assert_eq!(bottom.len(), 3);
+        assert_eq!(bottom.threshold(), Some(3));
+    }
+
+    #[test]
+    fn test_parse_fraction() {
+        assert_eq!(parse_fraction("0.05"), Ok(0.05));
+        assert_eq!(parse_fraction("1"), Ok(1.0));
+        assert!(parse_fraction("0").is_err());
+        assert!(parse_fraction("1.5").is_err());
+        assert!(parse_fraction("abc").is_err());
+    }
+
+    #[test]
+    fn test_scale() {
+        let sampler = Sampler {
+            spec: SampleSpec::Fraction(0.5),
+            seed: 0,
+        };
+        let info = SamplingInfo::new(&sampler, 1000, 250);
+        assert_eq!(info.effective_fraction, 0.25);
+        assert_eq!(info.scale(10), 40);
+    }
+
+    #[test]
+    fn test_interval() {
+        let sampler = Sampler {
+            spec: SampleSpec::Fraction(0.1),
+            seed: 0,
+        };
+        let info = SamplingInfo::new(&sampler, 10_000, 1_000);
+        let (low, high) = info.interval(100);
+        assert!(low < info.scale(100) && info.scale(100) < high);
+        assert!(
+            (800..1000).contains(&low) && (1000..1200).contains(&high),
+            "{low}..{high}"
+        );
+
+        let (low, high) = info.interval(0);
+        assert_eq!(low, 0);
+        assert!(high > 0 && high < 100, "{high}");
+
+        let full = SamplingInfo::new(&sampler, 500, 500);
+        assert_eq!(full.interval(42), (42, 42));
+    }
+}
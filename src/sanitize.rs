@@ -0,0 +1,348 @@
+//! Masking of profanity and slurs in everything a report emits.
+//!
+//! A single [`Sanitizer`] is applied to the finished result before it reaches
+//! any sink (console, JSON output, `--watch` updates), so every report masks
+//! the same terms the same way: `fuck` becomes `f***`.
+
+use crate::similarity::Matrix;
+use crate::{AnalysisResult, AuthorAnalysis};
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::fs;
+
+/// Built-in terms for `--mask-profanity`. A trailing `*` matches any suffix.
+pub const DEFAULT_TERMS: &[&str] = &[
+    "asshole*",
+    "bastard*",
+    "bitch*",
+    "bullshit",
+    "cunt*",
+    "dick",
+    "dickhead*",
+    "fuck*",
+    "motherfuck*",
+    "shit*",
+    "wank*",
+];
+
+/// A term list: exact words, plus prefixes for terms ending in `*`.
+#[derive(Debug, Default)]
+pub struct Terms {
+    exact: Vec<String>,
+    prefixes: Vec<String>,
+}
+
+impl Terms {
+    pub fn add(&mut self, term: &str) {
+        let term = term.trim().to_lowercase();
+        match term.strip_suffix('*') {
+            Some(prefix) if !prefix.is_empty() => self.prefixes.push(prefix.to_string()),
+            Some(_) => {}
+            None if !term.is_empty() => self.exact.push(term),
+            None => {}
+        }
+    }
+
+    /// Adds the terms in a file with one term per line (blank lines and `#`
+    /// comments ignored).
+    pub fn add_file(&mut self, path: &str, what: &str) -> anyhow::Result<()> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {} {}", what, path))?;
+        for line in text.lines() {
+            self.add(line.split('#').next().unwrap_or(""));
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.prefixes.is_empty()
+    }
+
+    pub fn matches(&self, word: &str) -> bool {
+        let word = word.to_lowercase();
+        self.exact.contains(&word) || self.prefixes.iter().any(|prefix| word.starts_with(prefix))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Sanitizer {
+    terms: Terms,
+}
+
+impl Sanitizer {
+    /// Builds the sanitizer from the built-in list and/or a term file.
+    pub fn new(builtin: bool, path: Option<&str>) -> anyhow::Result<Self> {
+        let mut sanitizer = Sanitizer::default();
+        if builtin {
+            for term in DEFAULT_TERMS {
+                sanitizer.add(term);
+            }
+        }
+        if let Some(path) = path {
+            sanitizer.terms.add_file(path, "mask list")?;
+        }
+        Ok(sanitizer)
+    }
+
+    fn add(&mut self, term: &str) {
+        self.terms.add(term);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    fn matches(&self, word: &str) -> bool {
+        self.terms.matches(word)
+    }
+
+    /// Masks a single token, keeping its first character.
+    pub fn mask_word(&self, word: &str) -> String {
+        if !self.matches(word) {
+            return word.to_string();
+        }
+        let mut chars = word.chars();
+        let first = chars.next().map(String::from).unwrap_or_default();
+        first + &"*".repeat(chars.count())
+    }
+
+    /// Masks every matching word in free text, ignoring surrounding
+    /// punctuation and keeping everything else intact.
+    pub fn mask_text(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut word = String::new();
+        for c in text.chars() {
+            if c.is_alphanumeric() {
+                word.push(c);
+            } else {
+                out.push_str(&self.mask_word(&word));
+                word.clear();
+                out.push(c);
+            }
+        }
+        out.push_str(&self.mask_word(&word));
+        out
+    }
+
+    fn mask_counts(&self, counts: &mut BTreeMap<String, usize>) {
+        if !counts.keys().any(|word| self.matches(word)) {
+            return;
+        }
+        // Different words can mask to the same string; their counts merge.
+        let mut masked = BTreeMap::new();
+        for (word, count) in std::mem::take(counts) {
+            *masked.entry(self.mask_word(&word)).or_insert(0) += count;
+        }
+        *counts = masked;
+    }
+
+    fn mask_authors(&self, authors: &mut [AuthorAnalysis]) {
+        for author in authors {
+            author.author_name = self.mask_text(&author.author_name);
+            author.author_nickname = self.mask_text(&author.author_nickname);
+            for nickname in &mut author.nicknames {
+                *nickname = self.mask_text(nickname);
+            }
+            self.mask_counts(&mut author.word_frequency);
+            self.mask_ranked(&mut author.most_common_words);
+            for (word, _) in &mut author.top_tfidf_words {
+                *word = self.mask_word(word);
+            }
+            for keyword in &mut author.keywords {
+                keyword.word = self.mask_word(&keyword.word);
+            }
+            self.mask_list(&mut author.hapax_legomena);
+            for line in &mut author.concordance {
+                line.left = self.mask_text(&line.left);
+                line.keyword = self.mask_text(&line.keyword);
+                line.right = self.mask_text(&line.right);
+            }
+            for flagged in &mut author.flagged_excerpts {
+                flagged.excerpt = self.mask_text(&flagged.excerpt);
+                for term in &mut flagged.terms {
+                    *term = self.mask_word(term);
+                }
+            }
+            self.mask_phrases(&mut author.ngram_frequency);
+            self.mask_ranked_phrases(&mut author.most_common_ngrams);
+            for collocation in &mut author.collocations {
+                collocation.words = self.mask_text(&collocation.words);
+            }
+            for bucket in &mut author.word_buckets {
+                self.mask_ranked(&mut bucket.top_words);
+            }
+        }
+    }
+
+    /// [`Sanitizer::mask_counts`] for `--ngrams` keys, masking each word.
+    fn mask_phrases(&self, counts: &mut BTreeMap<String, usize>) {
+        let mut masked = BTreeMap::new();
+        for (phrase, count) in std::mem::take(counts) {
+            *masked.entry(self.mask_text(&phrase)).or_insert(0) += count;
+        }
+        *counts = masked;
+    }
+
+    /// Masks every word, merging words that mask alike.
+    fn mask_list(&self, words: &mut Vec<String>) {
+        for word in words.iter_mut() {
+            *word = self.mask_word(word);
+        }
+        words.sort();
+        words.dedup();
+    }
+
+    fn mask_ranked_phrases(&self, phrases: &mut Vec<(String, usize)>) {
+        for (phrase, _) in phrases.iter_mut() {
+            *phrase = self.mask_text(phrase);
+        }
+        self.mask_ranked(phrases);
+    }
+
+    /// Masks a ranked word list, merging collisions and re-ranking.
+    fn mask_ranked(&self, words: &mut Vec<(String, usize)>) {
+        let mut merged: BTreeMap<String, usize> = BTreeMap::new();
+        for (word, count) in words.drain(..) {
+            *merged.entry(self.mask_word(&word)).or_insert(0) += count;
+        }
+        *words = merged.into_iter().collect();
+        words.sort_by_key(|w| std::cmp::Reverse(w.1));
+    }
+
+    /// Masks the author names labelling a `--similarity` matrix.
+    pub fn mask_similarity(&self, matrix: &mut Matrix) {
+        if !self.is_empty() {
+            matrix.map_names(|name| self.mask_text(name));
+        }
+    }
+
+    /// Masks all words and names in a finished result.
+    pub fn apply(&self, result: &mut AnalysisResult) {
+        if self.is_empty() {
+            return;
+        }
+        self.mask_counts(&mut result.global_word_frequency);
+        self.mask_phrases(&mut result.global_ngram_frequency);
+        self.mask_ranked_phrases(&mut result.most_common_ngrams);
+        for collocation in &mut result.collocations {
+            collocation.words = self.mask_text(&collocation.words);
+        }
+        self.mask_list(&mut result.hapax_legomena);
+        if let Some(table) = &mut result.rank_frequency {
+            for ranked in &mut table.ranks {
+                ranked.word = self.mask_word(&ranked.word);
+            }
+        }
+        self.mask_authors(&mut result.authors_analysis);
+        for bucket in &mut result.word_buckets {
+            self.mask_ranked(&mut bucket.top_words);
+        }
+        for cluster in &mut result.clusters {
+            for (word, _) in &mut cluster.top_words {
+                *word = self.mask_word(word);
+            }
+        }
+        for topic in &mut result.topics {
+            for (term, _) in &mut topic.top_terms {
+                *term = self.mask_word(term);
+            }
+        }
+        if let Some(trends) = &mut result.word_trends {
+            for trend in &mut trends.words {
+                trend.word = self.mask_word(&trend.word);
+            }
+        }
+        for section in &mut result.target_sections {
+            self.mask_authors(&mut section.authors_analysis);
+        }
+        for channel in &mut result.channels {
+            if let Some(name) = &channel.channel_name {
+                channel.channel_name = Some(self.mask_text(name));
+            }
+        }
+        if let Some(gaps) = &mut result.deletion_gaps {
+            for channel in &mut gaps.channels {
+                if let Some(name) = &channel.channel_name {
+                    channel.channel_name = Some(self.mask_text(name));
+                }
+            }
+        }
+        // Stems can mask to the same string; their forms merge.
+        let mut variants: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (stem, forms) in std::mem::take(&mut result.word_variants) {
+            let merged = variants.entry(self.mask_word(&stem)).or_default();
+            for form in forms {
+                let form = self.mask_word(&form);
+                if !merged.contains(&form) {
+                    merged.push(form);
+                }
+            }
+        }
+        result.word_variants = variants;
+        if !self.is_empty() {
+            result.cooccurrence.map_words(|word| self.mask_word(word));
+        }
+        for user in &mut result.inactive_users {
+            user.author_name = self.mask_text(&user.author_name);
+        }
+        for entry in &mut result.timeline {
+            entry.author_name = self.mask_text(&entry.author_name);
+            entry.snippet = self.mask_text(&entry.snippet);
+        }
+        for entry in &mut result.explanations {
+            entry.author_name = self.mask_text(&entry.author_name);
+        }
+        for row in &mut result.pivot.rows {
+            row.author_name = self.mask_text(&row.author_name);
+        }
+        for channel in &mut result.pivot.channels {
+            *channel = self.mask_text(channel);
+        }
+        for conflict in &mut result.author_conflicts {
+            for sighting in &mut conflict.names {
+                sighting.author_name = self.mask_text(&sighting.author_name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sanitizer(terms: &[&str]) -> Sanitizer {
+        let mut sanitizer = Sanitizer::default();
+        for term in terms {
+            sanitizer.add(term);
+        }
+        sanitizer
+    }
+
+    #[test]
+    fn test_mask_word_exact_and_prefix() {
+        let s = sanitizer(&["fuck*", "dick"]);
+        assert_eq!(s.mask_word("fuck"), "f***");
+        assert_eq!(s.mask_word("Fucking"), "F******");
+        assert_eq!(s.mask_word("dick"), "d***");
+        assert_eq!(s.mask_word("dickens"), "dickens");
+    }
+
+    #[test]
+    fn test_mask_text_keeps_punctuation() {
+        let s = sanitizer(&["shit*"]);
+        assert_eq!(s.mask_text("oh shit, really?"), "oh s***, really?");
+    }
+
+    #[test]
+    fn test_mask_counts_merges_collisions() {
+        let s = sanitizer(&["fuck*"]);
+        let mut counts = BTreeMap::from([
+            ("fucks".to_string(), 2),
+            ("fucky".to_string(), 1),
+            ("hello".to_string(), 4),
+        ]);
+        s.mask_counts(&mut counts);
+        assert_eq!(counts["f****"], 3);
+        assert_eq!(counts["hello"], 4);
+    }
+}
@@ -0,0 +1,68 @@
+//! `--emit-output-schema`: a JSON Schema of the `--output` format, generated
+//! from the result types, for dashboards that validate or generate code
+//! against it.
+//!
+//! Fields left out of a result when empty or unset (`skip_serializing_if`)
+//! are optional in the schema; everything else is required.
+
+use crate::paths;
+use crate::AnalysisResult;
+use anyhow::Context;
+use schemars::generate::SchemaSettings;
+use schemars::Schema;
+use std::fs;
+use std::path::Path;
+
+/// Describes the serialized form, so fields that may be left out are not
+/// required.
+pub fn output_schema() -> Schema {
+    SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator()
+        .into_root_schema_for::<AnalysisResult>()
+}
+
+/// Prints the schema, or writes it to `output`.
+pub fn emit(output: Option<&Path>) -> anyhow::Result<()> {
+    let json = serde_json::to_string_pretty(&output_schema())?;
+    match output {
+        Some(path) => {
+            fs::write(paths::extended(path), json + "\n")
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            println!("Output schema saved to: {}", path.display());
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::demo;
+
+    #[test]
+    fn test_schema_covers_demo_output() {
+        let schema = serde_json::to_value(output_schema()).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|name| name.as_str())
+            .collect();
+        assert!(required.contains(&"total_messages"));
+        assert!(!required.contains(&"inactive_users"));
+        assert!(!properties.contains_key("timeline"));
+
+        let (_, result) = demo::analyze(false).unwrap();
+        let output = serde_json::to_value(&result).unwrap();
+        for key in output.as_object().unwrap().keys() {
+            assert!(properties.contains_key(key), "{} is not in the schema", key);
+        }
+        let author = &schema["$defs"]["AuthorAnalysis"]["properties"];
+        for key in output["authors_analysis"][0].as_object().unwrap().keys() {
+            assert!(author.get(key).is_some(), "{} is not in the schema", key);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! `--sentiment`: lexicon-based sentiment per message and per author, in
+//! the style of VADER, to gauge how hostile (or warm) the messages about
+//! the target were.
+//!
+//! Each word in the bundled English lexicon carries a valence from -4 to 4.
+//! A valence is strengthened by a preceding booster ("very") or by being
+//! written in capitals within a mixed-case message, flipped and damped by a
+//! negation in the three words before it ("not", "don't"), and weighted
+//! down before and up after a "but". Exclamation marks add emphasis. The
+//! sum is normalized to a compound score from -1 to 1; messages at or
+//! beyond ±0.05 count as positive or negative.
+
+use crate::message::Message;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Most negative messages listed per author.
+const LIMIT: usize = 5;
+
+/// Compound scores at or beyond this count as positive or negative.
+const THRESHOLD: f64 = 0.05;
+
+static LEXICON: LazyLock<HashMap<&'static str, f64>> = LazyLock::new(|| {
+    include_str!("../data/sentiment/en.tsv")
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (word, valence) = line.split_once('\t')?;
+            Some((word, valence.trim().parse().ok()?))
+        })
+        .collect()
+});
+
+const NEGATIONS: &[&str] = &[
+    "ain't", "aint", "cannot", "cant", "dont", "isnt", "neither", "never", "no", "nobody", "none",
+    "nor", "not", "nothing", "nowhere", "wasnt", "without", "wont",
+];
+
+const BOOSTERS: &[&str] = &[
+    "absolutely",
+    "completely",
+    "extremely",
+    "fucking",
+    "hella",
+    "incredibly",
+    "really",
+    "so",
+    "super",
+    "totally",
+    "very",
+];
+
+const BOOST: f64 = 0.293;
+const CAPS_BOOST: f64 = 0.733;
+const NEGATION_SCALE: f64 = -0.74;
+const EXCLAMATION_BOOST: f64 = 0.292;
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct MessageSentiment {
+    pub message_id: String,
+    /// From -1 (most negative) to 1.
+    pub compound: f64,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Sentiment {
+    /// Mean compound score over the author's unique messages.
+    pub mean_compound: f64,
+    pub positive: usize,
+    pub neutral: usize,
+    pub negative: usize,
+    /// The author's most negative messages, most negative first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub most_negative: Vec<MessageSentiment>,
+}
+
+/// Running sentiment totals for one author.
+#[derive(Debug, Clone, Default)]
+pub struct SentimentTally {
+    sum: f64,
+    positive: usize,
+    neutral: usize,
+    negative: usize,
+    most_negative: Vec<MessageSentiment>,
+}
+
+impl SentimentTally {
+    pub fn record(&mut self, msg: &Message) {
+        let compound = score(&msg.content);
+        self.sum += compound;
+        if compound >= THRESHOLD {
+            self.positive += 1;
+        } else if compound <= -THRESHOLD {
+            self.negative += 1;
+            self.most_negative.push(MessageSentiment {
+                message_id: msg.message_id.clone(),
+                compound,
+            });
+            self.most_negative
+                .sort_by(|a, b| a.compound.total_cmp(&b.compound));
+            self.most_negative.truncate(LIMIT);
+        } else {
+            self.neutral += 1;
+        }
+    }
+
+    /// `None` when no message was scored.
+    pub fn into_sentiment(self) -> Option<Sentiment> {
+        let scored = self.positive + self.neutral + self.negative;
+        (scored > 0).then(|| Sentiment {
+            mean_compound: self.sum / scored as f64,
+            positive: self.positive,
+            neutral: self.neutral,
+            negative: self.negative,
+            most_negative: self.most_negative,
+        })
+    }
+}
+
+/// The compound score of `text`, from -1 to 1.
+pub fn score(text: &str) -> f64 {
+    let raw: Vec<&str> = text.split_whitespace().collect();
+    let words: Vec<String> = raw
+        .iter()
+        .map(|chunk| {
+            chunk
+                .trim_matches(|c: char| !c.is_alphanumeric() && c != '\'' && c != '’')
+                .replace('’', "'")
+                .to_lowercase()
+        })
+        .collect();
+    let mixed_case = text.chars().any(char::is_lowercase);
+    let but = words.iter().position(|word| word == "but");
+
+    let mut sum = 0.0;
+    for (i, word) in words.iter().enumerate() {
+        let Some(&valence) = LEXICON.get(raw[i]).or_else(|| LEXICON.get(word.as_str())) else {
+            continue;
+        };
+        // Boosters that are also in the lexicon ("super") only boost the
+        // word after them, unless they end the message.
+        if BOOSTERS.contains(&word.as_str()) && i + 1 < words.len() {
+            continue;
+        }
+        let sign = valence.signum();
+        let mut valence = valence;
+        if mixed_case && is_shouted(raw[i]) {
+            valence += sign * CAPS_BOOST;
+        }
+        for (distance, before) in words[..i].iter().rev().take(3).enumerate() {
+            if BOOSTERS.contains(&before.as_str()) {
+                valence += sign * BOOST * (1.0 - 0.05 * distance as f64);
+            }
+        }
+        if words[..i]
+            .iter()
+            .rev()
+            .take(3)
+            .any(|before| is_negation(before))
+        {
+            valence *= NEGATION_SCALE;
+        }
+        match but {
+            Some(at) if i < at => valence *= 0.5,
+            Some(at) if i > at => valence *= 1.5,
+            _ => {}
+        }
+        sum += valence;
+    }
+    if sum != 0.0 {
+        let exclamations = text.matches('!').count().min(4) as f64;
+        sum += sum.signum() * exclamations * EXCLAMATION_BOOST;
+    }
+    sum / (sum * sum + 15.0).sqrt()
+}
+
+fn is_negation(word: &str) -> bool {
+    NEGATIONS.contains(&word) || word.ends_with("n't")
+}
+
+fn is_shouted(word: &str) -> bool {
+    word.chars().filter(|c| c.is_alphabetic()).count() > 1 && !word.chars().any(char::is_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negation_boosters_and_tally() {
+        assert_eq!(score("see you at the meeting"), 0.0);
+        let good = score("that was good");
+        assert!(good > THRESHOLD);
+        assert!(score("that was very good") > good);
+        assert!(score("that was GOOD") > good);
+        assert!(score("that was good!!") > good);
+        assert!(score("that wasn't good") < 0.0);
+        assert!(score("good but awful") < 0.0);
+        assert!(score("you are the WORST, I hate you") < -0.5);
+        assert!(score(":)") > 0.0);
+
+        let mut tally = SentimentTally::default();
+        for (id, content) in [
+            ("1", "love it"),
+            ("2", "meh"),
+            ("3", "bad"),
+            ("4", "so stupid"),
+        ] {
+            tally.record(&Message {
+                message_id: id.to_string(),
+                content: content.to_string(),
+                ..Message::default()
+            });
+        }
+        let sentiment = tally.into_sentiment().unwrap();
+        assert_eq!(
+            (sentiment.positive, sentiment.neutral, sentiment.negative),
+            (1, 1, 2)
+        );
+        assert_eq!(sentiment.most_negative[0].message_id, "4");
+        assert!(SentimentTally::default().into_sentiment().is_none());
+    }
+}
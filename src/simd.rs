@@ -0,0 +1,139 @@
+//! Byte-level primitives for the tokenizer's ASCII fast path.
+//!
+//! On x86_64 with SSE2 (part of the baseline, so no runtime detection is
+//! needed) text is processed 16 bytes at a time; other targets use the
+//! scalar versions, which define the expected behavior.
+
+/// Set in [`lower_and_classify`] output for `[0-9a-z]` after lowercasing.
+pub const ALNUM: u8 = 1;
+/// Set for the ASCII bytes `char::is_whitespace` accepts: `\t \n \x0B \x0C \r` and space.
+pub const SPACE: u8 = 2;
+
+pub fn is_ascii(bytes: &[u8]) -> bool {
+    // SAFETY: the cfg guarantees SSE2 is enabled for the whole build.
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    return unsafe { sse2::is_ascii(bytes) };
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "sse2")))]
+    return bytes.is_ascii();
+}
+
+/// Lowercases ASCII `bytes` into `lowered` and writes each byte's class
+/// flags into `classes`. Both buffers are cleared first.
+pub fn lower_and_classify(bytes: &[u8], lowered: &mut Vec<u8>, classes: &mut Vec<u8>) {
+    lowered.clear();
+    classes.clear();
+    lowered.resize(bytes.len(), 0);
+    classes.resize(bytes.len(), 0);
+    // SAFETY: the cfg guarantees SSE2 is enabled for the whole build.
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    let done = unsafe { sse2::lower_and_classify(bytes, lowered, classes) };
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "sse2")))]
+    let done = 0;
+    scalar_lower_and_classify(&bytes[done..], &mut lowered[done..], &mut classes[done..]);
+}
+
+fn scalar_lower_and_classify(bytes: &[u8], lowered: &mut [u8], classes: &mut [u8]) {
+    for ((&b, low), class) in bytes.iter().zip(lowered).zip(classes) {
+        *low = b.to_ascii_lowercase();
+        *class = if b.is_ascii_alphanumeric() {
+            ALNUM
+        } else if b == b' ' || (b'\t'..=b'\r').contains(&b) {
+            SPACE
+        } else {
+            0
+        };
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+mod sse2 {
+    use super::{ALNUM, SPACE};
+    use std::arch::x86_64::*;
+
+    const LANES: usize = 16;
+
+    #[target_feature(enable = "sse2")]
+    pub fn is_ascii(bytes: &[u8]) -> bool {
+        let mut chunks = bytes.chunks_exact(LANES);
+        for chunk in &mut chunks {
+            // SAFETY: `chunk` is exactly 16 readable bytes and the load is unaligned.
+            let v = unsafe { _mm_loadu_si128(chunk.as_ptr() as *const __m128i) };
+            if _mm_movemask_epi8(v) != 0 {
+                return false;
+            }
+        }
+        chunks.remainder().is_ascii()
+    }
+
+    /// Inclusive byte range test. Only valid for ASCII input, since the
+    /// comparisons are signed.
+    #[target_feature(enable = "sse2")]
+    fn in_range(v: __m128i, lo: u8, hi: u8) -> __m128i {
+        let above = _mm_cmpgt_epi8(v, _mm_set1_epi8(lo as i8 - 1));
+        let below = _mm_cmplt_epi8(v, _mm_set1_epi8(hi as i8 + 1));
+        _mm_and_si128(above, below)
+    }
+
+    /// Handles all complete 16-byte blocks and returns how many bytes were
+    /// processed; the caller finishes the remainder.
+    #[target_feature(enable = "sse2")]
+    pub fn lower_and_classify(bytes: &[u8], lowered: &mut [u8], classes: &mut [u8]) -> usize {
+        let blocks = bytes.len() / LANES;
+        for block in 0..blocks {
+            let at = block * LANES;
+            // SAFETY: `at + 16 <= len` for all three slices, which have the
+            // same length; loads and stores are unaligned.
+            unsafe {
+                let v = _mm_loadu_si128(bytes.as_ptr().add(at) as *const __m128i);
+                let upper = in_range(v, b'A', b'Z');
+                let low = _mm_add_epi8(v, _mm_and_si128(upper, _mm_set1_epi8(0x20)));
+                let alnum = _mm_or_si128(in_range(low, b'a', b'z'), in_range(low, b'0', b'9'));
+                let space = _mm_or_si128(
+                    _mm_cmpeq_epi8(v, _mm_set1_epi8(b' ' as i8)),
+                    in_range(v, b'\t', b'\r'),
+                );
+                let class = _mm_or_si128(
+                    _mm_and_si128(alnum, _mm_set1_epi8(ALNUM as i8)),
+                    _mm_and_si128(space, _mm_set1_epi8(SPACE as i8)),
+                );
+                _mm_storeu_si128(lowered.as_mut_ptr().add(at) as *mut __m128i, low);
+                _mm_storeu_si128(classes.as_mut_ptr().add(at) as *mut __m128i, class);
+            }
+        }
+        blocks * LANES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lower_and_classify_matches_scalar() {
+        let text: Vec<u8> = (0u8..128).cycle().take(16 * 9 + 5).collect();
+        let (mut lowered, mut classes) = (Vec::new(), Vec::new());
+        lower_and_classify(&text, &mut lowered, &mut classes);
+
+        let mut expected_lowered = vec![0; text.len()];
+        let mut expected_classes = vec![0; text.len()];
+        scalar_lower_and_classify(&text, &mut expected_lowered, &mut expected_classes);
+        assert_eq!(lowered, expected_lowered);
+        assert_eq!(classes, expected_classes);
+        for &b in &text {
+            let expected = (b as char).is_whitespace();
+            assert_eq!(
+                expected_classes[b as usize] == SPACE,
+                expected,
+                "byte {}",
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_ascii() {
+        assert!(is_ascii(b"plain ascii text that is longer than one block"));
+        assert!(!is_ascii("plain ascii text, then é".as_bytes()));
+        assert!(is_ascii(b""));
+    }
+}
@@ -0,0 +1,199 @@
+//! `--similarity`: cosine similarity between every two authors' word
+//! frequencies, so sockpuppets and coordinated accounts writing near
+//! identical things about the target stand out.
+//!
+//! Compared over the counted words (after stopwords, `--stem` and
+//! `--lemmatize`): 1 means the same words in the same proportions, 0 no
+//! word in common. The full authors × authors matrix goes to the given
+//! file, as CSV or, for a `.json` path, JSON; the most similar pairs are
+//! also listed in the result's `similar_authors`.
+
+use crate::paths;
+use crate::timeline::csv_field;
+use crate::AuthorAnalysis;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Pairs listed in `similar_authors`.
+const LIMIT: usize = 20;
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct SimilarAuthors {
+    pub author_a: String,
+    pub author_b: String,
+    pub similarity: f64,
+}
+
+/// Pairwise similarities, in the order of the result's authors.
+#[derive(Debug, Clone, Default)]
+pub struct Matrix {
+    /// `(author_id, author_name)`.
+    authors: Vec<(String, String)>,
+    rows: Vec<Vec<f64>>,
+}
+
+impl Matrix {
+    pub fn build(authors: &[AuthorAnalysis]) -> Self {
+        let norms: Vec<f64> = authors
+            .iter()
+            .map(|author| norm(&author.word_frequency))
+            .collect();
+        let n = authors.len();
+        let mut rows = vec![vec![0.0; n]; n];
+        for i in 0..n {
+            rows[i][i] = if norms[i] > 0.0 { 1.0 } else { 0.0 };
+            for j in i + 1..n {
+                let similarity = if norms[i] > 0.0 && norms[j] > 0.0 {
+                    dot(&authors[i].word_frequency, &authors[j].word_frequency)
+                        / (norms[i] * norms[j])
+                } else {
+                    0.0
+                };
+                rows[i][j] = similarity;
+                rows[j][i] = similarity;
+            }
+        }
+        Matrix {
+            authors: authors
+                .iter()
+                .map(|author| (author.author_id.clone(), author.author_name.clone()))
+                .collect(),
+            rows,
+        }
+    }
+
+    /// The most similar distinct pairs, highest first; pairs with no word
+    /// in common are left out.
+    pub fn most_similar(&self) -> Vec<SimilarAuthors> {
+        let mut pairs = Vec::new();
+        for (i, row) in self.rows.iter().enumerate() {
+            for (j, &similarity) in row.iter().enumerate().skip(i + 1) {
+                if similarity > 0.0 {
+                    pairs.push((i, j, similarity));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| {
+            b.2.total_cmp(&a.2)
+                .then_with(|| (a.0, a.1).cmp(&(b.0, b.1)))
+        });
+        pairs
+            .into_iter()
+            .take(LIMIT)
+            .map(|(i, j, similarity)| SimilarAuthors {
+                author_a: self.authors[i].0.clone(),
+                author_b: self.authors[j].0.clone(),
+                similarity,
+            })
+            .collect()
+    }
+
+    /// Renames every author, as masking does; the similarities are kept,
+    /// having been measured on the words as written.
+    pub fn map_names(&mut self, rename: impl Fn(&str) -> String) {
+        for (_, name) in &mut self.authors {
+            *name = rename(name);
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let mut out = String::from("author_id,author_name");
+        for (id, _) in &self.authors {
+            out.push(',');
+            out.push_str(&csv_field(id));
+        }
+        out.push('\n');
+        for ((id, name), row) in self.authors.iter().zip(&self.rows) {
+            out.push_str(&format!("{},{}", csv_field(id), csv_field(name)));
+            for similarity in row {
+                out.push_str(&format!(",{:.4}", similarity));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let authors: Vec<serde_json::Value> = self
+            .authors
+            .iter()
+            .map(|(id, name)| json!({ "author_id": id, "author_name": name }))
+            .collect();
+        json!({ "authors": authors, "matrix": self.rows })
+    }
+}
+
+fn norm(words: &BTreeMap<String, usize>) -> f64 {
+    words
+        .values()
+        .map(|&count| (count * count) as f64)
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn dot(a: &BTreeMap<String, usize>, b: &BTreeMap<String, usize>) -> f64 {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    small
+        .iter()
+        .filter_map(|(word, &count)| large.get(word).map(|&other| (count * other) as f64))
+        .sum()
+}
+
+/// Writes CSV for a `.csv` path and JSON otherwise.
+pub fn write(matrix: &Matrix, path: &Path) -> anyhow::Result<()> {
+    let data = if paths::is_csv(path) {
+        matrix.to_csv()
+    } else {
+        serde_json::to_string(&matrix.to_json())? + "\n"
+    };
+    fs::write(paths::extended(path), data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_author_analysis, MessageCounts};
+    use std::collections::HashMap;
+
+    fn author(id: &str, words: &[(&str, usize)]) -> AuthorAnalysis {
+        let words: HashMap<String, usize> = words
+            .iter()
+            .map(|(word, count)| (word.to_string(), *count))
+            .collect();
+        let counts = MessageCounts::default();
+        build_author_analysis(id.to_string(), id.to_string(), String::new(), counts, words)
+    }
+
+    #[test]
+    fn test_cosine_similarity_between_authors() {
+        let authors = [
+            author("1", &[("where", 2), ("refund", 1)]),
+            author("2", &[("where", 4), ("refund", 2)]),
+            author("3", &[("gg", 1)]),
+            author("4", &[]),
+        ];
+        let matrix = Matrix::build(&authors);
+        assert!((matrix.rows[0][1] - 1.0).abs() < 1e-9);
+        assert_eq!(matrix.rows[0][2], 0.0);
+        assert_eq!(matrix.rows[3][3], 0.0);
+
+        let pairs = matrix.most_similar();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(
+            (pairs[0].author_a.as_str(), pairs[0].author_b.as_str()),
+            ("1", "2")
+        );
+        assert!(matrix
+            .to_csv()
+            .starts_with("author_id,author_name,1,2,3,4\n1,1,1.0000,1.0000,"));
+
+        let mut masked = matrix.clone();
+        masked.map_names(|name| name.replace('1', "*"));
+        assert!(masked.to_csv().contains("\n1,*,1.0000,"));
+    }
+}
@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+
+/// Discord epoch (2015-01-01T00:00:00Z) in milliseconds since the Unix epoch.
+pub const DISCORD_EPOCH_MS: i64 = 1_420_070_400_000;
+
+/// Decodes the creation time embedded in a Discord snowflake ID.
+///
+/// The upper 42 bits of a snowflake are milliseconds since the Discord epoch,
+/// so any valid message, user or channel ID carries its own timestamp.
+pub fn snowflake_to_datetime(id: &str) -> Option<DateTime<Utc>> {
+    let raw: u64 = id.trim().parse().ok()?;
+    let ms = (raw >> 22) as i64 + DISCORD_EPOCH_MS;
+    DateTime::from_timestamp_millis(ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snowflake_to_datetime() {
+        let ts = snowflake_to_datetime("1222277081971294297").unwrap();
+        assert_eq!(ts.to_rfc3339(), "2024-03-26T20:12:45.152+00:00");
+    }
+
+    #[test]
+    fn test_snowflake_to_datetime_rejects_garbage() {
+        assert!(snowflake_to_datetime("").is_none());
+        assert!(snowflake_to_datetime("not-an-id").is_none());
+    }
+
+    #[test]
+    fn test_snowflake_zero_is_discord_epoch() {
+        let ts = snowflake_to_datetime("0").unwrap();
+        assert_eq!(ts.timestamp_millis(), DISCORD_EPOCH_MS);
+    }
+}
@@ -0,0 +1,145 @@
+//! `--stem`: Snowball stemming, so inflections such as "banned", "banning"
+//! and "bans" are counted as one word ("ban").
+//!
+//! Stems are often not words themselves, so the surface forms behind each
+//! stem are kept and reported in `word_variants`. Stopwords are removed
+//! before stemming, from the words as written.
+
+use rust_stemmers::{Algorithm, Stemmer};
+use std::collections::{BTreeMap, HashMap};
+use whatlang::Lang;
+
+/// Snowball algorithms, by language.
+const ALGORITHMS: &[(Lang, Algorithm)] = &[
+    (Lang::Ara, Algorithm::Arabic),
+    (Lang::Dan, Algorithm::Danish),
+    (Lang::Nld, Algorithm::Dutch),
+    (Lang::Eng, Algorithm::English),
+    (Lang::Fin, Algorithm::Finnish),
+    (Lang::Fra, Algorithm::French),
+    (Lang::Deu, Algorithm::German),
+    (Lang::Ell, Algorithm::Greek),
+    (Lang::Hun, Algorithm::Hungarian),
+    (Lang::Ita, Algorithm::Italian),
+    (Lang::Nob, Algorithm::Norwegian),
+    (Lang::Por, Algorithm::Portuguese),
+    (Lang::Ron, Algorithm::Romanian),
+    (Lang::Rus, Algorithm::Russian),
+    (Lang::Spa, Algorithm::Spanish),
+    (Lang::Swe, Algorithm::Swedish),
+    (Lang::Tam, Algorithm::Tamil),
+    (Lang::Tur, Algorithm::Turkish),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stem(Algorithm);
+
+impl Stem {
+    pub fn for_language(lang: Lang) -> anyhow::Result<Self> {
+        ALGORITHMS
+            .iter()
+            .find(|(known, _)| *known == lang)
+            .map(|(_, algorithm)| Stem(*algorithm))
+            .ok_or_else(|| anyhow::anyhow!("no Snowball stemmer for {}", lang.eng_name()))
+    }
+
+    /// Replaces each word with its stem, recording the word under it.
+    pub fn apply(&self, words: &mut [String], variants: &mut Variants) {
+        let stemmer = Stemmer::create(self.0);
+        for word in words {
+            let stem = stemmer.stem(word).into_owned();
+            let surface = std::mem::replace(word, stem);
+            variants.record(word, surface);
+        }
+    }
+}
+
+/// Surface forms seen for each stem (or lemma), with how often each was
+/// seen.
+#[derive(Debug, Clone, Default)]
+pub struct Variants(HashMap<String, HashMap<String, usize>>);
+
+impl Variants {
+    pub fn record(&mut self, stem: &str, surface: String) {
+        // Most words recur, so look the stem up before allocating a key.
+        let forms = match self.0.get_mut(stem) {
+            Some(forms) => forms,
+            None => self.0.entry(stem.to_string()).or_default(),
+        };
+        *forms.entry(surface).or_insert(0) += 1;
+    }
+
+    /// Adds `count` sightings of `form` under `word` at once.
+    pub fn add(&mut self, word: &str, form: String, count: usize) {
+        *self
+            .0
+            .entry(word.to_string())
+            .or_default()
+            .entry(form)
+            .or_insert(0) += count;
+    }
+
+    /// Moves the forms recorded under `from` to `to`, as `--fuzzy-words`
+    /// does when merging a misspelled stem.
+    pub fn rename(&mut self, from: &str, to: &str) {
+        if let Some(forms) = self.0.remove(from) {
+            let merged = self.0.entry(to.to_string()).or_default();
+            for (form, count) in forms {
+                *merged.entry(form).or_insert(0) += count;
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn merge(&mut self, other: Variants) {
+        for (stem, forms) in other.0 {
+            let merged = self.0.entry(stem).or_default();
+            for (form, count) in forms {
+                *merged.entry(form).or_insert(0) += count;
+            }
+        }
+    }
+
+    /// Every stem written in some other way, with its forms, most frequent
+    /// first.
+    pub fn into_map(self) -> BTreeMap<String, Vec<String>> {
+        self.0
+            .into_iter()
+            .filter(|(stem, forms)| forms.len() > 1 || !forms.contains_key(stem))
+            .map(|(stem, forms)| {
+                let mut forms: Vec<(String, usize)> = forms.into_iter().collect();
+                forms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                (stem, forms.into_iter().map(|(form, _)| form).collect())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inflections_share_a_stem() {
+        let stem = Stem::for_language(Lang::Eng).unwrap();
+        let mut words: Vec<String> = ["banned", "banning", "bans", "banned", "the"]
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let mut variants = Variants::default();
+        stem.apply(&mut words, &mut variants);
+        assert_eq!(words, ["ban", "ban", "ban", "ban", "the"]);
+
+        let mut more = Variants::default();
+        stem.apply(&mut ["bans".to_string()], &mut more);
+        variants.merge(more);
+        let map = variants.into_map();
+        assert_eq!(map["ban"], ["banned", "bans", "banning"]);
+        // Words that are their own stem are not listed.
+        assert!(!map.contains_key("the"));
+        assert!(Stem::for_language(Lang::Jpn).is_err());
+    }
+}
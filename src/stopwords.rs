@@ -0,0 +1,96 @@
+//! `--stopwords` / `--stopwords-file`: function words ("the", "and", "that")
+//! dropped before anything is counted, so the most common words say
+//! something about the messages.
+//!
+//! Lists hold whitespace-separated words, with `#` starting a comment. Words
+//! are compared lowercased, like tokens. A word with an apostrophe also
+//! matches its spelling without one, since the simple tokenizer drops it
+//! (`don't` becomes `dont`).
+
+use anyhow::Context;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use whatlang::Lang;
+
+/// Built-in lists, by language.
+const BUILT_IN: &[(Lang, &str)] = &[
+    (Lang::Eng, include_str!("../data/stopwords/en.txt")),
+    (Lang::Deu, include_str!("../data/stopwords/de.txt")),
+    (Lang::Fra, include_str!("../data/stopwords/fr.txt")),
+    (Lang::Spa, include_str!("../data/stopwords/es.txt")),
+    (Lang::Por, include_str!("../data/stopwords/pt.txt")),
+    (Lang::Ita, include_str!("../data/stopwords/it.txt")),
+    (Lang::Nld, include_str!("../data/stopwords/nl.txt")),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct Stopwords(HashSet<String>);
+
+impl Stopwords {
+    /// The built-in lists for `langs` plus the words in `file`.
+    pub fn load(langs: &[Lang], file: Option<&Path>) -> anyhow::Result<Self> {
+        let mut stopwords = Stopwords::default();
+        for lang in langs {
+            let Some((_, list)) = BUILT_IN.iter().find(|(built_in, _)| built_in == lang) else {
+                anyhow::bail!(
+                    "no built-in stopword list for {}; use --stopwords-file",
+                    lang.eng_name()
+                );
+            };
+            stopwords.add(list);
+        }
+        if let Some(path) = file {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("failed to read stopwords {}", path.display()))?;
+            stopwords.add(&text);
+        }
+        Ok(stopwords)
+    }
+
+    fn add(&mut self, list: &str) {
+        let words = list
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .flat_map(str::split_whitespace);
+        for word in words {
+            let word = word.to_lowercase();
+            let bare: String = word.chars().filter(|c| *c != '\'' && *c != '’').collect();
+            if bare != word {
+                self.0.insert(bare);
+            }
+            self.0.insert(word);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.0.contains(word)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_built_in_and_custom_lists() {
+        let dir = std::env::temp_dir().join(format!("dua-stopwords-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let extra = dir.join("extra.txt");
+        fs::write(&extra, "# server slang\nLOL  brb\n\ngg # good game\n").unwrap();
+
+        let stopwords = Stopwords::load(&[Lang::Eng], Some(&extra)).unwrap();
+        for word in ["the", "and", "that", "don't", "dont", "lol", "brb", "gg"] {
+            assert!(stopwords.contains(word), "{}", word);
+        }
+        assert!(!stopwords.contains("ban"));
+        assert!(!stopwords.contains("good"));
+        assert!(Stopwords::load(&[Lang::Deu], None).unwrap().contains("und"));
+        assert!(Stopwords::load(&[Lang::Jpn], None).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
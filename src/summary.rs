@@ -0,0 +1,143 @@
+//! A short plain-language paragraph describing a result, for readers who
+//! will not look past the first lines of a report.
+
+use crate::locale::Locale;
+use crate::{target, AnalysisResult};
+use chrono_tz::Tz;
+use std::cmp::Reverse;
+
+/// How many of the most active authors the concentration sentence names.
+const TOP_AUTHORS: usize = 3;
+
+/// Summarizes the period, volume, author concentration and top word.
+pub fn paragraph(result: &AnalysisResult, locale: &Locale) -> String {
+    let target = target::label(&result.targets);
+    let tz = result.timezone.unwrap_or(Tz::UTC);
+    if result.total_messages == 0 {
+        return format!("No messages to {} were found.", target);
+    }
+
+    let first = result
+        .authors_analysis
+        .iter()
+        .filter_map(|a| a.first_message_at)
+        .min();
+    let last = result
+        .authors_analysis
+        .iter()
+        .filter_map(|a| a.last_message_at)
+        .max();
+    let period = match (first, last) {
+        (Some(first), Some(last)) if locale.date(&first, tz) == locale.date(&last, tz) => {
+            format!("On {}, ", locale.date(&first, tz))
+        }
+        (Some(first), Some(last)) => {
+            format!(
+                "Between {} and {}, ",
+                locale.date(&first, tz),
+                locale.date(&last, tz)
+            )
+        }
+        _ => String::new(),
+    };
+    let mut text = format!(
+        "{}{} sent {} to {}",
+        period,
+        plural(result.unique_authors, "author", locale),
+        plural(result.total_messages, "message", locale),
+        target
+    );
+    if let Some(sampling) = &result.sampling {
+        text.push_str(&format!(
+            " in a {} sample (about {} in the full input, {} CI {}–{})",
+            locale.percent(sampling.effective_fraction),
+            locale.count(sampling.estimated_total_messages),
+            locale.percent(sampling.confidence_level),
+            locale.count(sampling.estimated_total_messages_low),
+            locale.count(sampling.estimated_total_messages_high)
+        ));
+    }
+
+    let mut authors: Vec<_> = result.authors_analysis.iter().collect();
+    authors.sort_by(|a, b| {
+        b.total_messages_to_deleted_user
+            .cmp(&a.total_messages_to_deleted_user)
+            .then_with(|| a.author_id.cmp(&b.author_id))
+    });
+    if authors.len() > TOP_AUTHORS {
+        let top = &authors[..TOP_AUTHORS];
+        let top_messages: usize = top.iter().map(|a| a.total_messages_to_deleted_user).sum();
+        let names: Vec<&str> = top.iter().map(|a| a.author_name.as_str()).collect();
+        text.push_str(&format!(
+            "; {} came from the {} most active accounts ({})",
+            locale.percent(top_messages as f64 / result.total_messages as f64),
+            TOP_AUTHORS,
+            names.join(", ")
+        ));
+    }
+    text.push('.');
+
+    let blank = result.blank_messages.total();
+    if blank > 0 {
+        text.push_str(&format!(
+            " Of these, {} carried no text.",
+            locale.percent(blank as f64 / result.total_messages as f64)
+        ));
+    }
+
+    if let Some((word, count)) = result
+        .global_word_frequency
+        .iter()
+        .max_by_key(|(word, count)| (**count, Reverse(word.as_str())))
+    {
+        text.push_str(&format!(
+            " The most frequent word was \"{}\" ({} times).",
+            word,
+            locale.count(*count)
+        ));
+    }
+    text
+}
+
+fn plural(n: usize, noun: &str, locale: &Locale) -> String {
+    if n == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", locale.count(n), noun)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::BlankCounts;
+    use crate::warnings::Warnings;
+
+    #[test]
+    fn test_paragraph() {
+        let mut result = crate::build_result(
+            Vec::new(),
+            BlankCounts::default(),
+            Vec::new(),
+            0,
+            Warnings::default(),
+        );
+        let locale = Locale::default();
+        assert_eq!(
+            paragraph(&result, &locale),
+            "No messages to deleted users were found."
+        );
+
+        result.total_messages = 862;
+        result.unique_authors = 14;
+        result.blank_messages.empty = 431;
+        result.global_word_frequency = [("gone".to_string(), 40), ("why".to_string(), 40)]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            paragraph(&result, &locale),
+            "14 authors sent 862 messages to deleted users. Of these, 50.0% carried no text. \
+             The most frequent word was \"gone\" (40 times)."
+        );
+    }
+}
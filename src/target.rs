@@ -0,0 +1,592 @@
+//! Who the analysis is about: messages that mention or reply to a target.
+
+use crate::message::Message;
+use anyhow::Context;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs;
+use unicode_normalization::UnicodeNormalization;
+
+/// Pattern matched when no `--target` or `--target-regex` is given. Covers
+/// `Deleted User`, `Deleted User#0000`, `deleted_user_<hash>` and the names
+/// localized Discord clients show for deleted accounts.
+pub const DEFAULT_PATTERN: &str = "(?i)deleted[ _-]?user|gelöschter benutzer|utilisateur supprimé|usuario eliminado|usuário (?:excluído|deletado)|utente eliminato|usunięty użytkownik|verwijderde gebruiker|удал[её]нный пользователь|削除されたユーザー";
+
+/// How names are compared with the target's.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NameMatching {
+    /// `--match-case`: compare `--target` names case-sensitively.
+    pub match_case: bool,
+    /// `--normalize-unicode`: NFKC-normalize names before comparing, so
+    /// fullwidth, styled and ligature lookalikes match their plain forms.
+    /// Also applies to what patterns are matched against.
+    pub normalize_unicode: bool,
+}
+
+impl NameMatching {
+    /// The name as patterns see it.
+    fn normalize<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        if self.normalize_unicode {
+            Cow::Owned(name.nfkc().collect())
+        } else {
+            Cow::Borrowed(name)
+        }
+    }
+
+    /// The name as target names are compared with it.
+    fn fold<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        let name = self.normalize(name);
+        if self.match_case {
+            name
+        } else {
+            Cow::Owned(name.to_lowercase())
+        }
+    }
+}
+
+/// Substrings (case-insensitive unless `--match-case`) and regexes matched
+/// against mentioned and replied-to user names and nicknames, grouped by
+/// target.
+#[derive(Debug, Clone)]
+pub struct TargetGroup {
+    /// Set for targets from `--targets-file`, which get their own section.
+    pub label: Option<String>,
+    pub names: Vec<String>,
+    pub patterns: Vec<Regex>,
+    pub matching: NameMatching,
+}
+
+/// A target as given on the command line or in a targets file, before
+/// names are normalized and patterns compiled.
+#[derive(Deserialize)]
+struct TargetSpec {
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    patterns: Vec<String>,
+}
+
+impl TargetSpec {
+    /// `None` when the spec has nothing to match.
+    fn compile(self, matching: NameMatching) -> anyhow::Result<Option<TargetGroup>> {
+        let names: Vec<String> = self
+            .names
+            .iter()
+            .map(|name| matching.fold(name.trim()).into_owned())
+            .filter(|name| !name.is_empty())
+            .collect();
+        let patterns = self
+            .patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).with_context(|| format!("invalid target pattern `{}`", pattern))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if names.is_empty() && patterns.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(TargetGroup {
+            label: self.label,
+            names,
+            patterns,
+            matching,
+        }))
+    }
+}
+
+/// Why a message matched: which rule of which target fired on which field.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MatchReason {
+    /// The `--targets-file` label, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// `mentioned_user_name`, `referenced_message.author_nickname`, ...
+    pub field: &'static str,
+    /// The field's value.
+    pub value: String,
+    /// `name "..."` for a substring match, `pattern /.../` for a regex.
+    pub rule: String,
+}
+
+impl TargetGroup {
+    /// The rules that match `name`, described for [`MatchReason::rule`].
+    fn matching_rules(&self, name: &str) -> Vec<String> {
+        let (folded, normalized) = (self.matching.fold(name), self.matching.normalize(name));
+        self.names
+            .iter()
+            .filter(|target| folded.contains(target.as_str()))
+            .map(|target| format!("name {:?}", target))
+            .chain(
+                self.patterns
+                    .iter()
+                    .filter(|re| re.is_match(&normalized))
+                    .map(|re| format!("pattern /{}/", re.as_str())),
+            )
+            .collect()
+    }
+
+    /// Every rule that matched, on every name field of the message.
+    pub fn explain(&self, msg: &Message) -> Vec<MatchReason> {
+        let reference = msg.referenced_message.as_ref();
+        let fields = [
+            ("mentioned_user_name", msg.mentioned_user_name.as_ref()),
+            (
+                "mentioned_user_nickname",
+                msg.mentioned_user_nickname.as_ref(),
+            ),
+            (
+                "referenced_message.author_name",
+                reference.and_then(|r| r.author_name.as_ref()),
+            ),
+            (
+                "referenced_message.author_nickname",
+                reference.and_then(|r| r.author_nickname.as_ref()),
+            ),
+        ];
+        let mut reasons = Vec::new();
+        for (field, value) in fields {
+            let Some(value) = value else { continue };
+            for rule in self.matching_rules(value) {
+                reasons.push(MatchReason {
+                    target: self.label.clone(),
+                    field,
+                    value: value.clone(),
+                    rule,
+                });
+            }
+        }
+        reasons
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        let folded = self.matching.fold(name);
+        self.names
+            .iter()
+            .any(|target| folded.contains(target.as_str()))
+            || {
+                let normalized = self.matching.normalize(name);
+                self.patterns.iter().any(|re| re.is_match(&normalized))
+            }
+    }
+
+    /// Names as given, and patterns as `/regex/`.
+    pub fn describe(&self) -> Vec<String> {
+        self.names
+            .iter()
+            .cloned()
+            .chain(self.patterns.iter().map(|re| format!("/{}/", re.as_str())))
+            .collect()
+    }
+
+    fn mentioned(&self, msg: &Message) -> bool {
+        [&msg.mentioned_user_name, &msg.mentioned_user_nickname]
+            .into_iter()
+            .flatten()
+            .any(|name| self.matches_name(name))
+    }
+
+    /// Replies sent without a ping carry no mention, only the author of the
+    /// referenced message.
+    fn replied_to(&self, msg: &Message) -> bool {
+        msg.referenced_message.as_ref().is_some_and(|reference| {
+            [&reference.author_name, &reference.author_nickname]
+                .into_iter()
+                .flatten()
+                .any(|name| self.matches_name(name))
+        })
+    }
+
+    pub fn matches(&self, msg: &Message) -> bool {
+        self.mentioned(msg) || self.replied_to(msg)
+    }
+}
+
+/// Layout of a `--targets-file`:
+///
+/// ```toml
+/// [[targets]]
+/// label = "old-main"
+/// names = ["Deleted User 4f2a9c1b"]
+/// patterns = ["(?i)^old_main"]
+/// ```
+#[derive(Deserialize)]
+struct TargetsFile {
+    targets: Vec<TargetSpec>,
+}
+
+/// How `targets` lists a run over every message (`--no-target-filter`).
+pub const EVERYONE: &str = "*";
+
+#[derive(Debug, Clone)]
+pub struct Target {
+    groups: Vec<TargetGroup>,
+    /// `--no-target-filter`: every message matches.
+    everyone: bool,
+    /// `--inactive-targets`: every message matches here, and the messages
+    /// to users who had stopped writing are picked once all inputs are in.
+    inactive_days: Option<u32>,
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Target {
+            groups: vec![default_group(NameMatching::default())],
+            everyone: false,
+            inactive_days: None,
+        }
+    }
+}
+
+fn default_group(matching: NameMatching) -> TargetGroup {
+    TargetGroup {
+        label: None,
+        names: Vec::new(),
+        patterns: vec![Regex::new(DEFAULT_PATTERN).expect("default target pattern is valid")],
+        matching,
+    }
+}
+
+impl Target {
+    /// `--target` names and `--target-regex` patterns plus the labeled
+    /// targets of a `--targets-file`. Falls back to [`DEFAULT_PATTERN`]
+    /// when none of them match anything.
+    pub fn load(
+        names: &[String],
+        patterns: &[String],
+        path: Option<&str>,
+        matching: NameMatching,
+    ) -> anyhow::Result<Self> {
+        let mut specs = vec![TargetSpec {
+            label: None,
+            names: names.to_vec(),
+            patterns: patterns.to_vec(),
+        }];
+        if let Some(path) = path {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("failed to read targets file {}", path))?;
+            let file: TargetsFile = toml::from_str(&text)
+                .with_context(|| format!("failed to parse targets file {}", path))?;
+            for (i, mut spec) in file.targets.into_iter().enumerate() {
+                spec.label
+                    .get_or_insert_with(|| format!("target-{}", i + 1));
+                specs.push(spec);
+            }
+        }
+
+        let mut groups = Vec::new();
+        for spec in specs {
+            groups.extend(spec.compile(matching)?);
+        }
+        if groups.is_empty() {
+            groups.push(default_group(matching));
+        }
+        Ok(Target {
+            groups,
+            everyone: false,
+            inactive_days: None,
+        })
+    }
+
+    /// Matches every message, so the whole input is analyzed.
+    pub fn everyone() -> Self {
+        Target {
+            groups: Vec::new(),
+            everyone: true,
+            inactive_days: None,
+        }
+    }
+
+    /// Users mentioned at least `days` after their last message.
+    pub fn inactive(days: u32) -> Self {
+        Target {
+            inactive_days: Some(days),
+            ..Target::everyone()
+        }
+    }
+
+    /// Whether every message is analyzed. Under `--inactive-targets` the
+    /// target stage still counts the messages dropped late.
+    pub fn is_everyone(&self) -> bool {
+        self.everyone && self.inactive_days.is_none()
+    }
+
+    pub fn inactive_days(&self) -> Option<u32> {
+        self.inactive_days
+    }
+
+    /// Every name and pattern matched, across all groups.
+    pub fn names(&self) -> Vec<String> {
+        if let Some(days) = self.inactive_days {
+            return vec![format!("users inactive for {}+ days", days)];
+        }
+        if self.everyone {
+            return vec![EVERYONE.to_string()];
+        }
+        self.groups.iter().flat_map(TargetGroup::describe).collect()
+    }
+
+    /// Targets from `--targets-file`, which get per-target sections.
+    pub fn labeled(&self) -> impl Iterator<Item = &TargetGroup> {
+        self.groups.iter().filter(|group| group.label.is_some())
+    }
+
+    pub fn is_default(&self) -> bool {
+        match self.groups.as_slice() {
+            [group] => {
+                group.label.is_none()
+                    && group.names.is_empty()
+                    && group.patterns.len() == 1
+                    && group.patterns[0].as_str() == DEFAULT_PATTERN
+            }
+            _ => false,
+        }
+    }
+
+    /// How console output refers to the target.
+    pub fn label(&self) -> String {
+        if self.is_default() {
+            label(&[])
+        } else {
+            label(&self.names())
+        }
+    }
+
+    pub fn matches(&self, msg: &Message) -> bool {
+        self.everyone || self.groups.iter().any(|group| group.matches(msg))
+    }
+
+    /// Why the message matches, across all groups; empty when it does not.
+    /// Under `--no-target-filter` every message matches without a rule.
+    pub fn explain(&self, msg: &Message) -> Vec<MatchReason> {
+        if let Some(days) = self.inactive_days {
+            return vec![MatchReason {
+                target: None,
+                field: "*",
+                value: String::new(),
+                rule: format!("--inactive-targets {}", days),
+            }];
+        }
+        if self.everyone {
+            return vec![MatchReason {
+                target: None,
+                field: "*",
+                value: String::new(),
+                rule: "--no-target-filter".to_string(),
+            }];
+        }
+        self.groups
+            .iter()
+            .flat_map(|group| group.explain(msg))
+            .collect()
+    }
+
+    /// Whether a message's mentioned user is a target. Never true for
+    /// `--no-target-filter` or `--inactive-targets`, which match by author.
+    pub fn mentioned(&self, msg: &Message) -> bool {
+        self.groups.iter().any(|group| group.mentioned(msg))
+    }
+
+    /// Whether a message only matched through the reply it points at.
+    pub fn matched_by_reply_only(&self, msg: &Message) -> bool {
+        !self.mentioned(msg) && self.groups.iter().any(|group| group.replied_to(msg))
+    }
+}
+
+/// "deleted users" for the default target, otherwise the target names.
+pub fn label(names: &[String]) -> String {
+    if names.is_empty() {
+        "deleted users".to_string()
+    } else if names == [EVERYONE] {
+        "all recipients".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::ReferencedMessage;
+
+    fn mentioning(name: &str) -> Message {
+        Message {
+            mentioned_user_nickname: Some(name.to_string()),
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_reply_without_mention_matches() {
+        let target = Target::default();
+        let reply = Message {
+            referenced_message: Some(ReferencedMessage {
+                author_name: Some("Deleted User".to_string()),
+                ..Default::default()
+            }),
+            ..Message::default()
+        };
+        assert!(target.matches(&reply));
+        assert!(target.matched_by_reply_only(&reply));
+
+        let mention = Message {
+            mentioned_user_name: Some("Deleted User".to_string()),
+            ..reply.clone()
+        };
+        assert!(target.matches(&mention));
+        assert!(!target.matched_by_reply_only(&mention));
+        assert!(!target.matches(&Message::default()));
+    }
+
+    #[test]
+    fn test_custom_targets() {
+        let target = Target::load(
+            &["Alice".to_string(), " bob ".to_string()],
+            &[],
+            None,
+            NameMatching::default(),
+        )
+        .unwrap();
+        assert!(!target.is_default());
+        assert!(target.matches(&mentioning("alice#1234")));
+        assert!(target.matches(&mentioning("BOB")));
+        assert!(!target.matches(&mentioning("Deleted User")));
+        assert!(
+            Target::load(&[" ".to_string()], &[], None, NameMatching::default())
+                .unwrap()
+                .is_default()
+        );
+    }
+
+    #[test]
+    fn test_match_case_and_unicode_normalization() {
+        let load = |match_case, normalize_unicode| {
+            let matching = NameMatching {
+                match_case,
+                normalize_unicode,
+            };
+            Target::load(&["Alice".to_string()], &[], None, matching).unwrap()
+        };
+        let styled = mentioning("𝐀𝐥𝐢𝐜𝐞"); // mathematical bold
+        let fullwidth = mentioning("ａｌｉｃｅ");
+
+        let default = load(false, false);
+        assert!(default.matches(&mentioning("ALICE")));
+        assert!(!default.matches(&styled));
+
+        let case = load(true, false);
+        assert!(case.matches(&mentioning("Alice_alt")));
+        assert!(!case.matches(&mentioning("alice")));
+
+        let normalized = load(false, true);
+        assert!(normalized.matches(&styled));
+        assert!(normalized.matches(&fullwidth));
+        assert!(!load(true, true).matches(&fullwidth));
+        assert!(Target {
+            groups: vec![default_group(normalized.groups[0].matching)],
+            everyone: false,
+            inactive_days: None,
+        }
+        .matches(&mentioning("Ｄｅｌｅｔｅｄ Ｕｓｅｒ")));
+    }
+
+    #[test]
+    fn test_explain_lists_every_matching_rule() {
+        let target = Target::load(
+            &["alice".to_string()],
+            &["(?i)^ali".to_string()],
+            None,
+            NameMatching::default(),
+        )
+        .unwrap();
+        let msg = Message {
+            mentioned_user_name: Some("Alice".to_string()),
+            referenced_message: Some(ReferencedMessage {
+                author_nickname: Some("alice2".to_string()),
+                ..Default::default()
+            }),
+            ..mentioning("bob")
+        };
+        let rules: Vec<_> = target
+            .explain(&msg)
+            .into_iter()
+            .map(|r| format!("{} {} {}", r.field, r.value, r.rule))
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                "mentioned_user_name Alice name \"alice\"",
+                "mentioned_user_name Alice pattern /(?i)^ali/",
+                "referenced_message.author_nickname alice2 name \"alice\"",
+                "referenced_message.author_nickname alice2 pattern /(?i)^ali/",
+            ]
+        );
+        assert!(target.explain(&mentioning("bob")).is_empty());
+    }
+
+    #[test]
+    fn test_everyone_matches_all_messages() {
+        let target = Target::everyone();
+        assert!(target.matches(&Message::default()));
+        assert!(!target.matched_by_reply_only(&Message::default()));
+        assert_eq!(target.label(), "all recipients");
+        assert_eq!(target.labeled().count(), 0);
+    }
+
+    #[test]
+    fn test_default_pattern_covers_variants() {
+        let target = Target::default();
+        for name in [
+            "Deleted User",
+            "Deleted User#0000",
+            "Deleted User 4f2a9c1b",
+            "deleted_user_4f2a9c1b",
+            "Gelöschter Benutzer",
+            "Utilisateur supprimé",
+            "Usuário excluído",
+            "Удалённый пользователь",
+        ] {
+            assert!(target.matches(&mentioning(name)), "{}", name);
+        }
+        assert!(!target.matches(&mentioning("bob")));
+    }
+
+    #[test]
+    fn test_target_regex_replaces_default() {
+        let target = Target::load(
+            &[],
+            &[r"^spam_\d+$".to_string()],
+            None,
+            NameMatching::default(),
+        )
+        .unwrap();
+        assert!(!target.is_default());
+        assert_eq!(target.names(), [r"/^spam_\d+$/"]);
+        assert!(target.matches(&mentioning("spam_42")));
+        assert!(!target.matches(&mentioning("Deleted User")));
+        assert!(Target::load(&[], &["(".to_string()], None, NameMatching::default()).is_err());
+    }
+
+    #[test]
+    fn test_targets_file_groups() {
+        let path = std::env::temp_dir().join(format!("dua-targets-{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            "[[targets]]\nlabel = \"first\"\nnames = [\"Deleted User 1a2b\"]\n\n[[targets]]\npatterns = [\"(?i)deleted user 9f9f\"]\n",
+        )
+        .unwrap();
+        let target = Target::load(&[], &[], path.to_str(), NameMatching::default()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let labels: Vec<_> = target.labeled().map(|g| g.label.clone().unwrap()).collect();
+        assert_eq!(labels, ["first", "target-2"]);
+        assert!(target.matches(&mentioning("Deleted User 9F9F")));
+        assert!(!target.matches(&mentioning("Deleted User 0000")));
+        assert_eq!(
+            target.names(),
+            ["deleted user 1a2b", "/(?i)deleted user 9f9f/"]
+        );
+    }
+}
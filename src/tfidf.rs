@@ -0,0 +1,91 @@
+//! `top_tfidf_words`: each author's words weighted by TF-IDF, treating every
+//! author's words as one document, so words every author uses sink and
+//! the ones that set an author apart rise.
+//!
+//! The term frequency is the word's share of the author's words, and the
+//! smoothed inverse document frequency `ln((1 + n) / (1 + df)) + 1` keeps
+//! words used by every author above zero, so a lone author still gets a
+//! ranking.
+
+use crate::AuthorAnalysis;
+use std::collections::HashMap;
+
+/// Words listed per author.
+const LIMIT: usize = 10;
+
+/// Fills in `top_tfidf_words` for every author.
+pub fn score(authors: &mut [AuthorAnalysis]) {
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for author in authors.iter() {
+        for word in author.word_frequency.keys() {
+            *document_frequency.entry(word).or_insert(0) += 1;
+        }
+    }
+    let n = authors.len() as f64;
+    let idf: HashMap<&str, f64> = document_frequency
+        .into_iter()
+        .map(|(word, df)| (word, ((1.0 + n) / (1.0 + df as f64)).ln() + 1.0))
+        .collect();
+
+    let ranked: Vec<Vec<(String, f64)>> = authors
+        .iter()
+        .map(|author| {
+            let total: usize = author.word_frequency.values().sum();
+            let mut scores: Vec<(String, f64)> = author
+                .word_frequency
+                .iter()
+                .map(|(word, &count)| {
+                    (
+                        word.clone(),
+                        count as f64 / total as f64 * idf[word.as_str()],
+                    )
+                })
+                .collect();
+            scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            scores.truncate(LIMIT);
+            scores
+        })
+        .collect();
+    for (author, top) in authors.iter_mut().zip(ranked) {
+        author.top_tfidf_words = top;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_author_analysis, MessageCounts};
+
+    fn author(words: &[(&str, usize)]) -> AuthorAnalysis {
+        let counts = MessageCounts {
+            total: 1,
+            ..MessageCounts::default()
+        };
+        let words = words.iter().map(|(w, c)| (w.to_string(), *c)).collect();
+        build_author_analysis(String::new(), String::new(), String::new(), counts, words)
+    }
+
+    #[test]
+    fn test_shared_words_rank_below_distinctive_ones() {
+        let mut authors = vec![
+            author(&[("where", 5), ("refund", 2)]),
+            author(&[("where", 5), ("miss", 1)]),
+        ];
+        score(&mut authors);
+        let top: Vec<&str> = authors[0]
+            .top_tfidf_words
+            .iter()
+            .map(|(w, _)| w.as_str())
+            .collect();
+        assert_eq!(top, ["where", "refund"]);
+        // Every author says "where", so its lead shrinks below the 5:2 of
+        // the raw counts.
+        let scores = &authors[0].top_tfidf_words;
+        assert!(scores[0].1 / scores[1].1 < 2.5);
+        assert_eq!(authors[1].top_tfidf_words[1].0, "miss");
+
+        let mut lone = vec![author(&[("hi", 2), ("bye", 1)])];
+        score(&mut lone);
+        assert_eq!(lone[0].top_tfidf_words[0].0, "hi");
+    }
+}
@@ -0,0 +1,145 @@
+//! Chronological timeline of every analyzed message across authors.
+
+use crate::message::Message;
+use crate::paths;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Longer contents are cut at this many characters in the snippet column.
+const SNIPPET_CHARS: usize = 280;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TimelineEntry {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub message_id: String,
+    /// Hash of the normalized content, stable across runs.
+    pub fingerprint: String,
+    pub author_id: String,
+    pub author_name: String,
+    pub channel: Option<String>,
+    pub snippet: String,
+}
+
+impl TimelineEntry {
+    fn new(msg: &Message) -> Self {
+        TimelineEntry {
+            timestamp: msg.parsed_timestamp(),
+            message_id: msg.message_id.clone(),
+            fingerprint: msg.fingerprint(),
+            author_id: msg.author_id.clone(),
+            author_name: msg.author_name.clone(),
+            channel: msg.channel_name.clone().or_else(|| msg.channel_id.clone()),
+            snippet: snippet(&msg.content),
+        }
+    }
+}
+
+/// Entries ordered by time, then message ID for messages sent in the same
+/// instant. Messages without a usable timestamp sort first.
+pub fn build(msgs: &[Message]) -> Vec<TimelineEntry> {
+    let mut entries: Vec<TimelineEntry> = msgs.iter().map(TimelineEntry::new).collect();
+    entries.sort_by(|a, b| {
+        a.timestamp
+            .cmp(&b.timestamp)
+            .then_with(|| a.message_id.cmp(&b.message_id))
+    });
+    entries
+}
+
+fn snippet(content: &str) -> String {
+    let flat = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    match flat.char_indices().nth(SNIPPET_CHARS) {
+        Some((cut, _)) => format!("{}…", &flat[..cut]),
+        None => flat,
+    }
+}
+
+/// Writes CSV for a `.csv` path and a JSON array otherwise.
+pub fn write(entries: &[TimelineEntry], path: &Path) -> anyhow::Result<()> {
+    let data = if paths::is_csv(path) {
+        to_csv(entries)
+    } else {
+        serde_json::to_string_pretty(entries)?
+    };
+    fs::write(paths::extended(path), data)?;
+    Ok(())
+}
+
+fn to_csv(entries: &[TimelineEntry]) -> String {
+    let mut out =
+        String::from("timestamp,message_id,fingerprint,author_id,author_name,channel,snippet\n");
+    for entry in entries {
+        let timestamp = entry
+            .timestamp
+            .map(|ts| ts.to_rfc3339_opts(SecondsFormat::Secs, true))
+            .unwrap_or_default();
+        let fields = [
+            timestamp.as_str(),
+            &entry.message_id,
+            &entry.fingerprint,
+            &entry.author_id,
+            &entry.author_name,
+            entry.channel.as_deref().unwrap_or(""),
+            &entry.snippet,
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(id: &str, timestamp: &str, content: &str) -> Message {
+        Message {
+            message_id: id.to_string(),
+            timestamp: timestamp.to_string(),
+            content: content.to_string(),
+            author_id: "1".to_string(),
+            author_name: "alice".to_string(),
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_build_sorts_chronologically() {
+        let entries = build(&[
+            msg("b", "2024-01-02T00:00:00Z", "second"),
+            msg("a", "2024-01-01T00:00:00Z", "first"),
+            msg("c", "2024-01-02T00:00:00Z", "third"),
+        ]);
+        let ids: Vec<&str> = entries.iter().map(|e| e.message_id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_csv_escapes_and_flattens() {
+        let entries = build(&[msg("1", "2024-01-01T00:00:00Z", "hi, \"you\"\nthere")]);
+        let csv = to_csv(&entries);
+        assert_eq!(
+            csv.lines().nth(1).unwrap(),
+            "2024-01-01T00:00:00Z,1,d7d5ae64bcfbe92d,1,alice,,\"hi, \"\"you\"\" there\""
+        );
+    }
+
+    #[test]
+    fn test_snippet_truncates_on_char_boundary() {
+        let long = "é".repeat(SNIPPET_CHARS + 10);
+        let cut = snippet(&long);
+        assert_eq!(cut.chars().count(), SNIPPET_CHARS + 1);
+        assert!(cut.ends_with('…'));
+    }
+}
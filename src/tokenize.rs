@@ -0,0 +1,550 @@
+//! Splitting message content into the words that are counted.
+
+use crate::cjk::{self, CjkMode};
+use crate::content;
+use crate::leet;
+use crate::message::Message;
+use crate::simd;
+use crate::stopwords::Stopwords;
+use regex::Regex;
+use std::borrow::Cow;
+use std::str::FromStr;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// `--tokenizer`: how content is split into words.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TokenizerMode {
+    /// Whitespace-separated chunks with everything but letters, digits and
+    /// `--token-chars` removed.
+    #[default]
+    Simple,
+    /// Unicode (UAX #29) word boundaries: `don't` and `3.5` stay whole,
+    /// punctuation separates words instead of gluing them together, and
+    /// hyphenated words are kept joined.
+    Unicode,
+}
+
+impl FromStr for TokenizerMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "simple" => Ok(TokenizerMode::Simple),
+            "unicode" => Ok(TokenizerMode::Unicode),
+            other => Err(format!("`{}` is not simple or unicode", other)),
+        }
+    }
+}
+
+/// Hyphens that join the words on either side under
+/// [`TokenizerMode::Unicode`].
+const HYPHENS: &[char] = &['-', '\u{2010}', '\u{2011}'];
+
+/// Word settings shared by every analysis path.
+#[derive(Debug, Clone, Default)]
+pub struct Tokenizer {
+    /// Shorter words (in bytes) are dropped.
+    pub min_len: usize,
+    /// `--max-word-length`: longer words (in characters) are dropped; 0
+    /// keeps every word.
+    pub max_len: usize,
+    /// `--drop-numbers`: drop words made only of digits (and `.`/`,`).
+    pub drop_numbers: bool,
+    /// `--drop-tokens-matching`: drop words the pattern matches.
+    pub drop_pattern: Option<Regex>,
+    /// `--token-chars`: kept inside words in addition to letters and digits.
+    pub extra_chars: Vec<char>,
+    pub mode: TokenizerMode,
+    /// `--cjk`: segment Chinese and Japanese runs.
+    pub cjk: Option<CjkMode>,
+    /// `--strip-quotes`: drop blockquoted lines before splitting.
+    pub strip_quotes: bool,
+    /// `--strip-urls`: drop links before splitting.
+    pub strip_urls: bool,
+    /// `--strip-mentions`: drop user, role and channel mentions.
+    pub strip_mentions: bool,
+    /// Drop fenced and inline code; on unless `--include-code`.
+    pub strip_code_blocks: bool,
+    /// `--strip-emoji`: drop Unicode and custom emoji.
+    pub strip_emoji: bool,
+    /// `--strip-spoilers`: drop spoilered text.
+    pub strip_spoilers: bool,
+    /// `--normalize-leet`: map leetspeak and lookalike letters.
+    pub normalize_leet: bool,
+    /// `--stopwords` / `--stopwords-file`: words never counted.
+    pub stopwords: Stopwords,
+}
+
+impl Tokenizer {
+    pub fn new(min_len: usize, extra_chars: &str) -> Self {
+        let mut extra_chars: Vec<char> = extra_chars.chars().collect();
+        extra_chars.sort_unstable();
+        extra_chars.dedup();
+        Tokenizer {
+            min_len,
+            extra_chars,
+            ..Tokenizer::default()
+        }
+    }
+
+    /// [`Tokenizer::tokenize`] for a message; `strip_mentions` also drops
+    /// rendered mentions of the user it mentions.
+    pub fn tokenize_message(&self, msg: &Message) -> Vec<String> {
+        let mut words = self.tokenize_message_with_stopwords(msg);
+        self.drop_stopwords(&mut words);
+        words
+    }
+
+    /// [`Tokenizer::tokenize_message`], keeping stopwords.
+    pub fn tokenize_message_with_stopwords(&self, msg: &Message) -> Vec<String> {
+        let names = if self.strip_mentions {
+            content::mentioned_names(msg)
+        } else {
+            Vec::new()
+        };
+        self.split(&self.clean(&msg.content, &names))
+    }
+
+    /// Lowercased whitespace-separated words, stripped of every character
+    /// that is neither alphanumeric nor one of `extra_chars`.
+    pub fn tokenize(&self, content: &str) -> Vec<String> {
+        let mut words = self.split(&self.clean(content, &[]));
+        self.drop_stopwords(&mut words);
+        words
+    }
+
+    pub fn drop_stopwords(&self, words: &mut Vec<String>) {
+        if !self.stopwords.is_empty() {
+            words.retain(|word| !self.stopwords.contains(word));
+        }
+    }
+
+    /// Removes the spans the `strip_*` settings exclude. Code goes first so
+    /// links and quotes inside it go with it.
+    fn clean<'a>(&self, text: &'a str, names: &[&str]) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        if self.strip_code_blocks {
+            text = Cow::Owned(content::strip_code_blocks(&text));
+        }
+        // Custom emoji are counted on their own, never as words.
+        let stripped = match content::strip_custom_emoji(&text) {
+            Cow::Owned(stripped) => Some(stripped),
+            Cow::Borrowed(_) => None,
+        };
+        if let Some(stripped) = stripped {
+            text = Cow::Owned(stripped);
+        }
+        if self.strip_spoilers {
+            text = Cow::Owned(content::strip_spoilers(&text));
+        }
+        // Markers are never part of words, even with `--token-chars`.
+        let stripped = match content::strip_markdown(&text) {
+            Cow::Owned(stripped) => Some(stripped),
+            Cow::Borrowed(_) => None,
+        };
+        if let Some(stripped) = stripped {
+            text = Cow::Owned(stripped);
+        }
+        if self.strip_quotes {
+            text = Cow::Owned(content::strip_quotes(&text));
+        }
+        if self.strip_urls {
+            text = Cow::Owned(content::strip_urls(&text));
+        }
+        if self.strip_mentions {
+            text = Cow::Owned(content::strip_mentions(&text, names));
+        }
+        if self.strip_emoji {
+            text = Cow::Owned(content::strip_emoji(&text));
+        }
+        // Last, so mentions and links are gone before `@` and digits map.
+        if self.normalize_leet {
+            let normalized = match leet::normalize(&text) {
+                Cow::Owned(normalized) => Some(normalized),
+                Cow::Borrowed(_) => None,
+            };
+            if let Some(normalized) = normalized {
+                text = Cow::Owned(normalized);
+            }
+        }
+        text
+    }
+
+    /// Words of `content`, without any over `max_len`: base64 blobs, stray
+    /// links and keyboard mashes would only add one-off entries.
+    fn split(&self, content: &str) -> Vec<String> {
+        let mut words = self.split_runs(content);
+        if self.max_len > 0 {
+            words.retain(|word| word.len() <= self.max_len || word.chars().count() <= self.max_len);
+        }
+        if self.drop_numbers {
+            words.retain(|word| !is_number(word));
+        }
+        if let Some(pattern) = &self.drop_pattern {
+            words.retain(|word| !pattern.is_match(word));
+        }
+        words
+    }
+
+    fn split_runs(&self, content: &str) -> Vec<String> {
+        match self.cjk {
+            Some(mode) if content.chars().any(cjk::is_cjk) => {
+                let mut tokens = Vec::new();
+                for (text, is_run) in cjk::runs(content) {
+                    if is_run {
+                        tokens.extend(
+                            cjk::segment(text, mode)
+                                .into_iter()
+                                .filter(|word| word.len() >= self.min_len),
+                        );
+                    } else {
+                        tokens.extend(self.split_words(text));
+                    }
+                }
+                tokens
+            }
+            _ => self.split_words(content),
+        }
+    }
+
+    fn split_words(&self, content: &str) -> Vec<String> {
+        if self.mode == TokenizerMode::Unicode {
+            return self.split_unicode(content);
+        }
+        if simd::is_ascii(content.as_bytes()) {
+            return self.tokenize_ascii(content.as_bytes());
+        }
+        content
+            .to_lowercase()
+            .split_whitespace()
+            .filter_map(|w| {
+                let clean_w: String = w.chars().filter(|c| self.keeps(*c)).collect();
+
+                if clean_w.len() >= self.min_len {
+                    Some(clean_w)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn keeps(&self, c: char) -> bool {
+        c.is_alphanumeric() || self.extra_chars.contains(&c)
+    }
+
+    /// Lowercased UAX #29 words. A hyphen or one of `extra_chars` directly
+    /// between two words joins them into one token.
+    fn split_unicode(&self, content: &str) -> Vec<String> {
+        let lowered = content.to_lowercase();
+        let mut tokens = Vec::new();
+        let mut word = String::new();
+        let mut joiner: Option<&str> = None;
+        let flush = |word: &mut String, tokens: &mut Vec<String>| {
+            if !word.is_empty() && word.len() >= self.min_len {
+                tokens.push(std::mem::take(word));
+            }
+            word.clear();
+        };
+        for segment in lowered.split_word_bounds() {
+            if segment.chars().any(char::is_alphanumeric) {
+                match joiner.take() {
+                    Some(joiner) => word.push_str(joiner),
+                    // Scripts without spaces, like Han, yield one segment
+                    // per character; each is a word of its own.
+                    None => flush(&mut word, &mut tokens),
+                }
+                word.push_str(segment);
+            } else if joiner.is_none() && !word.is_empty() && self.joins(segment) {
+                joiner = Some(segment);
+            } else {
+                flush(&mut word, &mut tokens);
+                joiner = None;
+            }
+        }
+        flush(&mut word, &mut tokens);
+        tokens
+    }
+
+    fn joins(&self, segment: &str) -> bool {
+        let mut chars = segment.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => HYPHENS.contains(&c) || self.extra_chars.contains(&c),
+            _ => false,
+        }
+    }
+
+    /// [`Tokenizer::tokenize`] for ASCII input, using the vectorized
+    /// lowercasing and classification in [`simd`]. Produces exactly the same
+    /// tokens.
+    fn tokenize_ascii(&self, bytes: &[u8]) -> Vec<String> {
+        let (mut lowered, mut classes) = (Vec::new(), Vec::new());
+        simd::lower_and_classify(bytes, &mut lowered, &mut classes);
+
+        let mut tokens = Vec::new();
+        let mut word = String::new();
+        let mut in_chunk = false;
+        for (&b, &class) in lowered.iter().zip(&classes) {
+            if class == simd::SPACE {
+                if in_chunk && word.len() >= self.min_len {
+                    tokens.push(std::mem::take(&mut word));
+                }
+                word.clear();
+                in_chunk = false;
+                continue;
+            }
+            in_chunk = true;
+            if class == simd::ALNUM || self.extra_chars.contains(&(b as char)) {
+                word.push(b as char);
+            }
+        }
+        if in_chunk && word.len() >= self.min_len {
+            tokens.push(word);
+        }
+        tokens
+    }
+}
+
+/// Digits only, allowing the `.` and `,` of `3.5` and `1,000` when
+/// `--token-chars` or `--tokenizer unicode` keeps them.
+fn is_number(word: &str) -> bool {
+    word.chars().any(char::is_numeric)
+        && word.chars().all(|c| c.is_numeric() || c == '.' || c == ',')
+}
+
+/// Tokenizes with the default character set.
+pub fn tokenize_content(content: &str, min_len: usize) -> Vec<String> {
+    Tokenizer {
+        min_len,
+        ..Tokenizer::default()
+    }
+    .tokenize(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_content() {
+        let content = "Hello, World! This is a test.";
+        let tokens = tokenize_content(content, 3);
+        assert_eq!(tokens, vec!["hello", "world", "this", "test"]);
+    }
+
+    #[test]
+    fn test_tokenize_content_with_numbers() {
+        let content = "Test123 with numbers 456!";
+        let tokens = tokenize_content(content, 3);
+        assert_eq!(tokens, vec!["test123", "with", "numbers", "456"]);
+    }
+
+    #[test]
+    fn test_tokenize_content_drop_numbers() {
+        let content = "Test123 with numbers 456! 3.5 ٤٥٦";
+        let tokenizer = Tokenizer {
+            drop_numbers: true,
+            ..Tokenizer::new(3, ".")
+        };
+        assert_eq!(
+            tokenizer.tokenize(content),
+            vec!["test123", "with", "numbers"]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_content_drop_tokens_matching() {
+        let content = "Test123 with numbers 456!";
+        let tokenizer = Tokenizer {
+            drop_pattern: Some(Regex::new(r"^\w+\d+$").unwrap()),
+            ..Tokenizer::new(3, "")
+        };
+        assert_eq!(tokenizer.tokenize(content), vec!["with", "numbers"]);
+    }
+
+    #[test]
+    fn test_tokenize_content_filters_short_words() {
+        let content = "a an the is to of";
+        let tokens = tokenize_content(content, 3);
+        assert_eq!(tokens, vec!["the"]);
+    }
+
+    #[test]
+    fn test_tokenize_content_min_length() {
+        let content = "a bb ccc dddd";
+        let tokens = tokenize_content(content, 2);
+        assert_eq!(tokens, vec!["bb", "ccc", "dddd"]);
+    }
+
+    #[test]
+    fn test_extra_token_chars() {
+        let tokenizer = Tokenizer::new(3, "'-_");
+        assert_eq!(
+            tokenizer.tokenize("Don't re-export snake_case, ok?"),
+            vec!["don't", "re-export", "snake_case"]
+        );
+        assert_eq!(
+            tokenizer.tokenize("C'était très-bien"),
+            vec!["c'était", "très-bien"]
+        );
+        assert_eq!(
+            Tokenizer::new(1, "·").tokenize("l·l caf·e"),
+            vec!["l·l", "caf·e"]
+        );
+    }
+
+    #[test]
+    fn test_max_word_length() {
+        let text = "ok aGVsbG8gd29ybGQgdGhpcyBpcyBiYXNlNjQ= fine";
+        assert_eq!(Tokenizer::new(2, "").tokenize(text).len(), 3);
+        let tokenizer = Tokenizer {
+            max_len: 20,
+            ..Tokenizer::new(2, "")
+        };
+        assert_eq!(tokenizer.tokenize(text), vec!["ok", "fine"]);
+        // Characters, not bytes.
+        assert_eq!(
+            Tokenizer {
+                max_len: 4,
+                ..Tokenizer::new(1, "")
+            }
+            .tokenize("café"),
+            vec!["café"]
+        );
+    }
+
+    #[test]
+    fn test_strip_quotes_before_tokenizing() {
+        let tokenizer = Tokenizer {
+            strip_quotes: true,
+            ..Tokenizer::new(3, "")
+        };
+        assert_eq!(
+            tokenizer.tokenize("> their words\nmine only"),
+            vec!["mine", "only"]
+        );
+    }
+
+    #[test]
+    fn test_strip_spans_before_tokenizing() {
+        let msg = Message {
+            content: "@Deleted User check https://github.com/a/b `x = 1` 🎉 gg".to_string(),
+            mentioned_user_name: Some("Deleted User".to_string()),
+            ..Message::default()
+        };
+        assert_eq!(
+            Tokenizer::new(2, "").tokenize_message(&msg),
+            vec!["deleted", "user", "check", "httpsgithubcomab", "gg"]
+        );
+        let tokenizer = Tokenizer {
+            strip_urls: true,
+            strip_mentions: true,
+            strip_code_blocks: true,
+            strip_emoji: true,
+            ..Tokenizer::new(2, "")
+        };
+        assert_eq!(tokenizer.tokenize_message(&msg), vec!["check", "gg"]);
+
+        let markdown = "__snake_case__ was ||the_butler||";
+        assert_eq!(
+            Tokenizer::new(2, "_|").tokenize(markdown),
+            vec!["snake_case", "was", "the_butler"]
+        );
+        let tokenizer = Tokenizer {
+            strip_spoilers: true,
+            ..Tokenizer::new(2, "_|")
+        };
+        assert_eq!(tokenizer.tokenize(markdown), vec!["snake_case", "was"]);
+    }
+
+    #[test]
+    fn test_unicode_word_boundaries() {
+        let tokenizer = Tokenizer {
+            mode: TokenizerMode::Unicode,
+            ..Tokenizer::new(2, "")
+        };
+        assert_eq!(
+            tokenizer.tokenize("Don't re-export it... ok,fine! v3.5 rock—paper"),
+            vec![
+                "don't",
+                "re-export",
+                "it",
+                "ok",
+                "fine",
+                "v3.5",
+                "rock",
+                "paper"
+            ]
+        );
+        assert_eq!(
+            tokenizer.tokenize("Привет, мир! «Ça va?» 日本語"),
+            vec!["привет", "мир", "ça", "va", "日", "本", "語"]
+        );
+        assert_eq!(
+            tokenizer.tokenize("trailing- -leading"),
+            vec!["trailing", "leading"]
+        );
+        let slashes = Tokenizer {
+            mode: TokenizerMode::Unicode,
+            ..Tokenizer::new(1, "/")
+        };
+        assert_eq!(slashes.tokenize("and/or x / y"), vec!["and/or", "x", "y"]);
+        assert_eq!("Unicode".parse(), Ok(TokenizerMode::Unicode));
+        assert!("icu".parse::<TokenizerMode>().is_err());
+    }
+
+    #[test]
+    fn test_cjk_runs_are_segmented() {
+        let tokenizer = Tokenizer {
+            cjk: Some(CjkMode::Bigram),
+            ..Tokenizer::new(3, "")
+        };
+        assert_eq!(
+            tokenizer.tokenize("New iPhone很好用, really"),
+            vec!["new", "iphone", "很好", "好用", "really"]
+        );
+        assert_eq!(
+            Tokenizer::new(3, "").tokenize("iPhone很好用"),
+            vec!["iphone很好用"]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_ascii_matches_unicode_path() {
+        let unicode = |content: &str, tokenizer: &Tokenizer| -> Vec<String> {
+            content
+                .to_lowercase()
+                .split_whitespace()
+                .map(|w| {
+                    w.chars()
+                        .filter(|c| tokenizer.keeps(*c))
+                        .collect::<String>()
+                })
+                .filter(|w| w.len() >= tokenizer.min_len)
+                .collect()
+        };
+        let samples = [
+            "Hello, World! It's 2024 -- yes?",
+            "tabs\tand\x0Bvertical\x0Cfeeds\r\nend",
+            "  leading and trailing  ",
+            "!!! ... ??? a",
+            "",
+            "MiXeD CaSe WoRdS that span more than sixteen bytes in total",
+        ];
+        for sample in samples {
+            for min_len in [0, 1, 3] {
+                for extra in ["", "'-"] {
+                    let tokenizer = Tokenizer::new(min_len, extra);
+                    assert_eq!(
+                        tokenizer.tokenize_ascii(sample.as_bytes()),
+                        unicode(sample, &tokenizer),
+                        "{:?} min_len {} extra {:?}",
+                        sample,
+                        min_len,
+                        extra
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! `--topics N`: latent Dirichlet allocation over the matched messages,
+//! grouping their words into N topics, each summarized by its top terms,
+//! with the share of every author's words that falls in each topic.
+//!
+//! Each unique message is one document of its counted words (after
+//! stopwords, `--stem` and `--lemmatize`). The model is fitted by collapsed
+//! Gibbs sampling with priors suited to short texts (α = 0.1, β = 0.01)
+//! and seeded by `--seed`, so the same input and seed give the same
+//! topics. Topics are numbered from 1 by their share of all words, largest
+//! first. Fitting takes time in proportion to words × topics ×
+//! `--topic-iterations`.
+
+use crate::AnalysisResult;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Default `--topic-iterations`.
+pub const DEFAULT_ITERATIONS: usize = 200;
+
+/// Most topics `--topics` accepts.
+pub const MAX_TOPICS: usize = 100;
+
+const ALPHA: f64 = 0.1;
+const BETA: f64 = 0.01;
+
+/// Terms listed per topic.
+const TOP_TERMS: usize = 10;
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Topic {
+    /// From 1, largest topic first.
+    pub topic: usize,
+    /// The topic's most probable words with their probability in it.
+    pub top_terms: Vec<(String, f64)>,
+    /// Share of all words assigned to the topic.
+    pub share: f64,
+}
+
+/// Parses a `--topics` count.
+pub fn parse_count(value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(n) if (2..=MAX_TOPICS).contains(&n) => Ok(n),
+        _ => Err(format!(
+            "`{}` is not a topic count from 2 to {}",
+            value, MAX_TOPICS
+        )),
+    }
+}
+
+/// SplitMix64, so fits are reproducible without a random number crate.
+struct Rng(u64);
+
+impl Rng {
+    fn next_f64(&mut self) -> f64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        ((z ^ (z >> 31)) >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        ((self.next_f64() * n as f64) as usize).min(n - 1)
+    }
+}
+
+struct Model {
+    topics: Vec<Topic>,
+    /// Per owner of the documents, the share of their words in each topic,
+    /// aligned with `topics`; empty for owners without words.
+    proportions: Vec<Vec<f64>>,
+}
+
+/// Fits `k` topics to `docs`, each tagged with the index of its owner.
+fn fit(
+    docs: &[(usize, &[String])],
+    owners: usize,
+    k: usize,
+    iterations: usize,
+    seed: u64,
+) -> Model {
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let mut vocabulary: Vec<&str> = Vec::new();
+    let docs: Vec<(usize, Vec<usize>)> = docs
+        .iter()
+        .map(|(owner, words)| {
+            let words = words
+                .iter()
+                .map(|word| {
+                    *ids.entry(word).or_insert_with(|| {
+                        vocabulary.push(word);
+                        vocabulary.len() - 1
+                    })
+                })
+                .collect();
+            (*owner, words)
+        })
+        .collect();
+    let v = vocabulary.len();
+
+    let mut rng = Rng(seed);
+    let mut doc_topic = vec![vec![0usize; k]; docs.len()];
+    let mut topic_word = vec![vec![0usize; v]; k];
+    let mut topic_total = vec![0usize; k];
+    let mut assignments: Vec<Vec<usize>> = Vec::with_capacity(docs.len());
+    for (d, (_, words)) in docs.iter().enumerate() {
+        let topics: Vec<usize> = words.iter().map(|_| rng.below(k)).collect();
+        for (&w, &t) in words.iter().zip(&topics) {
+            doc_topic[d][t] += 1;
+            topic_word[t][w] += 1;
+            topic_total[t] += 1;
+        }
+        assignments.push(topics);
+    }
+
+    let mut weights = vec![0.0; k];
+    for _ in 0..iterations {
+        for (d, (_, words)) in docs.iter().enumerate() {
+            for (i, &w) in words.iter().enumerate() {
+                let old = assignments[d][i];
+                doc_topic[d][old] -= 1;
+                topic_word[old][w] -= 1;
+                topic_total[old] -= 1;
+
+                let mut sum = 0.0;
+                for t in 0..k {
+                    sum += (doc_topic[d][t] as f64 + ALPHA) * (topic_word[t][w] as f64 + BETA)
+                        / (topic_total[t] as f64 + v as f64 * BETA);
+                    weights[t] = sum;
+                }
+                let draw = rng.next_f64() * sum;
+                let new = weights.iter().position(|&w| draw < w).unwrap_or(k - 1);
+
+                assignments[d][i] = new;
+                doc_topic[d][new] += 1;
+                topic_word[new][w] += 1;
+                topic_total[new] += 1;
+            }
+        }
+    }
+
+    // Number topics by size, largest first.
+    let mut order: Vec<usize> = (0..k).collect();
+    order.sort_by(|a, b| topic_total[*b].cmp(&topic_total[*a]).then(a.cmp(b)));
+    let tokens: usize = topic_total.iter().sum();
+    let topics = order
+        .iter()
+        .enumerate()
+        .map(|(rank, &t)| {
+            let denominator = topic_total[t] as f64 + v as f64 * BETA;
+            let mut terms: Vec<(usize, usize)> = topic_word[t]
+                .iter()
+                .enumerate()
+                .filter(|(_, &count)| count > 0)
+                .map(|(w, &count)| (w, count))
+                .collect();
+            terms.sort_by(|a, b| {
+                b.1.cmp(&a.1)
+                    .then_with(|| vocabulary[a.0].cmp(vocabulary[b.0]))
+            });
+            Topic {
+                topic: rank + 1,
+                top_terms: terms
+                    .into_iter()
+                    .take(TOP_TERMS)
+                    .map(|(w, count)| {
+                        (
+                            vocabulary[w].to_string(),
+                            (count as f64 + BETA) / denominator,
+                        )
+                    })
+                    .collect(),
+                share: topic_total[t] as f64 / tokens.max(1) as f64,
+            }
+        })
+        .collect();
+
+    let mut owner_topic = vec![vec![0usize; k]; owners];
+    for (d, (owner, _)) in docs.iter().enumerate() {
+        for t in 0..k {
+            owner_topic[*owner][t] += doc_topic[d][t];
+        }
+    }
+    let proportions = owner_topic
+        .into_iter()
+        .map(|counts| {
+            let total: usize = counts.iter().sum();
+            if total == 0 {
+                return Vec::new();
+            }
+            order
+                .iter()
+                .map(|&t| (counts[t] as f64 + ALPHA) / (total as f64 + k as f64 * ALPHA))
+                .collect()
+        })
+        .collect();
+    Model {
+        topics,
+        proportions,
+    }
+}
+
+/// Fits the topics over every author's documents, setting the result's
+/// `topics` and each author's `topic_proportions`.
+pub fn annotate(result: &mut AnalysisResult, k: usize, iterations: usize, seed: u64) {
+    let authors = &mut result.authors_analysis;
+    let docs: Vec<(usize, &[String])> = authors
+        .iter()
+        .enumerate()
+        .flat_map(|(i, author)| author.documents.iter().map(move |doc| (i, doc.as_slice())))
+        .collect();
+    if docs.is_empty() {
+        return;
+    }
+    let model = fit(&docs, authors.len(), k, iterations, seed);
+    for (author, proportions) in authors.iter_mut().zip(model.proportions) {
+        author.topic_proportions = proportions;
+        author.documents = Vec::new();
+    }
+    result.topics = model.topics;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_separates_unrelated_vocabularies() {
+        let games = doc("raid boss loot raid boss loot");
+        let food = doc("pizza pasta sauce pizza pasta sauce");
+        let docs: Vec<(usize, &[String])> = (0..10)
+            .flat_map(|_| [(0, games.as_slice()), (1, food.as_slice())])
+            .collect();
+        let model = fit(&docs, 2, 2, 100, 7);
+        assert_eq!(model.topics.len(), 2);
+        assert_eq!(model.topics[0].topic, 1);
+        assert!((model.topics.iter().map(|t| t.share).sum::<f64>() - 1.0).abs() < 1e-9);
+        let terms = |t: &Topic| -> Vec<String> {
+            let mut terms: Vec<String> = t.top_terms.iter().map(|(w, _)| w.clone()).collect();
+            terms.sort();
+            terms
+        };
+        let first = terms(&model.topics[0]);
+        assert!(first == ["boss", "loot", "raid"] || first == ["pasta", "pizza", "sauce"]);
+        // Each author lands almost wholly in one topic, a different one each.
+        let main = |p: &[f64]| (p[0] < p[1]) as usize;
+        assert!(model.proportions[0].iter().any(|&p| p > 0.9));
+        assert_ne!(main(&model.proportions[0]), main(&model.proportions[1]));
+        // Same seed, same topics.
+        assert_eq!(fit(&docs, 2, 2, 100, 7).topics, model.topics);
+        assert!(parse_count("1").is_err());
+        assert_eq!(parse_count("5"), Ok(5));
+    }
+}
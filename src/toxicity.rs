@@ -0,0 +1,180 @@
+//! `--flag-toxicity`: messages containing a profanity or insult term are
+//! flagged, and authors whose share of flagged messages reaches
+//! `--flag-threshold` are flagged themselves, so moderators get a summary
+//! with excerpts instead of hunting through word frequencies.
+//!
+//! The built-in list is the `--mask-profanity` list plus common insults;
+//! `--flag-terms` adds terms in the same format as `--mask-words`. Words
+//! are also checked after leetspeak and lookalike letters are mapped, so
+//! `sh1t` is flagged as `shit`.
+
+use crate::leet;
+use crate::message::Message;
+use crate::sanitize::{Terms, DEFAULT_TERMS};
+use crate::AuthorAnalysis;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::borrow::Cow;
+
+/// Built-in terms flagged on top of [`DEFAULT_TERMS`].
+const INSULTS: &[&str] = &[
+    "cuck*",
+    "dumbass*",
+    "idiot*",
+    "imbecile*",
+    "kys",
+    "loser*",
+    "moron*",
+    "retard*",
+    "scum*",
+    "stfu",
+    "twat*",
+    "whore*",
+];
+
+/// Default `--flag-threshold`.
+pub const DEFAULT_THRESHOLD: f64 = 0.1;
+
+/// Flagged messages quoted per author.
+const EXCERPTS: usize = 3;
+
+/// Characters quoted from each flagged message.
+const EXCERPT_CHARS: usize = 100;
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct FlaggedMessage {
+    pub message_id: String,
+    /// The start of the message on one line.
+    pub excerpt: String,
+    /// Terms found, lowercased, in order of appearance.
+    pub terms: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Flagger {
+    terms: Terms,
+    threshold: f64,
+}
+
+impl Flagger {
+    /// The built-in terms and those in `path`.
+    pub fn new(path: Option<&str>, threshold: f64) -> anyhow::Result<Self> {
+        let mut terms = Terms::default();
+        for term in DEFAULT_TERMS.iter().chain(INSULTS) {
+            terms.add(term);
+        }
+        if let Some(path) = path {
+            terms.add_file(path, "flag terms")?;
+        }
+        Ok(Flagger { terms, threshold })
+    }
+
+    /// The terms in `content`, each once.
+    pub fn terms_in(&self, content: &str) -> Vec<String> {
+        let mut found: Vec<String> = Vec::new();
+        let normalized = leet::normalize(content);
+        let texts = match &normalized {
+            Cow::Owned(text) => vec![content, text.as_str()],
+            Cow::Borrowed(_) => vec![content],
+        };
+        for text in texts {
+            for word in text.split(|c: char| !c.is_alphanumeric()) {
+                if word.is_empty() || !self.terms.matches(word) {
+                    continue;
+                }
+                let word = word.to_lowercase();
+                if !found.contains(&word) {
+                    found.push(word);
+                }
+            }
+        }
+        found
+    }
+}
+
+/// Flagged messages of one author.
+#[derive(Debug, Clone, Default)]
+pub struct FlagTally {
+    count: usize,
+    /// The oldest flagged messages, by snowflake.
+    excerpts: Vec<FlaggedMessage>,
+}
+
+impl FlagTally {
+    pub fn record(&mut self, msg: &Message, flagger: &Flagger) {
+        let terms = flagger.terms_in(&msg.content);
+        if terms.is_empty() {
+            return;
+        }
+        self.count += 1;
+        self.excerpts.push(FlaggedMessage {
+            message_id: msg.message_id.clone(),
+            excerpt: excerpt(&msg.content),
+            terms,
+        });
+        self.excerpts
+            .sort_by_key(|flagged| flagged.message_id.parse::<u64>().unwrap_or(u64::MAX));
+        self.excerpts.truncate(EXCERPTS);
+    }
+
+    /// Sets the author's flag fields, measuring the threshold against their
+    /// unique messages.
+    pub fn apply(self, author: &mut AuthorAnalysis, flagger: &Flagger) {
+        author.flagged = self.count > 0
+            && self.count as f64 >= flagger.threshold * author.unique_message_count as f64;
+        author.flagged_message_count = self.count;
+        author.flagged_excerpts = self.excerpts;
+    }
+}
+
+fn excerpt(content: &str) -> String {
+    let line = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_author_analysis, MessageCounts};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_flags_messages_and_authors() {
+        let flagger = Flagger::new(None, 0.5).unwrap();
+        assert_eq!(flagger.terms_in("what a MORON, shut up"), ["moron"]);
+        assert_eq!(flagger.terms_in("sh1t"), ["shit"]);
+        assert!(flagger.terms_in("dickens wrote this").is_empty());
+
+        let mut tally = FlagTally::default();
+        for (id, content) in [("3", "stfu idiot"), ("2", "hello"), ("1", "you idiot")] {
+            tally.record(
+                &Message {
+                    message_id: id.to_string(),
+                    content: content.to_string(),
+                    ..Message::default()
+                },
+                &flagger,
+            );
+        }
+        let counts = MessageCounts {
+            unique: 3,
+            ..MessageCounts::default()
+        };
+        let mut author = build_author_analysis(
+            "42".to_string(),
+            "alice".to_string(),
+            "Alice".to_string(),
+            counts,
+            HashMap::new(),
+        );
+        tally.apply(&mut author, &flagger);
+        assert!(author.flagged);
+        assert_eq!(author.flagged_message_count, 2);
+        assert_eq!(author.flagged_excerpts[0].message_id, "1");
+        assert_eq!(author.flagged_excerpts[1].terms, ["stfu", "idiot"]);
+        assert_eq!(excerpt(&"a ".repeat(80)).chars().count(), EXCERPT_CHARS + 1);
+    }
+}
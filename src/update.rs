@@ -0,0 +1,220 @@
+//! `self-update`: replaces the running binary with the latest GitHub release
+//! built for this platform, for installs without cargo.
+//!
+//! Every release carries a `SHA256SUMS` file next to its binaries. A
+//! download whose hash does not match its entry there, or that has no entry,
+//! is rejected before anything on disk is touched. This only catches a
+//! corrupted or truncated download: the sums come from the same release as
+//! the binary and are not signed, so anyone able to publish a release can
+//! publish matching sums too.
+
+use crate::paths;
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+const REPO: &str = "chrrivee/DiscordParserRs";
+const BINARY: &str = "deleted-user-analyzer";
+const CHECKSUMS: &str = "SHA256SUMS";
+/// Release binaries are far smaller; a larger download is not one.
+const MAX_DOWNLOAD_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// The release asset for this platform, e.g. `deleted-user-analyzer-x86_64-linux`.
+fn asset_name() -> String {
+    format!(
+        "{}-{}-{}{}",
+        BINARY,
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+/// `v1.2.3`, `1.2` or `1.2.3-rc.1` as comparable numbers. Pre-release and
+/// build suffixes are ignored.
+fn parse_version(tag: &str) -> Option<(u64, u64, u64)> {
+    let core = tag
+        .trim()
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some((major, minor, patch))
+}
+
+/// The hash listed for `name` in a `sha256sum`-style file.
+fn expected_hash(sums: &str, name: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (hash, file) = line.trim().split_once(char::is_whitespace)?;
+        let file = file.trim_start().trim_start_matches('*');
+        (file == name).then(|| hash.to_lowercase())
+    })
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn get(url: &str) -> anyhow::Result<Vec<u8>> {
+    let response = ureq::get(url)
+        .set("User-Agent", BINARY)
+        .call()
+        .with_context(|| format!("failed to fetch {}", url))?;
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_DOWNLOAD_BYTES + 1)
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("failed to download {}", url))?;
+    if bytes.len() as u64 > MAX_DOWNLOAD_BYTES {
+        bail!(
+            "{} is larger than {} MB",
+            url,
+            MAX_DOWNLOAD_BYTES / 1024 / 1024
+        );
+    }
+    Ok(bytes)
+}
+
+pub fn run(check_only: bool) -> anyhow::Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    let url = format!("https://api.github.com/repos/{}/releases/latest", REPO);
+    let release: Release =
+        serde_json::from_slice(&get(&url)?).context("failed to parse the latest release")?;
+    let latest = parse_version(&release.tag_name)
+        .with_context(|| format!("release tag `{}` is not a version", release.tag_name))?;
+    if latest <= parse_version(current).expect("the crate version is a version") {
+        println!("Already up to date ({}).", current);
+        return Ok(());
+    }
+    println!("Update available: {} -> {}", current, release.tag_name);
+    if check_only {
+        return Ok(());
+    }
+
+    let name = asset_name();
+    let find = |wanted: &str| release.assets.iter().find(|asset| asset.name == wanted);
+    let asset = find(&name).with_context(|| {
+        format!(
+            "release {} has no binary for this platform ({})",
+            release.tag_name, name
+        )
+    })?;
+    let sums = find(CHECKSUMS).with_context(|| {
+        format!(
+            "release {} has no {}; refusing to install an unverified binary",
+            release.tag_name, CHECKSUMS
+        )
+    })?;
+    let sums = String::from_utf8(get(&sums.browser_download_url)?)
+        .with_context(|| format!("{} is not text", CHECKSUMS))?;
+    let expected = expected_hash(&sums, &name)
+        .with_context(|| format!("{} has no entry for {}", CHECKSUMS, name))?;
+    let binary = get(&asset.browser_download_url)?;
+    let actual = sha256_hex(&binary);
+    if actual != expected {
+        bail!(
+            "checksum mismatch for {}: expected {}, got {}",
+            name,
+            expected,
+            actual
+        );
+    }
+
+    let exe = std::env::current_exe().context("failed to locate the running binary")?;
+    replace(&exe, &binary)?;
+    println!("Updated to {} ({})", release.tag_name, exe.display());
+    Ok(())
+}
+
+/// Writes the new binary next to `exe` with the same permissions and swaps
+/// it in. A running executable cannot be overwritten on Windows but can be
+/// renamed, so the old one is moved aside first and put back if the swap
+/// fails.
+fn replace(exe: &Path, binary: &[u8]) -> anyhow::Result<()> {
+    let new = paths::with_suffix(exe, ".new");
+    fs::write(&new, binary).with_context(|| format!("failed to write {}", new.display()))?;
+    fs::set_permissions(&new, fs::metadata(exe)?.permissions())?;
+
+    let old = paths::with_suffix(exe, ".old");
+    let _ = fs::remove_file(&old);
+    fs::rename(exe, &old).with_context(|| format!("failed to move {} aside", exe.display()))?;
+    if let Err(err) = fs::rename(&new, exe) {
+        let _ = fs::rename(&old, exe);
+        return Err(err).with_context(|| format!("failed to replace {}", exe.display()));
+    }
+    // Still locked on Windows while the old binary runs; the next update
+    // removes it.
+    let _ = fs::remove_file(&old);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("v1.2.3"), Some((1, 2, 3)));
+        assert_eq!(parse_version("0.10"), Some((0, 10, 0)));
+        assert_eq!(parse_version("2.0.0-rc.1"), Some((2, 0, 0)));
+        assert_eq!(parse_version("nightly"), None);
+        assert!(parse_version("v0.10.0") > parse_version("0.9.12"));
+    }
+
+    #[test]
+    fn test_expected_hash_and_digest() {
+        let sums = "ABC123  deleted-user-analyzer-x86_64-linux\n\
+                    def456 *deleted-user-analyzer-x86_64-windows.exe\n";
+        assert_eq!(
+            expected_hash(sums, "deleted-user-analyzer-x86_64-linux").as_deref(),
+            Some("abc123")
+        );
+        assert_eq!(
+            expected_hash(sums, "deleted-user-analyzer-x86_64-windows.exe").as_deref(),
+            Some("def456")
+        );
+        assert_eq!(
+            expected_hash(sums, "deleted-user-analyzer-aarch64-macos"),
+            None
+        );
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_replace_swaps_binary() {
+        let dir = std::env::temp_dir().join(format!("dua-update-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("tool");
+        fs::write(&exe, "old").unwrap();
+        replace(&exe, b"new").unwrap();
+        assert_eq!(fs::read(&exe).unwrap(), b"new");
+        assert!(!paths::with_suffix(&exe, ".old").exists());
+        assert!(!paths::with_suffix(&exe, ".new").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,120 @@
+//! `--usage-log`: one JSON line per analysis run appended to a local file,
+//! for teams that want to report how much they use the tool.
+//!
+//! Nothing is ever sent anywhere. Records hold sizes, counts and timings
+//! only; no paths, names or message content, so the file can be shared
+//! as is.
+
+use crate::input;
+use crate::paths;
+use crate::AnalysisResult;
+use anyhow::Context;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Bumped when a field changes meaning, so aggregations can tell records
+/// apart.
+const SCHEMA: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UsageRecord {
+    pub schema: u32,
+    pub version: String,
+    pub started_at: String,
+    pub duration_ms: u64,
+    /// `memory` or `bounded` (`--max-memory`).
+    pub mode: String,
+    pub input_files: usize,
+    pub input_bytes: u64,
+    /// Whether the analysis finished; failed runs have no counts.
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analyzed_messages: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authors: Option<usize>,
+}
+
+impl UsageRecord {
+    pub fn new(
+        inputs: &[PathBuf],
+        bounded: bool,
+        started_at: DateTime<Utc>,
+        duration: Duration,
+        result: Option<&AnalysisResult>,
+    ) -> Self {
+        // Inputs that cannot be listed any more are left out of the sizes
+        // rather than failing a run that already happened.
+        let files = input::expand_inputs(inputs).unwrap_or_default();
+        UsageRecord {
+            schema: SCHEMA,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: started_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            duration_ms: duration.as_millis() as u64,
+            mode: if bounded { "bounded" } else { "memory" }.to_string(),
+            input_files: files.len(),
+            input_bytes: files
+                .iter()
+                .filter_map(|path| fs::metadata(path).ok())
+                .map(|meta| meta.len())
+                .sum(),
+            ok: result.is_some(),
+            analyzed_messages: result.map(|r| r.total_messages),
+            authors: result.map(|r| r.unique_authors),
+        }
+    }
+
+    /// Appends the record as one line, creating the file if needed.
+    pub fn append(&self, path: &Path) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(self)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(paths::extended(path))
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("failed to write usage log {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_append_as_lines() {
+        let dir = std::env::temp_dir().join(format!("dua-usage-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let export = dir.join("export.json");
+        fs::write(&export, "[]").unwrap();
+        let log = dir.join("usage.ndjson");
+
+        let started = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let failed = UsageRecord::new(
+            std::slice::from_ref(&export),
+            true,
+            started,
+            Duration::from_millis(1500),
+            None,
+        );
+        failed.append(&log).unwrap();
+        failed.append(&log).unwrap();
+
+        let text = fs::read_to_string(&log).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(!lines[0].contains("analyzed_messages"));
+        let parsed: UsageRecord = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(parsed, failed);
+        assert_eq!(parsed.started_at, "2023-11-14T22:13:20Z");
+        assert_eq!((parsed.input_files, parsed.input_bytes), (1, 2));
+        assert_eq!(
+            (parsed.mode.as_str(), parsed.duration_ms),
+            ("bounded", 1500)
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
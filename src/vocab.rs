@@ -0,0 +1,74 @@
+//! `--max-author-vocab`: a ceiling on the distinct words counted per author,
+//! so one author pasting huge unique blobs (hashes, base64, key mashing)
+//! cannot take most of the memory.
+//!
+//! While counting, a map may grow to half again the cap before the rarest
+//! words are evicted, which keeps eviction cheap; the final map holds at
+//! most the cap. Evicted words that come back start counting from one, so
+//! rare words' counts are lower bounds. Frequent words are unaffected.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VocabCap(pub usize);
+
+impl VocabCap {
+    /// Evicts down to the cap once the map is well past it. Returns the
+    /// number of words evicted.
+    pub fn trim(&self, words: &mut HashMap<String, usize>) -> usize {
+        if words.len() > self.0 + (self.0 / 2).max(1) {
+            self.evict(words)
+        } else {
+            0
+        }
+    }
+
+    /// Evicts the rarest words until at most the cap remain, alphabetically
+    /// last first among equal counts. Returns the number of words evicted.
+    pub fn evict(&self, words: &mut HashMap<String, usize>) -> usize {
+        let excess = words.len().saturating_sub(self.0);
+        if excess == 0 {
+            return 0;
+        }
+        let mut ranked: Vec<(usize, &String)> =
+            words.iter().map(|(word, &count)| (count, word)).collect();
+        let rarest = |a: &(usize, &String), b: &(usize, &String)| a.0.cmp(&b.0).then(b.1.cmp(a.1));
+        ranked.select_nth_unstable_by(excess - 1, rarest);
+        let evicted: Vec<String> = ranked[..excess]
+            .iter()
+            .map(|(_, word)| (*word).clone())
+            .collect();
+        for word in &evicted {
+            words.remove(word);
+        }
+        excess
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_rarest_words() {
+        let cap = VocabCap(2);
+        let mut words: HashMap<String, usize> = [("common", 9), ("blob1", 1), ("usual", 4)]
+            .into_iter()
+            .map(|(word, count)| (word.to_string(), count))
+            .collect();
+        assert_eq!(cap.trim(&mut words), 0);
+        words.insert("blob2".to_string(), 1);
+        words.insert("blob3".to_string(), 1);
+        assert_eq!(cap.trim(&mut words), 3);
+        let mut kept: Vec<&str> = words.keys().map(String::as_str).collect();
+        kept.sort_unstable();
+        assert_eq!(kept, ["common", "usual"]);
+
+        let mut ties: HashMap<String, usize> = ["b", "a", "c"]
+            .into_iter()
+            .map(|word| (word.to_string(), 1))
+            .collect();
+        assert_eq!(VocabCap(1).evict(&mut ties), 2);
+        assert!(ties.contains_key("a"));
+    }
+}
@@ -0,0 +1,154 @@
+//! Non-fatal data-quality issues collected during a run.
+
+use crate::conflicts::AuthorConflict;
+use crate::input::Loader;
+use crate::message::FallbackStats;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How many example identifiers to keep per warning kind.
+const MAX_EXAMPLES: usize = 5;
+
+#[derive(
+    Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    EncodingFallback,
+    ReplacedBytes,
+    SkippedRecord,
+    MissingAuthorName,
+    MissingAuthorNickname,
+    MissingTimestamp,
+    MissingContent,
+    ReconstructedTimestamp,
+    SuspiciousTimestamp,
+    AuthorIdConflict,
+    VocabularyCapped,
+}
+
+impl WarningKind {
+    fn describe(self) -> &'static str {
+        match self {
+            WarningKind::EncodingFallback => "input files were not clean UTF-8",
+            WarningKind::ReplacedBytes => "undecodable bytes were replaced with U+FFFD",
+            WarningKind::SkippedRecord => "unparseable NDJSON lines were skipped",
+            WarningKind::MissingAuthorName => "records had no author_name",
+            WarningKind::MissingAuthorNickname => "records had no author_nickname",
+            WarningKind::MissingTimestamp => "records had no timestamp",
+            WarningKind::MissingContent => "records had no content and were treated as empty",
+            WarningKind::ReconstructedTimestamp => {
+                "timestamps were reconstructed from snowflake message IDs"
+            }
+            WarningKind::SuspiciousTimestamp => {
+                "timestamps disagree with their message ID or lie in the future"
+            }
+            WarningKind::AuthorIdConflict => "author IDs appeared under conflicting names",
+            WarningKind::VocabularyCapped => {
+                "authors exceeded --max-author-vocab and had their rarest words dropped"
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+    pub count: usize,
+    /// A few message IDs, file paths or author IDs illustrating the issue.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<String>,
+}
+
+/// Collector that aggregates warnings by kind.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Warnings(BTreeMap<WarningKind, Warning>);
+
+impl Warnings {
+    pub fn add(&mut self, kind: WarningKind, example: impl Into<String>) {
+        let warning = self.entry(kind);
+        warning.count += 1;
+        if warning.examples.len() < MAX_EXAMPLES {
+            warning.examples.push(example.into());
+        }
+    }
+
+    pub fn add_count(&mut self, kind: WarningKind, count: usize) {
+        if count > 0 {
+            self.entry(kind).count += count;
+        }
+    }
+
+    fn entry(&mut self, kind: WarningKind) -> &mut Warning {
+        self.0.entry(kind).or_insert_with(|| Warning {
+            kind,
+            message: kind.describe().to_string(),
+            count: 0,
+            examples: Vec::new(),
+        })
+    }
+
+    /// Folds the load-time statistics kept elsewhere into the collector.
+    pub fn record_load(&mut self, loader: &Loader, fallbacks: &FallbackStats) {
+        for (path, encoding) in &loader.decoded_files {
+            self.add(
+                WarningKind::EncodingFallback,
+                format!("{} ({})", path, encoding),
+            );
+        }
+        self.add_count(WarningKind::ReplacedBytes, loader.replaced_bytes);
+        for record in &loader.skipped_records {
+            self.add(WarningKind::SkippedRecord, record.clone());
+        }
+        self.add_count(WarningKind::MissingAuthorName, fallbacks.author_name);
+        self.add_count(
+            WarningKind::MissingAuthorNickname,
+            fallbacks.author_nickname,
+        );
+        self.add_count(WarningKind::MissingTimestamp, fallbacks.timestamp);
+        self.add_count(WarningKind::MissingContent, fallbacks.content);
+    }
+
+    pub fn record_conflicts(&mut self, conflicts: &[AuthorConflict]) {
+        for conflict in conflicts {
+            self.add(WarningKind::AuthorIdConflict, conflict.author_id.clone());
+        }
+    }
+
+    pub fn into_vec(self) -> Vec<Warning> {
+        self.0.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warnings_aggregate_by_kind() {
+        let mut warnings = Warnings::default();
+        for i in 0..8 {
+            warnings.add(WarningKind::SuspiciousTimestamp, i.to_string());
+        }
+        warnings.add_count(WarningKind::MissingTimestamp, 0);
+        warnings.add_count(WarningKind::ReplacedBytes, 12);
+
+        let list = warnings.into_vec();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].kind, WarningKind::ReplacedBytes);
+        assert_eq!(list[0].count, 12);
+        assert_eq!(list[1].count, 8);
+        assert_eq!(list[1].examples.len(), MAX_EXAMPLES);
+    }
+
+    #[test]
+    fn test_warning_serializes_kind_as_snake_case() {
+        let mut warnings = Warnings::default();
+        warnings.add(WarningKind::AuthorIdConflict, "42");
+        let json = serde_json::to_value(warnings.into_vec()).unwrap();
+        assert_eq!(json[0]["kind"], "author_id_conflict");
+        assert_eq!(json[0]["examples"][0], "42");
+    }
+}
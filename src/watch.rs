@@ -0,0 +1,209 @@
+//! `--watch`: keep the analysis up to date while the inputs grow.
+//!
+//! Lines appended to NDJSON files are ingested incrementally. Any other
+//! change (a rewritten JSON array, a truncated or deleted file) triggers a
+//! full reload, since earlier contributions cannot be subtracted.
+
+use crate::checkpoint::RunState;
+use crate::input::{self, Loader};
+use crate::paths;
+use crate::pipeline::Pipeline;
+use crate::report::display_results;
+use crate::{cooccurrence, explain, pivot, timeline};
+use crate::{summarize_state, write_output, Args};
+use notify::{RecursiveMode, Watcher};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
+
+/// How long to wait for further events before re-running the analysis.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+struct TrackedFile {
+    ndjson: bool,
+    len: u64,
+    modified: Option<SystemTime>,
+    /// For NDJSON: byte offset and line count consumed so far.
+    offset: u64,
+    lines: usize,
+}
+
+struct Session<'a> {
+    pipeline: &'a Pipeline,
+    state: RunState,
+    loader: Loader,
+    files: BTreeMap<PathBuf, TrackedFile>,
+}
+
+pub fn run(args: &Args, pipeline: &Pipeline) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for path in &args.input {
+        watcher.watch(&paths::extended(path), RecursiveMode::Recursive)?;
+    }
+
+    let mut session = Session::load(args, pipeline)?;
+    session.publish(args, true)?;
+    println!("Watching for changes (Ctrl-C to stop)...");
+
+    loop {
+        // The event contents do not matter: every tracked file is re-checked.
+        rx.recv()??;
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+        if session.update(args)? {
+            session.publish(args, false)?;
+        }
+    }
+}
+
+impl<'a> Session<'a> {
+    fn load(args: &Args, pipeline: &'a Pipeline) -> anyhow::Result<Self> {
+        let mut session = Session {
+            pipeline,
+            state: RunState::new(args),
+            loader: Loader::new(args.strict),
+            files: BTreeMap::new(),
+        };
+        for path in watched_files(args)? {
+            session.ingest_file(&path)?;
+        }
+        session.check_strict()?;
+        Ok(session)
+    }
+
+    fn ingest_file(&mut self, path: &Path) -> anyhow::Result<()> {
+        let meta = fs::metadata(path)?;
+        let mut head = Vec::with_capacity(1024);
+        fs::File::open(path)?.take(1024).read_to_end(&mut head)?;
+        let ndjson = input::is_ndjson(&String::from_utf8_lossy(&head));
+
+        let mut tracked = TrackedFile {
+            ndjson,
+            len: meta.len(),
+            modified: meta.modified().ok(),
+            offset: 0,
+            lines: 0,
+        };
+        let raw_msgs = if ndjson {
+            let tail = self.loader.read_ndjson_tail(path, 0, 0)?;
+            tracked.offset = tail.offset;
+            tracked.lines = tail.lines;
+            tail.records
+        } else {
+            self.loader.read_raw_messages(path)?
+        };
+        self.pipeline
+            .ingest(&mut self.state, &path.display().to_string(), raw_msgs);
+        self.files.insert(path.to_path_buf(), tracked);
+        Ok(())
+    }
+
+    /// Re-checks every input. Returns whether anything changed.
+    fn update(&mut self, args: &Args) -> anyhow::Result<bool> {
+        let current = watched_files(args)?;
+        if self.files.keys().any(|path| !current.contains(path)) {
+            *self = Session::load(args, self.pipeline)?;
+            return Ok(true);
+        }
+
+        let mut changed = false;
+        for path in current {
+            let Some(tracked) = self.files.get_mut(&path) else {
+                self.ingest_file(&path)?;
+                changed = true;
+                continue;
+            };
+            let meta = fs::metadata(&path)?;
+            let modified = meta.modified().ok();
+            if meta.len() == tracked.len && modified == tracked.modified {
+                continue;
+            }
+            if !tracked.ndjson || meta.len() < tracked.offset {
+                *self = Session::load(args, self.pipeline)?;
+                return Ok(true);
+            }
+
+            tracked.len = meta.len();
+            tracked.modified = modified;
+            let tail = self
+                .loader
+                .read_ndjson_tail(&path, tracked.offset, tracked.lines)?;
+            tracked.offset = tail.offset;
+            tracked.lines += tail.lines;
+            if !tail.records.is_empty() {
+                self.pipeline
+                    .ingest(&mut self.state, &path.display().to_string(), tail.records);
+                changed = true;
+            }
+        }
+        self.check_strict()?;
+        Ok(changed)
+    }
+
+    fn check_strict(&self) -> anyhow::Result<()> {
+        if !self.loader.unknown_fields.is_empty() {
+            anyhow::bail!("{}", self.loader.unknown_fields);
+        }
+        Ok(())
+    }
+
+    fn publish(&self, args: &Args, initial: bool) -> anyhow::Result<()> {
+        let mut result = summarize_state(args, self.pipeline, self.state.clone(), &self.loader);
+        self.pipeline.sanitizer.apply(&mut result);
+        let locale = args.locale.unwrap_or_default();
+        if initial || args.verbose {
+            display_results(&result, args.verbose, &locale);
+        } else {
+            println!(
+                "[{}] Updated: {} messages to {} from {} authors",
+                chrono::Local::now().format("%H:%M:%S"),
+                locale.count(result.total_messages),
+                self.pipeline.target.label(),
+                locale.count(result.unique_authors)
+            );
+        }
+        if let Some(output_path) = &args.output {
+            write_output(&mut result, output_path, &self.pipeline.output)?;
+        }
+        if let Some(timeline_path) = &args.timeline {
+            timeline::write(&result.timeline, timeline_path)?;
+        }
+        if let Some(pivot_path) = &args.pivot {
+            pivot::write(&result.pivot, pivot_path)?;
+        }
+        if let Some(path) = &args.explain_matches {
+            explain::write(&result.explanations, path)?;
+        }
+        if let Some(path) = &args.cooccurrence {
+            cooccurrence::write(&result.cooccurrence, path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Expanded inputs, minus the files this run writes so rewriting them does
+/// not count as new input when they live inside a watched directory.
+fn watched_files(args: &Args) -> anyhow::Result<Vec<PathBuf>> {
+    let written: Vec<_> = [
+        args.output.as_deref(),
+        args.timeline.as_deref(),
+        args.pivot.as_deref(),
+        args.explain_matches.as_deref(),
+        args.cooccurrence.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|path| fs::canonicalize(path).ok())
+    .collect();
+    Ok(input::expand_inputs(&args.input)?
+        .into_iter()
+        .filter(|path| {
+            fs::canonicalize(path)
+                .ok()
+                .is_none_or(|path| !written.contains(&path))
+        })
+        .collect())
+}
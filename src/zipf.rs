@@ -0,0 +1,99 @@
+//! `--rank-frequency`: the global vocabulary as a rank-frequency table
+//! with its fitted Zipf exponent, for corpus studies.
+//!
+//! Words are ranked by descending count, ties alphabetically, each with a
+//! rank of its own. The exponent `s` of `count ∝ rank^-s` comes from a
+//! least-squares fit of `ln count` on `ln rank` over every rank; natural
+//! language usually lands near 1.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct RankFrequency {
+    pub ranks: Vec<RankedWord>,
+    /// `None` when fewer than two words were counted, or every word was
+    /// counted equally often.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zipf_exponent: Option<f64>,
+    /// How well the fit explains the log counts, from 0 to 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r_squared: Option<f64>,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct RankedWord {
+    pub rank: usize,
+    pub word: String,
+    pub count: usize,
+}
+
+pub fn rank_frequency(frequency: &BTreeMap<String, usize>) -> RankFrequency {
+    let mut words: Vec<(&String, usize)> = frequency.iter().map(|(w, &c)| (w, c)).collect();
+    // Stable, so ties stay alphabetical.
+    words.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    let ranks: Vec<RankedWord> = words
+        .into_iter()
+        .enumerate()
+        .map(|(i, (word, count))| RankedWord {
+            rank: i + 1,
+            word: word.clone(),
+            count,
+        })
+        .collect();
+    let fit = fit(&ranks);
+    RankFrequency {
+        ranks,
+        zipf_exponent: fit.map(|(exponent, _)| exponent),
+        r_squared: fit.map(|(_, r_squared)| r_squared),
+    }
+}
+
+/// The exponent and R² of the log-log least-squares line.
+fn fit(ranks: &[RankedWord]) -> Option<(f64, f64)> {
+    if ranks.len() < 2 {
+        return None;
+    }
+    let points: Vec<(f64, f64)> = ranks
+        .iter()
+        .map(|r| ((r.rank as f64).ln(), (r.count as f64).ln()))
+        .collect();
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let syy: f64 = points.iter().map(|p| (p.1 - mean_y).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    if syy == 0.0 {
+        return None;
+    }
+    let slope = sxy / sxx;
+    Some((-slope, sxy * sxy / (sxx * syy)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ideal_zipf_distribution() {
+        // 60 / rank: an exponent of exactly 1.
+        let frequency: BTreeMap<String, usize> =
+            [("a", 60), ("b", 30), ("c", 20), ("d", 15), ("e", 12)]
+                .into_iter()
+                .map(|(w, c)| (w.to_string(), c))
+                .collect();
+        let table = rank_frequency(&frequency);
+        assert_eq!(table.ranks[1].word, "b");
+        assert_eq!(table.ranks[4].rank, 5);
+        assert!((table.zipf_exponent.unwrap() - 1.0).abs() < 1e-9);
+        assert!((table.r_squared.unwrap() - 1.0).abs() < 1e-9);
+
+        let flat = BTreeMap::from([("x".to_string(), 2), ("y".to_string(), 2)]);
+        let table = rank_frequency(&flat);
+        assert_eq!(table.ranks[0].word, "x");
+        assert_eq!(table.zipf_exponent, None);
+        assert_eq!(rank_frequency(&BTreeMap::new()).zipf_exponent, None);
+    }
+}